@@ -7,7 +7,9 @@ static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
 use web_sys::HtmlTextAreaElement;
 
-use maspace::maspace_to_tex;
+use maspace::diagnostic::Severity;
+use maspace::highlight::{highlight, Class};
+use maspace::{convert, ConvertOptions};
 
 #[wasm_bindgen(inline_js = r#"
 import "https://cdn.jsdelivr.net/npm/mathjax@3.2.2/es5/tex-svg-full.js"
@@ -23,13 +25,55 @@ extern "C" {
     async fn tex2svg(input: &str) -> Result<JsValue, JsValue>;
 }
 
-async fn maspace2svg(tex: &str) -> Result<(String, Html), String> {
-    let tex = maspace_to_tex(tex).map_err(|x| format!("maspace error: {:?}", x))?;
+/// Renders `input` even with errors, which are returned with the TeX and the SVG.
+async fn maspace2svg(input: &str) -> Result<(String, Html, Vec<String>), String> {
+    let options = ConvertOptions {
+        recover: true,
+        ..Default::default()
+    };
+    let conversion = convert(input, &options);
+    let messages = conversion
+        .diagnostics
+        .iter()
+        .map(|x| match x.severity {
+            Severity::Error => format!("maspace error: {}", x.message),
+            Severity::Warning => format!("maspace warning: {}", x.message),
+        })
+        .collect::<Vec<_>>();
+    let tex = conversion.tex.ok_or_else(|| messages.join("\n"))?;
     let html = tex2svg(&tex)
         .await
         .map(|x| Html::from_html_unchecked(AttrValue::from(x.as_string().unwrap_or_default())))
         .map_err(|x| x.as_string().unwrap_or_default())?;
-    Ok((tex, html))
+    Ok((tex, html, messages))
+}
+
+/// Background colours of binary operators by the number of spaces they bind with.
+const ORDER_COLORS: [&str; 4] = ["#fff3b0", "#c8f0f0", "#f0d0f0", "#d0dcff"];
+
+/// `input` with each classified range coloured, to show under the textarea.
+fn highlighted(input: &str) -> Html {
+    let mut spans = vec![];
+    let mut end = 0;
+    for x in highlight(input) {
+        let style = match x.class {
+            Class::Symbol => continue,
+            Class::StringLiteral => "color: green;".to_string(),
+            Class::Accent => "color: purple;".to_string(),
+            Class::Op | Class::Open | Class::Close => "font-weight: bold;".to_string(),
+            Class::BinOp(_, order) => {
+                format!("background: {};", ORDER_COLORS[order % ORDER_COLORS.len()])
+            }
+            Class::UnicodeSub | Class::UnicodeSup => "color: teal;".to_string(),
+        };
+        spans.push(html! { {input[end..x.range.start].to_string()} });
+        spans.push(html! { <span style={style}>{input[x.range.clone()].to_string()}</span> });
+        end = x.range.end;
+    }
+    spans.push(html! { {input[end..].to_string()} });
+    // a final line break would not take up a line without something after it
+    spans.push(html! { {" "} });
+    html! { <pre aria-hidden="true">{for spans}</pre> }
 }
 
 #[function_component]
@@ -51,9 +95,9 @@ fn App() -> Html {
     };
     html! {
         <main>
-            <div>
+            <div class="editor">
+                {highlighted(&value)}
                 <textarea
-                    style="width: 100%; box-sizing: border-box;"
                     aria-label="Input"
                     value={value.to_string()}
                     oninput={on_input}/>
@@ -77,11 +121,14 @@ fn App() -> Html {
                                 </div>
                             }
                         },
-                        (Some((tex, html)), None) => {
+                        (Some((tex, html, messages)), None) => {
                             html!{
                                 <>
                                     <div>{"generated LaTeX code: "}{(*tex).clone()}</div>
                                     {(*html).clone()}
+                                    <div style="color: red; white-space: pre-line;">
+                                        {messages.join("\n")}
+                                    </div>
                                 </>
                             }
                         },
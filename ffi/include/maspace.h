@@ -0,0 +1,63 @@
+/* C ABI of maspace, implemented in src/ffi.rs. Panics are caught and reported as failures. */
+
+#ifndef MASPACE_H
+#define MASPACE_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define MASPACE_SYNTAX_MASPACE 0
+#define MASPACE_SYNTAX_ASCIIMATH 1
+
+#define MASPACE_SEVERITY_ERROR 0
+#define MASPACE_SEVERITY_WARNING 1
+
+typedef struct MaspaceOptions MaspaceOptions;
+typedef struct MaspaceResult MaspaceResult;
+
+/* A diagnostic borrowed from a MaspaceResult. Offsets count bytes of the input. */
+typedef struct MaspaceDiagnostic {
+    int severity;
+    const char *message;
+    size_t start;
+    size_t end;
+} MaspaceDiagnostic;
+
+/* Returns the version of this library as a static string. */
+const char *maspace_version(void);
+
+/* Creates options for maSpace input, or returns NULL on an internal error.
+ * Free with maspace_options_free. */
+MaspaceOptions *maspace_options_new(void);
+
+/* Sets the input syntax to one of MASPACE_SYNTAX_*.
+ * Returns 0, or -1 for an unknown syntax or an internal error. */
+int maspace_options_set_syntax(MaspaceOptions *options, int syntax);
+
+void maspace_options_free(MaspaceOptions *options);
+
+/* Converts len bytes of UTF-8 at input to TeX. options may be NULL for the defaults.
+ * Never returns NULL: an internal error is an error diagnostic over the whole input.
+ * Free with maspace_result_free. */
+MaspaceResult *maspace_convert(const char *input, size_t len, const MaspaceOptions *options);
+
+/* Returns the NUL-terminated TeX owned by result, or NULL if the conversion failed.
+ * Writes its length to len unless len is NULL. */
+const char *maspace_result_tex(const MaspaceResult *result, size_t *len);
+
+size_t maspace_result_diagnostic_count(const MaspaceResult *result);
+
+/* Fills out with the diagnostic at index, whose message is owned by result.
+ * Returns 0, or -1 if index is out of range. */
+int maspace_result_diagnostic(const MaspaceResult *result, size_t index, MaspaceDiagnostic *out);
+
+void maspace_result_free(MaspaceResult *result);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
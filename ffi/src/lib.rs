@@ -0,0 +1,5 @@
+//! The shared and static libraries exporting the C ABI in `maspace::ffi`.
+//!
+//! Link against `libmaspace_ffi` and include `include/maspace.h`.
+
+pub use maspace::ffi::*;
@@ -0,0 +1,118 @@
+//! Documents open in the editor and the maSpace formulas in them.
+
+use std::ops::Range;
+
+use lsp_types::Position;
+use maspace::markdown::math_spans;
+
+pub struct Document {
+    text: String,
+    /// Byte offset of the start of every line.
+    line_starts: Vec<usize>,
+    markdown: bool,
+    formulas: Vec<Range<usize>>,
+}
+
+impl Document {
+    /// Reads every non-blank line as a formula, or only the math of a Markdown document.
+    pub fn new(text: String, markdown: bool) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        let formulas = match markdown {
+            true => math_spans(&text).into_iter().map(|x| x.range).collect(),
+            false => lines(&text)
+                .filter(|x| !text[x.clone()].trim().is_empty())
+                .collect(),
+        };
+        Self {
+            text,
+            line_starts,
+            markdown,
+            formulas,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_markdown(&self) -> bool {
+        self.markdown
+    }
+
+    /// Byte ranges of the formulas, in order.
+    pub fn formulas(&self) -> &[Range<usize>] {
+        &self.formulas
+    }
+
+    /// The formula containing the byte offset `offset`, or ending there.
+    pub fn formula_at(&self, offset: usize) -> Option<Range<usize>> {
+        self.formulas
+            .iter()
+            .find(|x| x.start <= offset && offset <= x.end)
+            .cloned()
+    }
+
+    /// The position of the byte offset `offset`, counting UTF-16 code units in its line.
+    pub fn position(&self, offset: usize) -> Position {
+        let line = self.line_starts.partition_point(|x| *x <= offset) - 1;
+        let start = self.line_starts[line];
+        Position::new(
+            line as u32,
+            self.text[start..offset].encode_utf16().count() as u32,
+        )
+    }
+
+    /// The byte offset of `position`, clamped to its line.
+    pub fn offset(&self, position: Position) -> usize {
+        let Some(&start) = self.line_starts.get(position.line as usize) else {
+            return self.text.len();
+        };
+        let mut utf16 = 0;
+        for (i, c) in self.text[start..].char_indices() {
+            if utf16 >= position.character as usize || c == '\n' {
+                return start + i;
+            }
+            utf16 += c.len_utf16();
+        }
+        self.text.len()
+    }
+
+    pub fn range(&self, range: Range<usize>) -> lsp_types::Range {
+        lsp_types::Range::new(self.position(range.start), self.position(range.end))
+    }
+}
+
+/// Byte ranges of the lines of `text` without their line breaks.
+fn lines(text: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut start = 0;
+    text.split_inclusive('\n').map(move |line| {
+        let range = start..start + line.trim_end_matches(['\n', '\r']).len();
+        start += line.len();
+        range
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formulas() {
+        let text = "a+b /c\n\nα^2\r\n";
+        let document = Document::new(text.to_string(), false);
+        assert_eq!(document.formulas(), [0..6, 8..12]);
+        let document = Document::new("$x$ `$a$`\n```maspace\nα^2\n```\n".to_string(), true);
+        assert_eq!(document.formulas(), [1..2, 21..25]);
+    }
+
+    #[test]
+    fn test_position() {
+        let document = Document::new("a\n𝐚α b".to_string(), false);
+        assert_eq!(document.position(8), Position::new(1, 3));
+        assert_eq!(document.offset(Position::new(1, 3)), 8);
+        assert_eq!(document.offset(Position::new(0, 9)), 1);
+        assert_eq!(document.offset(Position::new(5, 0)), 10);
+    }
+}
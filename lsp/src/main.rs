@@ -0,0 +1,410 @@
+//! `maspace-lsp`: a language server for maSpace over stdio.
+//!
+//! Every non-blank line of a document is a formula, except in Markdown documents
+//! where only `$..$`, `$$..$$` and fenced `maspace` blocks are.
+
+mod document;
+
+use document::Document;
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+    PublishDiagnostics,
+};
+use lsp_types::request::{Completion, HoverRequest, Request as _, SemanticTokensFullRequest};
+use lsp_types::{
+    CompletionItem, CompletionOptions, CompletionParams, CompletionResponse, CompletionTextEdit,
+    DiagnosticSeverity, Hover, HoverContents, HoverParams, HoverProviderCapability, MarkupContent,
+    MarkupKind, PublishDiagnosticsParams, SemanticToken, SemanticTokenType, SemanticTokens,
+    SemanticTokensFullOptions, SemanticTokensLegend, SemanticTokensOptions, SemanticTokensParams,
+    SemanticTokensResult, SemanticTokensServerCapabilities, ServerCapabilities,
+    TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Url,
+};
+use maspace::diagnostic::Severity;
+use maspace::explain::explain;
+use maspace::highlight::{highlight, BinOp, Class};
+use maspace::{convert, maspace_completions, ConvertOptions};
+
+/// Semantic token types, indexed by the `token_type` of the tokens sent.
+const TOKEN_TYPES: [SemanticTokenType; 4] = [
+    // `/` and `_/`
+    SemanticTokenType::OPERATOR,
+    // `_`, `^`, `__`, `^^` and unicode scripts
+    SemanticTokenType::KEYWORD,
+    // unary operators such as `√`
+    SemanticTokenType::FUNCTION,
+    // string literals such as `"sin"`
+    SemanticTokenType::STRING,
+];
+
+fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec!["<".to_string(), "`".to_string()]),
+            ..Default::default()
+        }),
+        semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(
+            SemanticTokensOptions {
+                legend: SemanticTokensLegend {
+                    token_types: TOKEN_TYPES.to_vec(),
+                    token_modifiers: vec![],
+                },
+                full: Some(SemanticTokensFullOptions::Bool(true)),
+                ..Default::default()
+            },
+        )),
+        ..Default::default()
+    }
+}
+
+fn diagnostics(document: &Document) -> Vec<lsp_types::Diagnostic> {
+    let mut result = vec![];
+    for formula in document.formulas() {
+        let conversion = convert(
+            &document.text()[formula.clone()],
+            &ConvertOptions::default(),
+        );
+        for x in conversion.diagnostics {
+            let range = formula.start + x.range.start..formula.start + x.range.end;
+            result.push(lsp_types::Diagnostic {
+                range: document.range(range),
+                severity: Some(match x.severity {
+                    Severity::Error => DiagnosticSeverity::ERROR,
+                    Severity::Warning => DiagnosticSeverity::WARNING,
+                }),
+                source: Some("maspace".to_string()),
+                message: x.message,
+                ..Default::default()
+            });
+        }
+    }
+    result
+}
+
+/// Shows the TeX and the grouping diagram of the formula under the cursor.
+fn hover(document: &Document, params: HoverParams) -> Option<Hover> {
+    let offset = document.offset(params.text_document_position_params.position);
+    let formula = document.formula_at(offset)?;
+    let input = &document.text()[formula.clone()];
+    let conversion = convert(input, &ConvertOptions::default());
+    let value = match (&conversion.tex, explain(input)) {
+        (Some(tex), Ok(diagram)) => format!("```latex\n{}\n```\n```text\n{}```", tex, diagram),
+        _ => conversion
+            .errors()
+            .map(|x| x.message.clone())
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+    };
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        }),
+        range: Some(document.range(formula)),
+    })
+}
+
+fn completion(document: &Document, params: CompletionParams) -> Option<CompletionResponse> {
+    let offset = document.offset(params.text_document_position.position);
+    let formula = document.formula_at(offset)?;
+    let input = &document.text()[formula.start..offset];
+    let items = maspace_completions(input, input.len())
+        .into_iter()
+        .map(|x| CompletionItem {
+            label: x.text.clone(),
+            detail: Some(x.tex),
+            text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                range: document.range(formula.start + x.start..offset),
+                new_text: x.text,
+            })),
+            ..Default::default()
+        })
+        .collect();
+    Some(CompletionResponse::Array(items))
+}
+
+fn semantic_tokens(document: &Document) -> SemanticTokens {
+    let mut ranges = vec![];
+    for formula in document.formulas() {
+        let text = &document.text()[formula.clone()];
+        for x in highlight(text) {
+            let token_type = match x.class {
+                Class::BinOp(BinOp::Frac | BinOp::Root, _) => 0,
+                Class::BinOp(BinOp::Cat, _) => continue,
+                Class::BinOp(..) | Class::UnicodeSub | Class::UnicodeSup => 1,
+                Class::Op => 2,
+                Class::StringLiteral => 3,
+                _ => continue,
+            };
+            // binary operators include the spaces around them
+            let part = &text[x.range.clone()];
+            let start = formula.start + x.range.start + part.len() - part.trim_start().len();
+            ranges.push((start..start + part.trim().len(), token_type));
+        }
+    }
+    let mut data = vec![];
+    let mut previous = lsp_types::Position::new(0, 0);
+    for (range, token_type) in ranges {
+        let range = document.range(range);
+        data.push(SemanticToken {
+            delta_line: range.start.line - previous.line,
+            delta_start: match range.start.line == previous.line {
+                true => range.start.character - previous.character,
+                false => range.start.character,
+            },
+            length: range.end.character - range.start.character,
+            token_type,
+            token_modifiers_bitset: 0,
+        });
+        previous = range.start;
+    }
+    SemanticTokens {
+        result_id: None,
+        data,
+    }
+}
+
+fn handle_request(documents: &HashMap<Url, Document>, request: Request) -> Response {
+    let id = request.id.clone();
+    let result = match request.method.as_str() {
+        HoverRequest::METHOD => {
+            request
+                .extract::<HoverParams>(HoverRequest::METHOD)
+                .map(|(_, params)| {
+                    let uri = &params.text_document_position_params.text_document.uri;
+                    let hover = documents.get(uri).and_then(|x| hover(x, params));
+                    serde_json::to_value(hover)
+                })
+        }
+        Completion::METHOD => {
+            request
+                .extract::<CompletionParams>(Completion::METHOD)
+                .map(|(_, params)| {
+                    let uri = &params.text_document_position.text_document.uri;
+                    let completion = documents.get(uri).and_then(|x| completion(x, params));
+                    serde_json::to_value(completion)
+                })
+        }
+        SemanticTokensFullRequest::METHOD => request
+            .extract::<SemanticTokensParams>(SemanticTokensFullRequest::METHOD)
+            .map(|(_, params)| {
+                let tokens = documents
+                    .get(&params.text_document.uri)
+                    .map(|x| SemanticTokensResult::Tokens(semantic_tokens(x)));
+                serde_json::to_value(tokens)
+            }),
+        method => {
+            let message = format!("unknown method {}", method);
+            return Response::new_err(id, ErrorCode::MethodNotFound as i32, message);
+        }
+    };
+    match result {
+        Ok(Ok(value)) => Response {
+            id,
+            result: Some(value),
+            error: None,
+        },
+        Ok(Err(e)) => Response::new_err(id, ErrorCode::InternalError as i32, e.to_string()),
+        Err(e) => Response::new_err(id, ErrorCode::InvalidParams as i32, e.to_string()),
+    }
+}
+
+/// Updates `documents`, returning the document to publish diagnostics for.
+fn handle_notification(
+    documents: &mut HashMap<Url, Document>,
+    notification: Notification,
+) -> Result<Option<Url>> {
+    Ok(match notification.method.as_str() {
+        DidOpenTextDocument::METHOD => {
+            let params = notification
+                .extract::<lsp_types::DidOpenTextDocumentParams>(DidOpenTextDocument::METHOD)?;
+            let item = params.text_document;
+            let markdown = item.language_id == "markdown";
+            documents.insert(item.uri.clone(), Document::new(item.text, markdown));
+            Some(item.uri)
+        }
+        DidChangeTextDocument::METHOD => {
+            let params = notification
+                .extract::<lsp_types::DidChangeTextDocumentParams>(DidChangeTextDocument::METHOD)?;
+            let uri = params.text_document.uri;
+            // with full sync the last change is the whole text
+            match (
+                documents.get_mut(&uri),
+                params.content_changes.into_iter().last(),
+            ) {
+                (Some(document), Some(change)) => {
+                    *document = Document::new(change.text, document.is_markdown());
+                    Some(uri)
+                }
+                _ => None,
+            }
+        }
+        DidCloseTextDocument::METHOD => {
+            let params = notification
+                .extract::<lsp_types::DidCloseTextDocumentParams>(DidCloseTextDocument::METHOD)?;
+            documents.remove(&params.text_document.uri);
+            None
+        }
+        _ => None,
+    })
+}
+
+fn run(connection: &Connection) -> Result<()> {
+    connection.initialize(serde_json::to_value(capabilities())?)?;
+    let mut documents = HashMap::new();
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+                let response = handle_request(&documents, request);
+                connection.sender.send(Message::Response(response))?;
+            }
+            Message::Notification(notification) => {
+                let Some(uri) = handle_notification(&mut documents, notification)? else {
+                    continue;
+                };
+                let params = PublishDiagnosticsParams {
+                    diagnostics: documents.get(&uri).map(diagnostics).unwrap_or_default(),
+                    uri,
+                    version: None,
+                };
+                let notification =
+                    Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+                connection
+                    .sender
+                    .send(Message::Notification(notification))?;
+            }
+            Message::Response(_) => {}
+        }
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+    run(&connection)?;
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::{json, Value};
+
+    /// Plays the editor against a server running on another thread.
+    struct Client {
+        connection: Connection,
+        id: i32,
+    }
+
+    impl Client {
+        fn request(&mut self, method: &str, params: Value) -> Value {
+            self.id += 1;
+            let request = Request::new(self.id.into(), method.to_string(), params);
+            self.connection.sender.send(request.into()).unwrap();
+            loop {
+                match self.connection.receiver.recv().unwrap() {
+                    Message::Response(x) if x.id == self.id.into() => {
+                        return x.result.unwrap_or(Value::Null)
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        fn notify(&self, method: &str, params: Value) {
+            let notification = Notification::new(method.to_string(), params);
+            self.connection.sender.send(notification.into()).unwrap();
+        }
+
+        fn diagnostics(&self) -> Value {
+            loop {
+                match self.connection.receiver.recv().unwrap() {
+                    Message::Notification(x) if x.method == PublishDiagnostics::METHOD => {
+                        return x.params["diagnostics"].clone()
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_session() {
+        let (server, connection) = Connection::memory();
+        let thread = std::thread::spawn(move || run(&server).unwrap());
+        let mut client = Client { connection, id: 0 };
+        let x = client.request("initialize", json!({"capabilities": {}}));
+        assert_eq!(x["capabilities"]["hoverProvider"], true);
+        client.notify("initialized", json!({}));
+
+        let uri = "file:///notes.md";
+        let text = "Let $a^b^c$ and $$x_i /2$$.\n";
+        client.notify(
+            "textDocument/didOpen",
+            json!({"textDocument": {"uri": uri, "languageId": "markdown", "version": 1, "text": text}}),
+        );
+        let diagnostics = client.diagnostics();
+        assert_eq!(diagnostics.as_array().unwrap().len(), 1);
+        assert_eq!(
+            diagnostics[0]["range"],
+            json!({"start": {"line": 0, "character": 8}, "end": {"line": 0, "character": 9}})
+        );
+
+        let position = |character| json!({"textDocument": {"uri": uri}, "position": {"line": 0, "character": character}});
+        let x = client.request("textDocument/hover", position(19));
+        let value = x["contents"]["value"].as_str().unwrap();
+        assert!(
+            value.starts_with("```latex\n\\frac{x_{i}}{2}\n```"),
+            "{}",
+            value
+        );
+        assert!(value.contains("stack1"), "{}", value);
+        assert_eq!(
+            client.request("textDocument/hover", position(2)),
+            Value::Null
+        );
+
+        client.notify(
+            "textDocument/didChange",
+            json!({"textDocument": {"uri": uri, "version": 2}, "contentChanges": [{"text": "$<alp$\n"}]}),
+        );
+        assert_eq!(client.diagnostics().as_array().unwrap().len(), 1);
+        let x = client.request("textDocument/completion", position(5));
+        assert_eq!(x[0]["label"], "alpha");
+        assert_eq!(
+            x[0]["textEdit"]["range"],
+            json!({"start": {"line": 0, "character": 2}, "end": {"line": 0, "character": 5}})
+        );
+
+        client.notify(
+            "textDocument/didChange",
+            json!({"textDocument": {"uri": uri, "version": 3}, "contentChanges": [{"text": "$√x_i$ $a /b$\n"}]}),
+        );
+        assert_eq!(client.diagnostics(), json!([]));
+        let x = client.request(
+            "textDocument/semanticTokens/full",
+            json!({"textDocument": {"uri": uri}}),
+        );
+        // √ at 1, _ at 3 and / at 10
+        assert_eq!(
+            x["data"],
+            json!([0, 1, 1, 2, 0, 0, 2, 1, 1, 0, 0, 7, 1, 0, 0])
+        );
+
+        client.request("shutdown", Value::Null);
+        client.notify("exit", Value::Null);
+        thread.join().unwrap();
+    }
+}
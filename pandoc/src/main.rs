@@ -0,0 +1,153 @@
+//! `maspace-pandoc`: a Pandoc JSON filter converting maSpace math to TeX.
+//!
+//! `pandoc notes.md --filter maspace-pandoc -o notes.html` converts every `Math` element,
+//! and turns every code block of class `maspace` into a paragraph of display math per line.
+//! Formulas that fail are reported on stderr and replaced with a visible marker,
+//! so one mistake does not stop the whole document.
+
+use std::io::{self, Read, Write};
+
+use anyhow::{Context, Result};
+use maspace::{convert, ConvertOptions};
+use serde_json::{json, Map, Value};
+
+/// The class of the code blocks of maSpace and of the markers of failed formulas.
+const CLASS: &str = "maspace";
+const ERROR_CLASS: &str = "maspace-error";
+
+/// An element `{"t": tag, "c": content}` of the Pandoc AST.
+fn element(tag: &str, content: Value) -> Value {
+    json!({"t": tag, "c": content})
+}
+
+fn tag(value: &Map<String, Value>) -> Option<&str> {
+    value.get("t").and_then(Value::as_str)
+}
+
+/// Converts the maSpace of a `Math` element `[math_type, text]`, or returns a marker replacing it.
+fn convert_math(content: &mut Value, errors: &mut Vec<String>) -> Option<Value> {
+    let input = content.get(1)?.as_str()?.to_string();
+    let conversion = convert(&input, &ConvertOptions::default());
+    if let Some(tex) = conversion.tex {
+        content[1] = Value::String(tex);
+        return None;
+    }
+    let message = conversion
+        .errors()
+        .next()
+        .map_or("conversion failed".to_string(), |x| x.message.clone());
+    let mut report = format!("maspace-pandoc: error in `{}`: {}", input, message);
+    if let Some(fix) = conversion.fixes.first() {
+        report.push_str(&format!("\n  help: write `{}`", fix.input));
+    }
+    errors.push(report);
+    // the input as code with the message after it, which every output format shows
+    let attr = json!(["", [ERROR_CLASS], [["title", message]]]);
+    let inlines = json!([
+        element("Code", json!([["", [], []], input])),
+        json!({"t": "Space"}),
+        element(
+            "Emph",
+            json!([element(
+                "Str",
+                json!(format!("(maspace error: {})", message))
+            )])
+        ),
+    ]);
+    Some(element("Span", json!([attr, inlines])))
+}
+
+/// The paragraphs of display math replacing a `CodeBlock` element of class `maspace`.
+fn code_block_math(value: &Value) -> Option<Vec<Value>> {
+    if value.as_object().and_then(tag) != Some("CodeBlock") {
+        return None;
+    }
+    // the content is `[attr, text]` with `attr` being `[id, classes, attributes]`
+    let content = value.get("c")?;
+    let classes = content.get(0)?.get(1)?.as_array()?;
+    if !classes.iter().any(|x| x == CLASS) {
+        return None;
+    }
+    let text = content.get(1)?.as_str()?;
+    Some(
+        text.lines()
+            .filter(|x| !x.trim().is_empty())
+            .map(|x| {
+                let math = element("Math", json!([{"t": "DisplayMath"}, x.trim()]));
+                element("Para", json!([math]))
+            })
+            .collect(),
+    )
+}
+
+/// Converts the maSpace math in `value`, collecting a report for every formula that fails.
+fn filter(value: &mut Value, errors: &mut Vec<String>) {
+    match value {
+        Value::Array(items) => {
+            *items = std::mem::take(items)
+                .into_iter()
+                .flat_map(|x| code_block_math(&x).unwrap_or_else(|| vec![x]))
+                .collect();
+            for x in items {
+                filter(x, errors);
+            }
+        }
+        Value::Object(object) => {
+            if tag(object) == Some("Math") {
+                let marker = object
+                    .get_mut("c")
+                    .and_then(|content| convert_math(content, errors));
+                if let Some(marker) = marker {
+                    *value = marker;
+                }
+                return;
+            }
+            for x in object.values_mut() {
+                filter(x, errors);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn main() -> Result<()> {
+    let mut input = String::new();
+    io::stdin()
+        .read_to_string(&mut input)
+        .context("failed to read stdin")?;
+    let mut document: Value = serde_json::from_str(&input).context("stdin is not Pandoc JSON")?;
+    let mut errors = vec![];
+    filter(&mut document, &mut errors);
+    for x in errors {
+        eprintln!("{}", x);
+    }
+    let mut out = io::stdout().lock();
+    serde_json::to_writer(&mut out, &document)?;
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> Value {
+        let path = format!("{}/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_filter() {
+        let mut document = fixture("input.json");
+        let mut errors = vec![];
+        filter(&mut document, &mut errors);
+        assert_eq!(document, fixture("output.json"));
+        assert_eq!(errors.len(), 1);
+        assert!(
+            errors[0].starts_with("maspace-pandoc: error in `a^b^c`: Double exponent")
+                && errors[0].ends_with("  help: write `a ^b^c`"),
+            "{}",
+            errors[0]
+        );
+    }
+}
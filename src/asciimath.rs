@@ -0,0 +1,631 @@
+use super::expr::{Frac, Inter, Math, Root, Simple, Stack};
+use super::printer::{spell_symbol, Notation};
+use super::tex::{add_prime, apply_command, symbol_of_char, symbol_of_command};
+
+use std::fmt::Display;
+
+#[derive(Debug, Clone)]
+pub struct AsciiMathParseError {
+    description: String,
+    position: usize,
+}
+
+impl AsciiMathParseError {
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Byte offset in the input where the error was found.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl Display for AsciiMathParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.description)?;
+        writeln!(f, "at byte {}", self.position)?;
+        Ok(())
+    }
+}
+
+impl std::error::Error for AsciiMathParseError {}
+
+const SYMBOLS: &[(&str, &str)] = &[
+    // greek
+    ("alpha", r"\alpha"),
+    ("beta", r"\beta"),
+    ("gamma", r"\gamma"),
+    ("Gamma", r"\Gamma"),
+    ("delta", r"\delta"),
+    ("Delta", r"\Delta"),
+    ("epsilon", r"\epsilon"),
+    ("varepsilon", r"\varepsilon"),
+    ("zeta", r"\zeta"),
+    ("eta", r"\eta"),
+    ("theta", r"\theta"),
+    ("Theta", r"\Theta"),
+    ("vartheta", r"\vartheta"),
+    ("iota", r"\iota"),
+    ("kappa", r"\kappa"),
+    ("lambda", r"\lambda"),
+    ("Lambda", r"\Lambda"),
+    ("mu", r"\mu"),
+    ("nu", r"\nu"),
+    ("xi", r"\xi"),
+    ("Xi", r"\Xi"),
+    ("pi", r"\pi"),
+    ("Pi", r"\Pi"),
+    ("rho", r"\rho"),
+    ("sigma", r"\sigma"),
+    ("Sigma", r"\Sigma"),
+    ("tau", r"\tau"),
+    ("upsilon", r"\upsilon"),
+    ("phi", r"\phi"),
+    ("Phi", r"\Phi"),
+    ("varphi", r"\varphi"),
+    ("chi", r"\chi"),
+    ("psi", r"\psi"),
+    ("Psi", r"\Psi"),
+    ("omega", r"\omega"),
+    ("Omega", r"\Omega"),
+    // operators
+    ("*", r"\cdot"),
+    ("**", r"\ast"),
+    ("***", r"\star"),
+    ("//", "/"),
+    ("\\\\", r"\backslash"),
+    ("setminus", r"\setminus"),
+    ("xx", r"\times"),
+    ("|><", r"\ltimes"),
+    ("><|", r"\rtimes"),
+    ("|><|", r"\bowtie"),
+    ("-:", r"\div"),
+    ("@", r"\circ"),
+    ("o+", r"\oplus"),
+    ("ox", r"\otimes"),
+    ("o.", r"\odot"),
+    ("sum", r"\sum"),
+    ("prod", r"\prod"),
+    ("^^", r"\wedge"),
+    ("^^^", r"\bigwedge"),
+    ("vv", r"\vee"),
+    ("vvv", r"\bigvee"),
+    ("nn", r"\cap"),
+    ("nnn", r"\bigcap"),
+    ("uu", r"\cup"),
+    ("uuu", r"\bigcup"),
+    // relations
+    ("!=", r"\ne"),
+    ("<", r"\lt"),
+    (">", r"\gt"),
+    ("<=", r"\le"),
+    (">=", r"\ge"),
+    ("-<", r"\prec"),
+    (">-", r"\succ"),
+    ("-<=", r"\preceq"),
+    (">-=", r"\succeq"),
+    ("in", r"\in"),
+    ("!in", r"\notin"),
+    ("sub", r"\subset"),
+    ("sup", r"\supset"),
+    ("sube", r"\subseteq"),
+    ("supe", r"\supseteq"),
+    ("-=", r"\equiv"),
+    ("~=", r"\cong"),
+    ("~~", r"\approx"),
+    ("prop", r"\propto"),
+    // logic
+    ("not", r"\neg"),
+    ("=>", r"\implies"),
+    ("<=>", r"\iff"),
+    ("AA", r"\forall"),
+    ("EE", r"\exists"),
+    ("_|_", r"\bot"),
+    ("TT", r"\top"),
+    ("|--", r"\vdash"),
+    ("|==", r"\models"),
+    // misc
+    ("int", r"\int"),
+    ("oint", r"\oint"),
+    ("del", r"\partial"),
+    ("grad", r"\nabla"),
+    ("+-", r"\pm"),
+    ("O/", r"\emptyset"),
+    ("oo", r"\infty"),
+    ("infty", r"\infty"),
+    ("aleph", r"\aleph"),
+    (":.", r"\therefore"),
+    ("/_", r"\angle"),
+    ("...", r"\ldots"),
+    ("cdots", r"\cdots"),
+    ("vdots", r"\vdots"),
+    ("ddots", r"\ddots"),
+    ("quad", r"\quad"),
+    ("qquad", r"\qquad"),
+    ("diamond", r"\diamond"),
+    ("square", r"\square"),
+    ("|__", r"\lfloor"),
+    ("__|", r"\rfloor"),
+    ("|~", r"\lceil"),
+    ("~|", r"\rceil"),
+    ("CC", r"\mathbb{C}"),
+    ("NN", r"\mathbb{N}"),
+    ("QQ", r"\mathbb{Q}"),
+    ("RR", r"\mathbb{R}"),
+    ("ZZ", r"\mathbb{Z}"),
+    // functions
+    ("sin", r"\sin"),
+    ("cos", r"\cos"),
+    ("tan", r"\tan"),
+    ("sec", r"\sec"),
+    ("csc", r"\csc"),
+    ("cot", r"\cot"),
+    ("sinh", r"\sinh"),
+    ("cosh", r"\cosh"),
+    ("tanh", r"\tanh"),
+    ("coth", r"\coth"),
+    ("arcsin", r"\arcsin"),
+    ("arccos", r"\arccos"),
+    ("arctan", r"\arctan"),
+    ("exp", r"\exp"),
+    ("log", r"\log"),
+    ("ln", r"\ln"),
+    ("det", r"\det"),
+    ("dim", r"\dim"),
+    ("gcd", r"\gcd"),
+    ("lim", r"\lim"),
+    ("min", r"\min"),
+    ("max", r"\max"),
+    ("mod", r"\bmod"),
+    // arrows
+    ("uarr", r"\uparrow"),
+    ("darr", r"\downarrow"),
+    ("rarr", r"\rightarrow"),
+    ("->", r"\to"),
+    (">->", r"\rightarrowtail"),
+    ("->>", r"\twoheadrightarrow"),
+    ("|->", r"\mapsto"),
+    ("larr", r"\leftarrow"),
+    ("harr", r"\leftrightarrow"),
+    ("rArr", r"\Rightarrow"),
+    ("lArr", r"\Leftarrow"),
+    ("hArr", r"\Leftrightarrow"),
+];
+
+const OPENS: &[(&str, &str)] = &[
+    ("(", "("),
+    ("[", "["),
+    ("{", r"\{"),
+    ("(:", r"\langle"),
+    ("<<", r"\langle"),
+    ("{:", ""),
+];
+
+const CLOSES: &[(&str, &str)] = &[
+    (")", ")"),
+    ("]", "]"),
+    ("}", r"\}"),
+    (":)", r"\rangle"),
+    (">>", r"\rangle"),
+    (":}", ""),
+];
+
+/// Commands taking one argument. Accents and fonts become symbols where possible.
+const UNARY: &[(&str, &str)] = &[
+    ("sqrt", r"\sqrt"),
+    ("hat", r"\hat"),
+    ("bar", r"\overline"),
+    ("ul", r"\underline"),
+    ("vec", r"\vec"),
+    ("tilde", r"\tilde"),
+    ("dot", r"\dot"),
+    ("ddot", r"\ddot"),
+    ("obrace", r"\overbrace"),
+    ("ubrace", r"\underbrace"),
+    ("cancel", r"\cancel"),
+    ("bb", r"\mathbf"),
+    ("bbb", r"\mathbb"),
+    ("cc", r"\mathcal"),
+    ("tt", r"\mathtt"),
+    ("fr", r"\mathfrak"),
+    ("sf", r"\mathsf"),
+    ("rm", r"\mathrm"),
+];
+
+/// Commands enclosing their argument in a pair of delimiters.
+const FENCES: &[(&str, &str, &str)] = &[
+    ("abs", r"\lvert", r"\rvert"),
+    ("norm", r"\lVert", r"\rVert"),
+    ("floor", r"\lfloor", r"\rfloor"),
+    ("ceil", r"\lceil", r"\rceil"),
+];
+
+const BINARY: &[&str] = &["frac", "root", "stackrel", "overset", "underset"];
+
+/// Parses an AsciiMath formula into the `expr` AST.
+///
+/// Brackets around the arguments of `/`, `_`, `^` and commands such as `sqrt` are removed
+/// as in AsciiMath: `(a+b)/c` is `\frac{a+b}{c}` and `sum_(i=1)^n` is `\sum_{i=1}^{n}`.
+pub fn parse_asciimath(input: &str) -> Result<Math, AsciiMathParseError> {
+    let mut parser = Parser {
+        input,
+        tokens: lex(input)?,
+        pos: 0,
+    };
+    let math = parser.expression(false)?;
+    if math.0.is_empty() {
+        return Err(AsciiMathParseError {
+            description: "Input is empty".to_string(),
+            position: 0,
+        });
+    }
+    Ok(math)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Tok {
+    Symbol(&'static str),
+    Number(String),
+    Char(char),
+    Open(&'static str),
+    Close(&'static str),
+    Unary(&'static str),
+    Fence(&'static str, &'static str),
+    Binary(&'static str),
+    Text(&'static str, String),
+    Sub,
+    Sup,
+    Frac,
+    Prime,
+}
+
+fn lex(input: &str) -> Result<Vec<(usize, Tok)>, AsciiMathParseError> {
+    let keywords = SYMBOLS
+        .iter()
+        .map(|(x, tex)| (*x, Tok::Symbol(tex)))
+        .chain(OPENS.iter().map(|(x, tex)| (*x, Tok::Open(tex))))
+        .chain(CLOSES.iter().map(|(x, tex)| (*x, Tok::Close(tex))))
+        .chain(UNARY.iter().map(|(x, tex)| (*x, Tok::Unary(tex))))
+        .chain(FENCES.iter().map(|(x, l, r)| (*x, Tok::Fence(l, r))))
+        .chain(BINARY.iter().map(|x| (*x, Tok::Binary(x))))
+        .chain([
+            ("_", Tok::Sub),
+            ("^", Tok::Sup),
+            ("/", Tok::Frac),
+            ("'", Tok::Prime),
+        ])
+        .collect::<Vec<_>>();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while let Some(c) = input[i..].chars().next() {
+        let rest = &input[i..];
+        if c.is_whitespace() {
+            i += c.len_utf8();
+            continue;
+        }
+        let unclosed = |what: &str| AsciiMathParseError {
+            description: format!("Unclosed {}", what),
+            position: i,
+        };
+        if let Some(x) = rest.strip_prefix('"') {
+            let end = x.find('"').ok_or_else(|| unclosed("\""))?;
+            tokens.push((i, Tok::Text(r"\mathrm", x[..end].to_string())));
+            i += end + 2;
+            continue;
+        }
+        if let Some(x) = ["text(", "mbox("].iter().find_map(|x| rest.strip_prefix(x)) {
+            let end = x.find(')').ok_or_else(|| unclosed("\"(\""))?;
+            tokens.push((i, Tok::Text(r"\text", x[..end].to_string())));
+            i += 5 + end + 1;
+            continue;
+        }
+        let number = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        if number > 0 && rest.starts_with(|c: char| c.is_ascii_digit()) {
+            tokens.push((i, Tok::Number(rest[..number].to_string())));
+            i += number;
+            continue;
+        }
+        match keywords
+            .iter()
+            .filter(|(x, _)| rest.starts_with(x))
+            .max_by_key(|(x, _)| x.len())
+        {
+            Some((x, tok)) => {
+                tokens.push((i, tok.clone()));
+                i += x.len();
+            }
+            None => {
+                tokens.push((i, Tok::Char(c)));
+                i += c.len_utf8();
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<(usize, Tok)>,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Tok> {
+        self.tokens.get(self.pos).map(|(_, x)| x)
+    }
+
+    fn next(&mut self) -> Option<Tok> {
+        let tok = self.peek().cloned();
+        self.pos += 1;
+        tok
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .map_or(self.input.len(), |(i, _)| *i)
+    }
+
+    fn error<T>(&self, position: usize, description: &str) -> Result<T, AsciiMathParseError> {
+        Err(AsciiMathParseError {
+            description: description.to_string(),
+            position,
+        })
+    }
+
+    fn expression(&mut self, nested: bool) -> Result<Math, AsciiMathParseError> {
+        let mut roots = vec![];
+        loop {
+            match self.peek() {
+                None => break,
+                Some(Tok::Close(_)) if nested => break,
+                _ => {}
+            }
+            let mut term = self.intermediate()?;
+            while self.peek() == Some(&Tok::Frac) {
+                self.next();
+                let denom = self.intermediate()?;
+                term = Math::from(Frac::Frac {
+                    nume: unbracket(term).into_stack(),
+                    denom: unbracket(denom).into_stack(),
+                });
+            }
+            roots.extend(term.0);
+        }
+        Ok(Math(roots))
+    }
+
+    fn intermediate(&mut self) -> Result<Math, AsciiMathParseError> {
+        let mut base = self.simple()?;
+        while self.peek() == Some(&Tok::Prime) {
+            let position = self.position();
+            self.next();
+            base = match add_prime(base) {
+                Some(x) => x,
+                None => return self.error(position, "Prime must follow a symbol or a bracket"),
+            };
+        }
+        let (mut sub, mut sup) = (None, None);
+        loop {
+            let position = self.position();
+            match self.peek() {
+                Some(Tok::Sub) if sub.is_none() => {
+                    self.next();
+                    sub = Some(unbracket(self.simple()?).into_simple());
+                }
+                Some(Tok::Sup) if sup.is_none() => {
+                    self.next();
+                    sup = Some(unbracket(self.simple()?).into_simple());
+                }
+                Some(Tok::Sub | Tok::Sup) => {
+                    return self.error(position, "Double script: use brackets to clarify")
+                }
+                _ => break,
+            }
+        }
+        if sub.is_none() && sup.is_none() {
+            return Ok(base);
+        }
+        Ok(Math::from(Stack::from(Inter {
+            body: base.into_simple(),
+            sup,
+            sub,
+        })))
+    }
+
+    fn simple(&mut self) -> Result<Math, AsciiMathParseError> {
+        let position = self.position();
+        let symbol = |symbol| {
+            Math::from(Simple::UnarySymbol {
+                operators: vec![],
+                symbol,
+            })
+        };
+        Ok(match self.next() {
+            Some(Tok::Symbol(tex)) => match symbol_of_command(tex) {
+                Some(x) => symbol(x),
+                None => return self.error(position, &format!("Unsupported symbol \"{}\"", tex)),
+            },
+            Some(Tok::Number(number)) => Math(
+                number
+                    .chars()
+                    .filter_map(symbol_of_char)
+                    .flat_map(|x| symbol(x).0)
+                    .collect(),
+            ),
+            Some(Tok::Char(c)) => match symbol_of_char(c) {
+                Some(x) => symbol(x),
+                None => return self.error(position, &format!("Unsupported character \"{}\"", c)),
+            },
+            Some(Tok::Open(open)) => {
+                let body = self.expression(true)?;
+                let close = match self.peek() {
+                    Some(Tok::Close(close)) => {
+                        let close = close.to_string();
+                        self.next();
+                        close
+                    }
+                    _ => String::new(),
+                };
+                if body.0.is_empty() {
+                    return self.error(position, "Empty brackets are not supported");
+                }
+                Math::from(Simple::UnaryParened {
+                    operators: vec![],
+                    open: open.to_string(),
+                    body,
+                    close,
+                })
+            }
+            Some(Tok::Close(close)) => match symbol_of_command(close) {
+                Some(x) => symbol(x),
+                None => return self.error(position, "Unmatched closing bracket"),
+            },
+            Some(Tok::Unary(tex)) => {
+                let argument = unbracket(self.simple()?);
+                let raw = argument
+                    .0
+                    .iter()
+                    .map(|x| match x.as_simple() {
+                        Some(Simple::UnarySymbol { operators, symbol })
+                            if operators.is_empty()
+                                && symbol.chars().all(|c| c.is_ascii_alphanumeric()) =>
+                        {
+                            Some(symbol.as_str())
+                        }
+                        _ => None,
+                    })
+                    .collect::<Option<String>>()
+                    .filter(|_| tex.starts_with(r"\math"));
+                Math::from(apply_command(tex, raw.as_deref(), argument))
+            }
+            Some(Tok::Fence(open, close)) => Math::from(Simple::UnaryParened {
+                operators: vec![],
+                open: open.to_string(),
+                body: unbracket(self.simple()?),
+                close: close.to_string(),
+            }),
+            Some(Tok::Binary(name)) => {
+                let first = unbracket(self.simple()?);
+                let second = unbracket(self.simple()?);
+                match name {
+                    "frac" => Math::from(Frac::Frac {
+                        nume: first.into_stack(),
+                        denom: second.into_stack(),
+                    }),
+                    "root" => Math::from(Root::Root {
+                        root: first.into_frac(),
+                        body: second.into_frac(),
+                    }),
+                    "underset" => Math::from(Stack {
+                        body: second.into_inter(),
+                        over: None,
+                        under: Some(first.into_inter()),
+                    }),
+                    _ => Math::from(Stack {
+                        body: second.into_inter(),
+                        over: Some(first.into_inter()),
+                        under: None,
+                    }),
+                }
+            }
+            Some(Tok::Text(tex, raw)) => {
+                let text = format!("{}{{{}}}", tex, raw);
+                if spell_symbol(&text, Notation::Unicode).is_err() {
+                    return self.error(position, &format!("Unsupported text \"{}\"", raw));
+                }
+                symbol(text)
+            }
+            Some(Tok::Sub | Tok::Sup) => return self.error(position, "Missing base of a script"),
+            Some(Tok::Frac) => return self.error(position, "Missing numerator"),
+            Some(Tok::Prime) => {
+                return self.error(position, "Prime must follow a symbol or a bracket")
+            }
+            None => return self.error(position, "Unexpected end of input"),
+        })
+    }
+}
+
+/// Removes the brackets around an argument, e.g. `(a+b)` in `(a+b)/c`.
+fn unbracket(math: Math) -> Math {
+    match math.as_simple() {
+        Some(Simple::UnaryParened {
+            operators,
+            open,
+            close,
+            ..
+        }) if operators.is_empty()
+            && matches!(
+                (open.as_str(), close.as_str()),
+                ("(", ")") | ("[", "]") | (r"\{", r"\}") | ("", "")
+            ) => {}
+        _ => return math,
+    }
+    match math.into_simple() {
+        Simple::UnaryParened { body, .. } => body,
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maspace_to_tex;
+
+    #[test]
+    fn test_parse_asciimath() {
+        fn x(s: &str) -> String {
+            parse_asciimath(s)
+                .unwrap()
+                .to_string()
+                .trim_end_matches(' ')
+                .to_string()
+        }
+        // the samples in README.md
+        for (asciimath, maspace) in [
+            ("(a+b)/c", "a+b /c"),
+            ("a+b/c", "a+b/c"),
+            ("a_(b^c)", "a _b^c"),
+            ("a_b^c", "a_b^c"),
+            ("a_[b_c^d]^[e+f_[g/h]]", "a _b_c^d ^[e+f _g/h]"),
+            ("a_[b_[c^d]]^[e]+[f_g]/h", "a  _b _c^d  ^e  +  f_g/h"),
+            ("hat a", "â"),
+            ("alpha'", "α'"),
+            ("cancel hat alpha", "<'cancel>α̂"),
+            ("oo", "∞"),
+            ("dot oo", "<∞ dot>"),
+            ("<", "`<`"),
+            ("sqrt 2", "√2"),
+            ("sqrt[2]", "√2"),
+            ("root 3 123", "3 _/ 123"),
+            ("sqrt[3+4]", "√[3+4]"),
+            ("norm(a)", "`[||`a`||]`"),
+            ("\"abc\"", "\"abc\""),
+        ] {
+            assert_eq!(
+                x(asciimath),
+                maspace_to_tex(maspace).unwrap(),
+                "{}",
+                asciimath
+            );
+        }
+        // AsciiMath attaches both scripts to `a` where maSpace nests them
+        assert_eq!(
+            x("a_[b_c]^[d_g^[e+f]]/h"),
+            r"\frac{a^{d^{e+f}_{g}}_{b_{c}}}{h}"
+        );
+        assert_eq!(x("sum_(i=1)^n i^2"), r"\sum^{n}_{i=1}i^{2}");
+        assert_eq!(x("f(x) = x xx 2"), r"f\left(x\right)=x\times2");
+        assert_eq!(x("bb(AB) + bbb R"), r"\mathbf{AB}+\mathbb{R}");
+        assert_eq!(x("text(if ) x > 0"), r"\text{if }x\gt0");
+        assert_eq!(x("(: a, b :)"), r"\left\langle a,b\right\rangle");
+        assert_eq!(x("abs(x)"), r"\left\lvert x\right\rvert");
+        assert_eq!(x("1/2/3"), r"\frac{\frac{1}{2}}{3}");
+        assert!(parse_asciimath("a^b^c").is_err());
+        assert_eq!(parse_asciimath("a + b_").unwrap_err().position, 6);
+        assert_eq!(x("(a+b"), r"\left(a+b\right.");
+    }
+}
@@ -0,0 +1,58 @@
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A rewrite of a byte range of the input that resolves a diagnostic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub range: Range<usize>,
+    pub replacement: String,
+}
+
+impl Suggestion {
+    /// Returns `input` with the suggestion applied.
+    pub fn apply(&self, input: &str) -> String {
+        let mut result = input.to_string();
+        result.replace_range(self.range.clone(), &self.replacement);
+        result
+    }
+}
+
+/// A message about a byte range of the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub range: Range<usize>,
+    pub suggestion: Option<Suggestion>,
+}
+
+impl Diagnostic {
+    /// An error on the character at the byte offset `position`.
+    pub(crate) fn error_at(input: &str, position: usize, message: &str) -> Self {
+        let len = input[position..].chars().next().map_or(0, char::len_utf8);
+        Self {
+            severity: Severity::Error,
+            message: message.to_string(),
+            range: position..position + len,
+            suggestion: None,
+        }
+    }
+
+    /// A warning on `range` whose fix replaces that range with `replacement`.
+    pub(crate) fn warning(range: Range<usize>, message: String, replacement: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message,
+            suggestion: Some(Suggestion {
+                range: range.clone(),
+                replacement,
+            }),
+            range,
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use std::fmt::Display;
+
+use crate::asciimath::AsciiMathParseError;
+use crate::expr::ParseError;
+use crate::printer::PrintError;
+use crate::tex::TexParseError;
+use crate::token::TokenizeError;
+
+/// An error of the conversion functions at the crate root.
+///
+/// Its message already includes the message of the wrapped error, which is its `source`.
+#[derive(Debug, Clone)]
+pub enum Error {
+    Tokenize(TokenizeError),
+    Parse(ParseError),
+    AsciiMath(AsciiMathParseError),
+    Tex(TexParseError),
+    Print(PrintError),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tokenize(x) => write!(f, "tokenize failed\n{}", x),
+            Self::Parse(x) => write!(f, "parse failed\n{}", x),
+            Self::AsciiMath(x) => write!(f, "parse failed\n{}", x),
+            Self::Tex(x) => write!(f, "parse failed\n{}", x),
+            Self::Print(x) => write!(f, "print failed\n{}", x),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(match self {
+            Self::Tokenize(x) => x,
+            Self::Parse(x) => x,
+            Self::AsciiMath(x) => x,
+            Self::Tex(x) => x,
+            Self::Print(x) => x,
+        })
+    }
+}
+
+impl From<TokenizeError> for Error {
+    fn from(x: TokenizeError) -> Self {
+        Self::Tokenize(x)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(x: ParseError) -> Self {
+        Self::Parse(x)
+    }
+}
+
+impl From<AsciiMathParseError> for Error {
+    fn from(x: AsciiMathParseError) -> Self {
+        Self::AsciiMath(x)
+    }
+}
+
+impl From<TexParseError> for Error {
+    fn from(x: TexParseError) -> Self {
+        Self::Tex(x)
+    }
+}
+
+impl From<PrintError> for Error {
+    fn from(x: PrintError) -> Self {
+        Self::Print(x)
+    }
+}
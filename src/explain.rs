@@ -0,0 +1,294 @@
+//! The grouping diagram of the README, drawn for any input.
+
+use super::error::Error;
+use super::expr::{parse, Frac, Inter, Math, Root, Simple, Stack};
+use super::token::{tokenize, Token};
+
+use std::ops::Range;
+
+/// Tokens parsed by a nonterminal of the grammar, such as `simp2`, with the parts drawn under it.
+///
+/// A nonterminal with a single operand passes on the parts of that operand, so that
+/// only the operands of separators and the `mathⁱ⁻¹` of each `simpⁱ` are drawn.
+struct Part {
+    label: String,
+    tokens: Range<usize>,
+    children: Vec<Part>,
+}
+
+impl Part {
+    fn new(name: &str, level: usize, tokens: Range<usize>, children: Vec<Part>) -> Self {
+        Self {
+            label: format!("{}{}", name, level),
+            tokens,
+            children,
+        }
+    }
+
+    /// Relabels the only operand of a nonterminal as the nonterminal itself.
+    fn pass(self, name: &str, level: usize) -> Self {
+        Self::new(name, level, self.tokens, self.children)
+    }
+
+    /// A nonterminal with the operands `children`.
+    fn parent(name: &str, level: usize, children: Vec<Part>) -> Self {
+        let tokens = children[0].tokens.start..children[children.len() - 1].tokens.end;
+        Self::new(name, level, tokens, children)
+    }
+}
+
+/// Follows the parse of `tokens` into `math`, counting the tokens consumed by each node.
+struct Walker<'a> {
+    tokens: &'a [Token],
+    /// The level of the whole input and of bracket contents.
+    level_max: usize,
+}
+
+impl Walker<'_> {
+    fn math(&self, x: &Math, level: usize, start: usize) -> Part {
+        let mut children = vec![];
+        let mut end = start;
+        for root in x.roots() {
+            let part = self.root(root, level, end);
+            // skip the cat
+            end = part.tokens.end + 1;
+            children.push(part);
+        }
+        match children.len() {
+            1 => children.remove(0).pass("math", level),
+            _ => Part::parent("math", level, children),
+        }
+    }
+
+    fn root(&self, x: &Root, level: usize, start: usize) -> Part {
+        match x {
+            Root::Root { root, body } => {
+                let root = self.frac(root, level, start);
+                let body = self.frac(body, level, root.tokens.end + 1);
+                Part::parent("root", level, vec![root, body])
+            }
+            Root::Math { body } => self.frac(body, level, start).pass("root", level),
+        }
+    }
+
+    fn frac(&self, x: &Frac, level: usize, start: usize) -> Part {
+        match x {
+            Frac::Frac { nume, denom } => {
+                let nume = self.stack(nume, level, start);
+                let denom = self.stack(denom, level, nume.tokens.end + 1);
+                Part::parent("frac", level, vec![nume, denom])
+            }
+            Frac::Math { body } => self.stack(body, level, start).pass("frac", level),
+        }
+    }
+
+    fn stack(&self, x: &Stack, level: usize, start: usize) -> Part {
+        let mut children = vec![self.inter(x.body(), level, start)];
+        for _ in 0..[x.over(), x.under()].iter().flatten().count() {
+            let start = children[children.len() - 1].tokens.end + 1;
+            let part = match self.tokens[start - 1] {
+                Token::Over(_) => x.over().unwrap(),
+                _ => x.under().unwrap(),
+            };
+            children.push(self.inter(part, level, start));
+        }
+        match children.len() {
+            1 => children.remove(0).pass("stack", level),
+            _ => Part::parent("stack", level, children),
+        }
+    }
+
+    fn inter(&self, x: &Inter, level: usize, start: usize) -> Part {
+        let mut children = vec![self.simple(x.body(), level, start)];
+        for _ in 0..[x.sup(), x.sub()].iter().flatten().count() {
+            let start = children[children.len() - 1].tokens.end + 1;
+            let part = match self.tokens[start - 1] {
+                Token::Sup(_) => x.sup().unwrap(),
+                _ => x.sub().unwrap(),
+            };
+            children.push(self.simple(part, level, start));
+        }
+        match children.len() {
+            1 => children.remove(0).pass("inter", level),
+            _ => Part::parent("inter", level, children),
+        }
+    }
+
+    fn simple(&self, x: &Simple, level: usize, start: usize) -> Part {
+        let (operators, children) = match x {
+            Simple::UnaryExpr { operators, body } => (
+                operators,
+                vec![self.math(body, level - 1, start + operators.len())],
+            ),
+            Simple::UnarySymbol { operators, .. } => (operators, vec![]),
+            Simple::UnaryParened {
+                operators, body, ..
+            } => (
+                operators,
+                vec![self.math(body, self.level_max, start + operators.len() + 1)],
+            ),
+        };
+        let end = match (x, children.first()) {
+            (Simple::UnaryParened { .. }, Some(body)) => body.tokens.end + 1,
+            (_, Some(body)) => body.tokens.end,
+            (_, None) => start + operators.len() + 1,
+        };
+        Part::new("simp", level, start..end, children)
+    }
+}
+
+/// Writes symbols as their quoted TeX, without escapes or the space ending a command,
+/// and the other tokens as they are debugged.
+fn token_text(token: &Token) -> String {
+    match token {
+        Token::Symbol(x) => format!("\"{}\"", x.trim_end_matches(' ')),
+        x => format!("{:?}", x),
+    }
+}
+
+/// Draws how maSpace groups `input`, one line per depth of the parse tree.
+///
+/// Under the input and its tokens, each part is underlined with the nonterminal of the
+/// grammar and the level it was parsed at, e.g. `simp2` for an operand of `␣␣^`.
+/// Parts of a single token are written as the token.
+pub fn explain(input: &str) -> Result<String, Error> {
+    let tokens = tokenize(input)?;
+    let math = parse(&tokens)?;
+    let level_max = tokens.iter().map(Token::order).max().unwrap_or_default();
+    let walker = Walker {
+        tokens: &tokens,
+        level_max,
+    };
+    let root = walker.math(&math, level_max, 0);
+    let texts = tokens.iter().map(token_text).collect::<Vec<_>>();
+
+    // the parts on each line, drawn as a label over their tokens or as the token itself
+    let mut lines: Vec<Vec<(Option<&str>, &Range<usize>)>> = vec![];
+    let mut depth = vec![&root];
+    while !depth.is_empty() {
+        let children = depth
+            .iter()
+            .flat_map(|x| x.children.iter())
+            .collect::<Vec<_>>();
+        if children.is_empty() {
+            break;
+        }
+        lines.push(
+            children
+                .iter()
+                .map(|x| ((x.tokens.len() > 1).then_some(x.label.as_str()), &x.tokens))
+                .collect(),
+        );
+        // a single token is drawn as it is, with nothing under it
+        depth = children
+            .into_iter()
+            .filter(|x| x.tokens.len() > 1)
+            .collect();
+    }
+
+    // widen the tokens under labels longer than them
+    let mut widths = texts.iter().map(|x| x.chars().count()).collect::<Vec<_>>();
+    let mut labels = lines
+        .iter()
+        .flatten()
+        .filter_map(|(label, range)| label.map(|x| (x, *range)))
+        .collect::<Vec<_>>();
+    labels.sort_by_key(|(_, range)| range.len());
+    for (label, range) in labels {
+        let width = widths[range.clone()].iter().sum::<usize>() + range.len() - 1;
+        let label_width = label.chars().count();
+        if width < label_width {
+            widths[range.end - 1] += label_width - width;
+        }
+    }
+    let columns = widths
+        .iter()
+        .scan(0, |column, width| {
+            let start = *column;
+            *column += width + 1;
+            Some(start)
+        })
+        .collect::<Vec<_>>();
+
+    let mut result = vec![input.trim().replace(' ', "␣")];
+    let pad = |line: &mut String, column: usize| {
+        let len = line.chars().count();
+        line.push_str(&" ".repeat(column.saturating_sub(len)));
+    };
+    let mut line = String::new();
+    for (i, text) in texts.iter().enumerate() {
+        pad(&mut line, columns[i]);
+        line.push_str(text);
+    }
+    result.push(line);
+    for parts in lines {
+        let mut line = String::new();
+        for (label, range) in parts {
+            pad(&mut line, columns[range.start]);
+            match label {
+                Some(label) => {
+                    let width =
+                        columns[range.end - 1] + widths[range.end - 1] - columns[range.start];
+                    let dashes = width - label.chars().count();
+                    line.push_str(&"-".repeat(dashes / 2));
+                    line.push_str(label);
+                    line.push_str(&"-".repeat(dashes - dashes / 2));
+                }
+                None => line.push_str(&texts[range.start]),
+            }
+        }
+        result.push(line.trim_end().to_string());
+    }
+    Ok(result.iter().map(|x| format!("{}\n", x)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explain() {
+        assert_eq!(
+            explain("a _b_c  ^d ^e+f _g  /h").unwrap(),
+            r#"a␣_b_c␣␣^d␣^e+f␣_g␣␣/h
+"a" Sub(1) "b" Sub(0) "c" Sup(2) "d" Sup(1) "e" Cat(0) "+" Cat(0) "f" Sub(1) "g" Frac(2) "h"
+-------------------------------------stack2-------------------------------------         "h"
+----------simp2----------        ---------------------simp2---------------------
+----------math1----------        ---------------------math1---------------------
+"a"        ----simp1-----        "d"        ----------simp1----------        "g"
+           ----math0-----                   ----------math0----------
+           "b"        "c"                   "e"        "+"        "f"
+"#
+        );
+        assert_eq!(
+            explain("a _b_c^d ^[e+f _g/h]").unwrap(),
+            r#"a␣_b_c^d␣^[e+f␣_g/h]
+"a" Sub(1) "b" Sub(0) "c" Sup(0) "d" Sup(1) Open("") "e" Cat(0) "+" Cat(0) "f" Sub(1) "g" Frac(0) "h" Close("")
+"a"        ----------simp1----------        -------------------------------simp1-------------------------------
+           ----------math0----------        -------------------------------math0-------------------------------
+           "b"        "c"        "d"                 ---------------------math1----------------------
+                                                     ----------simp1----------        -----simp1-----
+                                                     ----------math0----------        -----math0-----
+                                                     "e"        "+"        "f"        "g"         "h"
+"#
+        );
+        assert_eq!(
+            explain("a  b(c)").unwrap(),
+            r#"a␣␣b(c)
+"a" Cat(2) "b" Cat(0) Open("(") "c" Close(")")
+"a"        ---------------root2---------------
+           ---------------math1---------------
+           ---------------math0---------------
+           "b"        ---------root0----------
+                                "c"
+"#
+        );
+        // the operator of `simp2` is left out of the `math1` under it
+        assert_eq!(
+            explain("<'sqrt>  b  /+").unwrap(),
+            "<'sqrt>␣␣b␣␣/+\nOp(\"\\\\sqrt\", 2) \"b\" Frac(2) \"+\"\n------stack2-------         \"+\"\n                \"b\"\n"
+        );
+        assert_eq!(explain("α").unwrap(), "α\n\"\\alpha\"\n");
+        assert!(explain("a^b^c").is_err());
+    }
+}
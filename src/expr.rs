@@ -2,12 +2,23 @@ use super::token::Token;
 
 use std::fmt::Display;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ParseError {
     description: String,
     unconsumed_tokens: Vec<Token>,
 }
 
+impl ParseError {
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// The tokens left from where parsing failed to the end of the input.
+    pub fn unconsumed_tokens(&self) -> &[Token] {
+        &self.unconsumed_tokens
+    }
+}
+
 impl Display for ParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "{}", self.description)?;
@@ -18,49 +29,156 @@ impl Display for ParseError {
 
 impl std::error::Error for ParseError {}
 
-pub fn parse(tokens: &[Token]) -> Result<Math, ParseError> {
-    let order_max = tokens.iter().map(|x| x.order()).max().ok_or(ParseError {
-        description: "Input tokens are empty".to_string(),
-        unconsumed_tokens: tokens.to_owned(),
-    })?;
-    let (rest, math) = Math::parse(tokens, order_max, order_max)?;
-    if !rest.is_empty() {
-        let err_message = |x, y| {
-            format!(
-                "Double {}: use bracket to clarify a group \
-                or change the number of spaces around \"{}\"",
-                x, y
-            )
-        };
-        return Err(ParseError {
-            description: match rest {
-                [Token::Frac(_), ..] => err_message("\"/\"", "/"),
-                [Token::Root(_), ..] => err_message("\"_/\"", "_/"),
-                [Token::Over(_), ..] => err_message("over", "^^"),
-                [Token::Under(_), ..] => err_message("under", "__"),
-                [Token::Sup(_), ..] => err_message("exponent", "^"),
-                [Token::Sub(_), ..] => err_message("subscripts", "_"),
-                _ => "Some tokens are unconsumed".to_string(),
+/// The symbol standing in for a missing operand in a recovered parse.
+pub const PLACEHOLDER: &str = r"\boxed{?}";
+
+/// Errors recovered from so far, or `None` to fail on the first one.
+pub(crate) type Recovery = Option<Vec<ParseError>>;
+
+/// Records `error` when recovering, otherwise fails with it.
+fn recover(recovery: &mut Recovery, error: ParseError) -> Result<(), ParseError> {
+    match recovery {
+        Some(errors) => {
+            errors.push(error);
+            Ok(())
+        }
+        None => Err(error),
+    }
+}
+
+fn unconsumed_error(rest: &[Token]) -> ParseError {
+    let err_message = |x, y| {
+        format!(
+            "Double {}: use bracket to clarify a group \
+            or change the number of spaces around \"{}\"",
+            x, y
+        )
+    };
+    ParseError {
+        description: match rest {
+            [Token::Frac(_), ..] => err_message("\"/\"", "/"),
+            [Token::Root(_), ..] => err_message("\"_/\"", "_/"),
+            [Token::Over(_), ..] => err_message("over", "^^"),
+            [Token::Under(_), ..] => err_message("under", "__"),
+            [Token::Sup(_), ..] => err_message("exponent", "^"),
+            [Token::Sub(_), ..] => err_message("subscripts", "_"),
+            [Token::Close(_), ..] => "Unmatched closing bracket".to_string(),
+            _ => "Some tokens are unconsumed".to_string(),
+        },
+        unconsumed_tokens: rest.to_owned(),
+    }
+}
+
+/// Parses math at `order_max`, then when recovering goes on after the tokens left
+/// unconsumed as if a placeholder came before them, up to a closing bracket if `in_brackets`.
+fn parse_math_to_end<'a>(
+    tokens: &'a [Token],
+    order_max: usize,
+    in_brackets: bool,
+    recovery: &mut Recovery,
+) -> Result<(&'a [Token], Math), ParseError> {
+    let (mut rest, math) = Math::parse_with(tokens, order_max, order_max, recovery)?;
+    if recovery.is_none() {
+        return Ok((rest, math));
+    }
+    let mut roots = math.into_roots();
+    while let [first, ..] = rest {
+        let is_close = matches!(first, Token::Close(_));
+        if is_close && in_brackets {
+            break;
+        }
+        recover(recovery, unconsumed_error(rest))?;
+        let skip = usize::from(is_close);
+        let next = [&[Token::Symbol(PLACEHOLDER.to_string())], &rest[skip..]].concat();
+        let (left, math) = Math::parse_with(&next, order_max, order_max, recovery)?;
+        roots.extend(math.into_roots());
+        // the placeholder is always consumed, so `left` ends `rest`
+        rest = &rest[(rest.len() - left.len()).max(1)..];
+    }
+    Ok((rest, Math(roots)))
+}
+
+fn parse_with(tokens: &[Token], recovery: &mut Recovery) -> Result<Math, ParseError> {
+    let Some(order_max) = tokens.iter().map(|x| x.order()).max() else {
+        recover(
+            recovery,
+            ParseError {
+                description: "Input tokens are empty".to_string(),
+                unconsumed_tokens: tokens.to_owned(),
             },
-            unconsumed_tokens: rest.to_owned(),
-        });
+        )?;
+        return Ok(Math::from(Simple::placeholder(vec![])));
+    };
+    let (rest, math) = parse_math_to_end(tokens, order_max, false, recovery)?;
+    if !rest.is_empty() {
+        return Err(unconsumed_error(rest));
     }
     Ok(math)
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub struct Math(Vec<Root>);
+pub fn parse(tokens: &[Token]) -> Result<Math, ParseError> {
+    parse_with(tokens, &mut None)
+}
+
+/// Same as [`parse`] but never fails, returning every error met on the way.
+///
+/// Missing operands become [`PLACEHOLDER`], a missing closing bracket is left open,
+/// and tokens that cannot be consumed are parsed on as if a placeholder came before them.
+pub fn parse_recovering(tokens: &[Token]) -> (Math, Vec<ParseError>) {
+    let mut recovery = Some(vec![]);
+    let math = parse_with(tokens, &mut recovery);
+    let mut errors = recovery.unwrap_or_default();
+    match math {
+        Ok(math) => (math, errors),
+        // not reached as errors are recovered from
+        Err(e) => {
+            errors.push(e);
+            (Math::from(Simple::placeholder(vec![])), errors)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Math(pub(crate) Vec<Root>);
 
 impl Math {
-    pub fn parse<'a>(
+    pub fn new(roots: Vec<Root>) -> Self {
+        Self(roots)
+    }
+
+    /// The roots concatenated in this formula.
+    pub fn roots(&self) -> &[Root] {
+        &self.0
+    }
+
+    pub fn roots_mut(&mut self) -> &mut Vec<Root> {
+        &mut self.0
+    }
+
+    pub fn into_roots(self) -> Vec<Root> {
+        self.0
+    }
+
+    pub fn parse(
+        tokens: &[Token],
+        order: usize,
+        order_max: usize,
+    ) -> Result<(&[Token], Self), ParseError> {
+        Self::parse_with(tokens, order, order_max, &mut None)
+    }
+
+    pub(crate) fn parse_with<'a>(
         tokens: &'a [Token],
         order: usize,
         order_max: usize,
+        recovery: &mut Recovery,
     ) -> Result<(&'a [Token], Self), ParseError> {
         let mut roots = vec![];
         let mut tokens = tokens;
         loop {
-            let (rest, root) = Root::parse(tokens, order, order_max)?;
+            let (rest, root) = Root::parse_with(tokens, order, order_max, recovery)?;
             roots.push(root);
             tokens = match rest {
                 [Token::Cat(ord), tokens @ ..] if *ord == order => tokens,
@@ -68,6 +186,117 @@ impl Math {
             };
         }
     }
+
+    /// Returns the only `Simple` when `self` is a mere wrapper around it.
+    pub(crate) fn as_simple(&self) -> Option<&Simple> {
+        match &self.0[..] {
+            [root] => root.as_simple(),
+            _ => None,
+        }
+    }
+
+    /// Unwraps `self` into a `Simple`, wrapping it in `Simple::UnaryExpr` if needed.
+    pub(crate) fn into_simple(self) -> Simple {
+        match self.as_simple() {
+            Some(_) => self.into_inter().body,
+            None => Simple::UnaryExpr {
+                operators: vec![],
+                body: self,
+            },
+        }
+    }
+
+    pub(crate) fn into_inter(self) -> Inter {
+        let stack = self.into_stack();
+        match stack {
+            Stack {
+                body,
+                over: None,
+                under: None,
+            } => body,
+            stack => Inter::from(Simple::from(Math::from(stack))),
+        }
+    }
+
+    pub(crate) fn into_stack(self) -> Stack {
+        match self.into_frac() {
+            Frac::Math { body } => body,
+            frac => Stack::from(Inter::from(Simple::from(Math::from(frac)))),
+        }
+    }
+
+    pub(crate) fn into_frac(mut self) -> Frac {
+        match (self.0.pop(), self.0.is_empty()) {
+            (Some(Root::Math { body }), true) => body,
+            (Some(root), _) => {
+                self.0.push(root);
+                Frac::from(Stack::from(Inter::from(Simple::from(self))))
+            }
+            (None, _) => Frac::from(Stack::from(Inter::from(Simple::from(self)))),
+        }
+    }
+}
+
+impl From<Root> for Math {
+    fn from(root: Root) -> Self {
+        Self(vec![root])
+    }
+}
+
+impl From<Frac> for Math {
+    fn from(frac: Frac) -> Self {
+        Self::from(Root::Math { body: frac })
+    }
+}
+
+impl From<Stack> for Math {
+    fn from(stack: Stack) -> Self {
+        Self::from(Frac::Math { body: stack })
+    }
+}
+
+impl From<Frac> for Root {
+    fn from(frac: Frac) -> Self {
+        Root::Math { body: frac }
+    }
+}
+
+impl From<Stack> for Frac {
+    fn from(stack: Stack) -> Self {
+        Frac::Math { body: stack }
+    }
+}
+
+impl From<Inter> for Stack {
+    fn from(inter: Inter) -> Self {
+        Stack {
+            body: inter,
+            over: None,
+            under: None,
+        }
+    }
+}
+
+impl From<Simple> for Inter {
+    fn from(simple: Simple) -> Self {
+        Inter {
+            body: simple,
+            sup: None,
+            sub: None,
+        }
+    }
+}
+
+impl From<Math> for Simple {
+    fn from(math: Math) -> Self {
+        math.into_simple()
+    }
+}
+
+impl From<Simple> for Math {
+    fn from(simple: Simple) -> Self {
+        Self::from(Stack::from(Inter::from(simple)))
+    }
 }
 
 impl Display for Math {
@@ -78,13 +307,20 @@ impl Display for Math {
             return Ok(());
         }
         for w in roots.windows(2) {
-            if w[1].to_string().starts_with(|c| match c {
-                'A'..='Z' | 'a'..='z' => true,
-                _ => false,
-            }) {
-                write!(f, "{}", w[0])?;
+            let x = w[0].to_string();
+            if w[1]
+                .to_string()
+                .starts_with(|c: char| c.is_ascii_alphabetic())
+            {
+                // keep a closing command such as `\right\rangle` apart from the letter
+                let name = x.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+                if name.len() < x.len() && name.ends_with('\\') {
+                    write!(f, "{} ", x)?;
+                } else {
+                    write!(f, "{}", x)?;
+                }
             } else {
-                write!(f, "{}", w[0].to_string().trim_end_matches(' '))?;
+                write!(f, "{}", x.trim_end_matches(' '))?;
             }
         }
         if let Some(x) = roots.last() {
@@ -94,22 +330,57 @@ impl Display for Math {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[allow(clippy::large_enum_variant)]
 pub enum Root {
     Root { root: Frac, body: Frac },
     Math { body: Frac },
 }
 
 impl Root {
-    pub fn parse<'a>(
+    /// Returns the only `Simple` when `self` is a mere wrapper around it.
+    pub(crate) fn as_simple(&self) -> Option<&Simple> {
+        match self {
+            Root::Math {
+                body:
+                    Frac::Math {
+                        body:
+                            Stack {
+                                body:
+                                    Inter {
+                                        body,
+                                        sup: None,
+                                        sub: None,
+                                    },
+                                over: None,
+                                under: None,
+                            },
+                    },
+            } => Some(body),
+            _ => None,
+        }
+    }
+
+    pub fn parse(
+        tokens: &[Token],
+        order: usize,
+        order_max: usize,
+    ) -> Result<(&[Token], Self), ParseError> {
+        Self::parse_with(tokens, order, order_max, &mut None)
+    }
+
+    pub(crate) fn parse_with<'a>(
         tokens: &'a [Token],
         order: usize,
         order_max: usize,
+        recovery: &mut Recovery,
     ) -> Result<(&'a [Token], Self), ParseError> {
-        let (tokens, frac_first) = Frac::parse(tokens, order, order_max)?;
+        let (tokens, frac_first) = Frac::parse_with(tokens, order, order_max, recovery)?;
         match tokens {
             [Token::Root(ord), tokens @ ..] if *ord == order => {
-                let (tokens, frac_second) = Frac::parse(tokens, order, order_max)?;
+                let (tokens, frac_second) = Frac::parse_with(tokens, order, order_max, recovery)?;
                 Ok((
                     tokens,
                     Self::Root {
@@ -138,22 +409,34 @@ impl Display for Root {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[allow(clippy::large_enum_variant)]
 pub enum Frac {
     Frac { nume: Stack, denom: Stack },
     Math { body: Stack },
 }
 
 impl Frac {
-    pub fn parse<'a>(
+    pub fn parse(
+        tokens: &[Token],
+        order: usize,
+        order_max: usize,
+    ) -> Result<(&[Token], Self), ParseError> {
+        Self::parse_with(tokens, order, order_max, &mut None)
+    }
+
+    pub(crate) fn parse_with<'a>(
         tokens: &'a [Token],
         order: usize,
         order_max: usize,
+        recovery: &mut Recovery,
     ) -> Result<(&'a [Token], Self), ParseError> {
-        let (tokens, stack_first) = Stack::parse(tokens, order, order_max)?;
+        let (tokens, stack_first) = Stack::parse_with(tokens, order, order_max, recovery)?;
         match tokens {
             [Token::Frac(ord), tokens @ ..] if *ord == order => {
-                let (tokens, stack_second) = Stack::parse(tokens, order, order_max)?;
+                let (tokens, stack_second) = Stack::parse_with(tokens, order, order_max, recovery)?;
                 Ok((
                     tokens,
                     Self::Frac {
@@ -182,27 +465,72 @@ impl Display for Frac {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Stack {
-    body: Inter,
-    over: Option<Inter>,
-    under: Option<Inter>,
+    pub(crate) body: Inter,
+    pub(crate) over: Option<Inter>,
+    pub(crate) under: Option<Inter>,
 }
 
 impl Stack {
-    pub fn parse<'a>(
+    pub fn new(body: Inter, over: Option<Inter>, under: Option<Inter>) -> Self {
+        Self { body, over, under }
+    }
+
+    pub fn body(&self) -> &Inter {
+        &self.body
+    }
+
+    /// The part written above `body` with `^^`.
+    pub fn over(&self) -> Option<&Inter> {
+        self.over.as_ref()
+    }
+
+    /// The part written below `body` with `__`.
+    pub fn under(&self) -> Option<&Inter> {
+        self.under.as_ref()
+    }
+
+    pub fn body_mut(&mut self) -> &mut Inter {
+        &mut self.body
+    }
+
+    pub fn over_mut(&mut self) -> &mut Option<Inter> {
+        &mut self.over
+    }
+
+    pub fn under_mut(&mut self) -> &mut Option<Inter> {
+        &mut self.under
+    }
+
+    pub fn into_parts(self) -> (Inter, Option<Inter>, Option<Inter>) {
+        (self.body, self.over, self.under)
+    }
+
+    pub fn parse(
+        tokens: &[Token],
+        order: usize,
+        order_max: usize,
+    ) -> Result<(&[Token], Self), ParseError> {
+        Self::parse_with(tokens, order, order_max, &mut None)
+    }
+
+    pub(crate) fn parse_with<'a>(
         tokens: &'a [Token],
         order: usize,
         order_max: usize,
+        recovery: &mut Recovery,
     ) -> Result<(&'a [Token], Self), ParseError> {
-        let (tokens, body) = Inter::parse(tokens, order, order_max)?;
+        let (tokens, body) = Inter::parse_with(tokens, order, order_max, recovery)?;
         match tokens {
             [Token::Over(ord), tokens @ ..] if *ord == order => {
-                let (tokens, over) = Inter::parse(tokens, order, order_max)?;
+                let (tokens, over) = Inter::parse_with(tokens, order, order_max, recovery)?;
                 let (over, under) = (Some(over), None);
                 match tokens {
                     [Token::Under(ord), tokens @ ..] if *ord == order => {
-                        let (tokens, under) = Inter::parse(tokens, order, order_max)?;
+                        let (tokens, under) =
+                            Inter::parse_with(tokens, order, order_max, recovery)?;
                         let under = Some(under);
                         Ok((tokens, Self { body, over, under }))
                     }
@@ -210,11 +538,11 @@ impl Stack {
                 }
             }
             [Token::Under(ord), tokens @ ..] if *ord == order => {
-                let (tokens, under) = Inter::parse(tokens, order, order_max)?;
+                let (tokens, under) = Inter::parse_with(tokens, order, order_max, recovery)?;
                 let (over, under) = (None, Some(under));
                 match tokens {
                     [Token::Over(ord), tokens @ ..] if *ord == order => {
-                        let (tokens, over) = Inter::parse(tokens, order, order_max)?;
+                        let (tokens, over) = Inter::parse_with(tokens, order, order_max, recovery)?;
                         let over = Some(over);
                         Ok((tokens, Self { body, over, under }))
                     }
@@ -273,27 +601,71 @@ impl Display for Stack {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Inter {
-    body: Simple,
-    sup: Option<Simple>,
-    sub: Option<Simple>,
+    pub(crate) body: Simple,
+    pub(crate) sup: Option<Simple>,
+    pub(crate) sub: Option<Simple>,
 }
 
 impl Inter {
-    pub fn parse<'a>(
+    pub fn new(body: Simple, sup: Option<Simple>, sub: Option<Simple>) -> Self {
+        Self { body, sup, sub }
+    }
+
+    pub fn body(&self) -> &Simple {
+        &self.body
+    }
+
+    /// The superscript written with `^`.
+    pub fn sup(&self) -> Option<&Simple> {
+        self.sup.as_ref()
+    }
+
+    /// The subscript written with `_`.
+    pub fn sub(&self) -> Option<&Simple> {
+        self.sub.as_ref()
+    }
+
+    pub fn body_mut(&mut self) -> &mut Simple {
+        &mut self.body
+    }
+
+    pub fn sup_mut(&mut self) -> &mut Option<Simple> {
+        &mut self.sup
+    }
+
+    pub fn sub_mut(&mut self) -> &mut Option<Simple> {
+        &mut self.sub
+    }
+
+    pub fn into_parts(self) -> (Simple, Option<Simple>, Option<Simple>) {
+        (self.body, self.sup, self.sub)
+    }
+
+    pub fn parse(
+        tokens: &[Token],
+        order: usize,
+        order_max: usize,
+    ) -> Result<(&[Token], Self), ParseError> {
+        Self::parse_with(tokens, order, order_max, &mut None)
+    }
+
+    pub(crate) fn parse_with<'a>(
         tokens: &'a [Token],
         order: usize,
         order_max: usize,
+        recovery: &mut Recovery,
     ) -> Result<(&'a [Token], Self), ParseError> {
-        let (tokens, body) = Simple::parse(tokens, order, order_max)?;
+        let (tokens, body) = Simple::parse_with(tokens, order, order_max, recovery)?;
         match tokens {
             [Token::Sup(ord), tokens @ ..] if *ord == order => {
-                let (tokens, sup) = Simple::parse(tokens, order, order_max)?;
+                let (tokens, sup) = Simple::parse_with(tokens, order, order_max, recovery)?;
                 let (sup, sub) = (Some(sup), None);
                 match tokens {
                     [Token::Sub(ord), tokens @ ..] if *ord == order => {
-                        let (tokens, sub) = Simple::parse(tokens, order, order_max)?;
+                        let (tokens, sub) = Simple::parse_with(tokens, order, order_max, recovery)?;
                         let sub = Some(sub);
                         Ok((tokens, Self { body, sup, sub }))
                     }
@@ -301,11 +673,11 @@ impl Inter {
                 }
             }
             [Token::Sub(ord), tokens @ ..] if *ord == order => {
-                let (tokens, sub) = Simple::parse(tokens, order, order_max)?;
+                let (tokens, sub) = Simple::parse_with(tokens, order, order_max, recovery)?;
                 let (sup, sub) = (None, Some(sub));
                 match tokens {
                     [Token::Sup(ord), tokens @ ..] if *ord == order => {
-                        let (tokens, sup) = Simple::parse(tokens, order, order_max)?;
+                        let (tokens, sup) = Simple::parse_with(tokens, order, order_max, recovery)?;
                         let sup = Some(sup);
                         Ok((tokens, Self { body, sup, sub }))
                     }
@@ -360,7 +732,9 @@ impl Display for Inter {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum Simple {
     UnaryExpr {
         operators: Vec<String>,
@@ -379,23 +753,32 @@ pub enum Simple {
 }
 
 impl Simple {
-    pub fn parse<'a>(
+    /// A [`PLACEHOLDER`] for a missing operand of `operators`.
+    pub(crate) fn placeholder(operators: Vec<String>) -> Self {
+        Self::UnarySymbol {
+            operators,
+            symbol: PLACEHOLDER.to_string(),
+        }
+    }
+
+    pub fn parse(
+        tokens: &[Token],
+        order: usize,
+        order_max: usize,
+    ) -> Result<(&[Token], Self), ParseError> {
+        Self::parse_with(tokens, order, order_max, &mut None)
+    }
+
+    pub(crate) fn parse_with<'a>(
         tokens: &'a [Token],
         order: usize,
         order_max: usize,
+        recovery: &mut Recovery,
     ) -> Result<(&'a [Token], Self), ParseError> {
         let mut tokens = tokens;
         let mut operators = vec![];
         loop {
             tokens = match tokens {
-                [] => {
-                    return Err(ParseError {
-                        description: "Failed to parse Simple: Missing symbol \
-                        or expression enclosed in brackets"
-                            .to_string(),
-                        unconsumed_tokens: tokens.to_owned(),
-                    })
-                }
                 [Token::Op(operator, ord), tokens @ ..] if *ord == order => {
                     operators.push(operator.to_owned());
                     tokens
@@ -410,7 +793,7 @@ impl Simple {
                     Ok((tokens, Self::UnarySymbol { operators, symbol }))
                 }
                 [Token::Open(open), tokens @ ..] => {
-                    let (tokens, body) = Math::parse(tokens, order_max, order_max)?;
+                    let (tokens, body) = parse_math_to_end(tokens, order_max, true, recovery)?;
                     match tokens {
                         [Token::Close(close), tokens @ ..] => {
                             let (open, close) = (open.to_owned(), close.to_owned());
@@ -424,21 +807,39 @@ impl Simple {
                                 },
                             ))
                         }
-                        _ => Err(ParseError {
-                            description: "Failed to parse Simple: No closing bracket".to_string(),
-                            unconsumed_tokens: tokens.to_owned(),
-                        }),
+                        _ => {
+                            let error = ParseError {
+                                description: "Failed to parse Simple: No closing bracket"
+                                    .to_string(),
+                                unconsumed_tokens: tokens.to_owned(),
+                            };
+                            recover(recovery, error)?;
+                            let (open, close) = (open.to_owned(), String::new());
+                            Ok((
+                                tokens,
+                                Self::UnaryParened {
+                                    operators,
+                                    open,
+                                    body,
+                                    close,
+                                },
+                            ))
+                        }
                     }
                 }
-                _ => Err(ParseError {
-                    description: "Failed to parse Simple: Missing symbol \
+                _ => {
+                    let error = ParseError {
+                        description: "Failed to parse Simple: Missing symbol \
                         or expression enclosed in brackets"
-                        .to_string(),
-                    unconsumed_tokens: tokens.to_owned(),
-                }),
+                            .to_string(),
+                        unconsumed_tokens: tokens.to_owned(),
+                    };
+                    recover(recovery, error)?;
+                    Ok((tokens, Self::placeholder(operators)))
+                }
             }
         } else {
-            let (tokens, body) = Math::parse(tokens, order - 1, order_max)?;
+            let (tokens, body) = Math::parse_with(tokens, order - 1, order_max, recovery)?;
             Ok((tokens, Self::UnaryExpr { operators, body }))
         }
     }
@@ -455,7 +856,7 @@ impl Display for Simple {
             if operators.is_empty() {
                 return x;
             }
-            let mut y = format!("{}", x.trim_end_matches(' '));
+            let mut y = x.trim_end_matches(' ').to_string();
             for z in operators.iter().rev() {
                 y = format!("{}{{{}}}", z, y);
             }
@@ -467,14 +868,26 @@ impl Display for Simple {
                 open, body, close, ..
             } => {
                 let (open, close) = match (open.as_str(), close.as_str()) {
-                    ("", "") => (format!(""), format!("")),
-                    ("", close) => (format!("\\left."), format!("\\right{}", close)),
-                    (open, "") => (format!("\\left{}", open), format!("\\right.")),
+                    ("", "") => (String::new(), String::new()),
+                    ("", close) => ("\\left.".to_string(), format!("\\right{}", close)),
+                    (open, "") => (format!("\\left{}", open), "\\right.".to_string()),
                     (open, close) => (format!("\\left{}", open), format!("\\right{}", close)),
                 };
-                write!(f, "{}", open)?;
-                write!(f, "{}", body.to_string().trim_end_matches(' '))?;
-                write!(f, "{}", close)?;
+                let body = body.to_string();
+                let body = body.trim_end_matches(' ');
+                // keep a command delimiter such as `\langle` apart from a following letter
+                let separator = if open.ends_with(|c: char| c.is_ascii_alphabetic())
+                    && body.starts_with(|c: char| c.is_ascii_alphabetic())
+                {
+                    " "
+                } else {
+                    ""
+                };
+                write!(
+                    f,
+                    "{}",
+                    fmt_op(format!("{}{}{}{}", open, separator, body, close))
+                )?;
             }
             Self::UnarySymbol { operators, symbol } if operators.is_empty() => {
                 write!(f, "{}", symbol)?
@@ -503,4 +916,51 @@ mod tests {
             r"a_{b_{c}}".to_string()
         );
     }
+
+    #[test]
+    fn test_parse_recovering() {
+        let x = |s: &str| {
+            let (math, errors) = parse_recovering(&crate::token::tokenize(s).unwrap());
+            let errors = errors
+                .iter()
+                .map(|x| x.unconsumed_tokens().len())
+                .collect::<Vec<_>>();
+            (math.to_string().trim_end().to_string(), errors)
+        };
+        assert_eq!(x("a+b /c"), (r"\frac{a+b}{c}".to_string(), vec![]));
+        assert_eq!(x("a /"), (r"\frac{a}{\boxed{?}}".to_string(), vec![0]));
+        assert_eq!(x("(a+b"), (r"\left(a+b\right.".to_string(), vec![0]));
+        assert_eq!(x("a^b^c"), (r"a^{b}\boxed{?}^{c}".to_string(), vec![2]));
+        assert_eq!(x("a)b"), (r"a\boxed{?}b".to_string(), vec![3]));
+        assert_eq!(x("√"), (r"\sqrt{\boxed{?}}".to_string(), vec![0]));
+        assert_eq!(
+            x("x_(a^b^c"),
+            (
+                r"x_{\left(a^{b}\boxed{?}^{c}\right.}".to_string(),
+                vec![2, 0]
+            )
+        );
+        assert_eq!(x(""), (r"\boxed{?}".to_string(), vec![0]));
+        assert!(parse(&crate::token::tokenize("a /").unwrap()).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let tokens = crate::token::tokenize("â_i").unwrap();
+        let math = parse(&tokens).unwrap();
+        assert_eq!(
+            serde_json::to_string(&tokens).unwrap(),
+            r#"[{"symbol":"\\hat{a}"},{"sub":0},{"symbol":"i"}]"#
+        );
+        let json = serde_json::to_string(&math).unwrap();
+        assert_eq!(
+            json,
+            r#"[{"math":{"body":{"math":{"body":{"body":{"body":{"unary_symbol":"#.to_string()
+                + r#"{"operators":[],"symbol":"\\hat{a}"}},"sup":null,"#
+                + r#""sub":{"unary_symbol":{"operators":[],"symbol":"i"}}},"#
+                + r#""over":null,"under":null}}}}}]"#
+        );
+        assert_eq!(serde_json::from_str::<Math>(&json).unwrap(), math);
+    }
 }
@@ -0,0 +1,289 @@
+//! C ABI declared in `ffi/include/maspace.h`.
+//!
+//! Options and results are opaque handles created and freed by this library.
+//! Strings returned from a result are NUL-terminated UTF-8 owned by the result and
+//! valid until it is freed. Offsets count bytes of the input. A panic never unwinds
+//! into C: each function catches it and returns its failure value instead.
+
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use crate::diagnostic::Severity;
+use crate::{convert, ConvertOptions, Syntax};
+
+pub const MASPACE_SYNTAX_MASPACE: c_int = 0;
+pub const MASPACE_SYNTAX_ASCIIMATH: c_int = 1;
+
+pub const MASPACE_SEVERITY_ERROR: c_int = 0;
+pub const MASPACE_SEVERITY_WARNING: c_int = 1;
+
+pub struct MaspaceOptions {
+    options: ConvertOptions,
+}
+
+pub struct MaspaceResult {
+    tex: Option<CString>,
+    diagnostics: Vec<(c_int, CString, usize, usize)>,
+}
+
+/// A diagnostic borrowed from a `MaspaceResult`.
+#[repr(C)]
+pub struct MaspaceDiagnostic {
+    pub severity: c_int,
+    pub message: *const c_char,
+    pub start: usize,
+    pub end: usize,
+}
+
+fn c_string(s: &str) -> CString {
+    CString::new(s.replace('\0', "")).unwrap_or_default()
+}
+
+/// Runs `f`, or returns `failure` if it panics.
+fn catch<T>(failure: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(failure)
+}
+
+/// Returns the version of this library as a static string.
+#[no_mangle]
+pub extern "C" fn maspace_version() -> *const c_char {
+    static VERSION: &CStr =
+        match CStr::from_bytes_with_nul(concat!(env!("CARGO_PKG_VERSION"), "\0").as_bytes()) {
+            Ok(x) => x,
+            Err(_) => panic!("version contains NUL"),
+        };
+    catch(ptr::null(), || VERSION.as_ptr())
+}
+
+/// Creates options for maSpace input, or returns null on an internal error.
+#[no_mangle]
+pub extern "C" fn maspace_options_new() -> *mut MaspaceOptions {
+    catch(ptr::null_mut(), || {
+        Box::into_raw(Box::new(MaspaceOptions {
+            options: ConvertOptions::default(),
+        }))
+    })
+}
+
+/// Sets the input syntax to one of `MASPACE_SYNTAX_*`. Returns 0, or -1 for an unknown syntax
+/// or an internal error.
+///
+/// # Safety
+///
+/// `options` must come from `maspace_options_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn maspace_options_set_syntax(
+    options: *mut MaspaceOptions,
+    syntax: c_int,
+) -> c_int {
+    catch(-1, || {
+        let Some(options) = options.as_mut() else {
+            return -1;
+        };
+        options.options.syntax = match syntax {
+            MASPACE_SYNTAX_MASPACE => Syntax::Maspace,
+            MASPACE_SYNTAX_ASCIIMATH => Syntax::AsciiMath,
+            _ => return -1,
+        };
+        0
+    })
+}
+
+/// # Safety
+///
+/// `options` must be null or come from `maspace_options_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn maspace_options_free(options: *mut MaspaceOptions) {
+    catch((), || {
+        if !options.is_null() {
+            drop(Box::from_raw(options));
+        }
+    })
+}
+
+/// Converts `len` bytes of UTF-8 at `input` to TeX. `options` may be null for the defaults.
+///
+/// Never returns null; check `maspace_result_tex` or the diagnostics for failures,
+/// including an internal error covering the whole input.
+///
+/// # Safety
+///
+/// `input` must point to `len` readable bytes, or be null when `len` is 0.
+/// `options` must be null or a live handle from `maspace_options_new`.
+#[no_mangle]
+pub unsafe extern "C" fn maspace_convert(
+    input: *const c_char,
+    len: usize,
+    options: *const MaspaceOptions,
+) -> *mut MaspaceResult {
+    let bytes = match input.is_null() {
+        true => &[][..],
+        false => std::slice::from_raw_parts(input.cast::<u8>(), len),
+    };
+    let internal_error = MaspaceResult {
+        tex: None,
+        diagnostics: vec![(
+            MASPACE_SEVERITY_ERROR,
+            c_string("Internal error"),
+            0,
+            bytes.len(),
+        )],
+    };
+    let result = catch(internal_error, || match std::str::from_utf8(bytes) {
+        Ok(input) => {
+            let default = ConvertOptions::default();
+            let options = options.as_ref().map_or(&default, |x| &x.options);
+            let conversion = convert(input, options);
+            MaspaceResult {
+                tex: conversion.tex.as_deref().map(c_string),
+                diagnostics: conversion
+                    .diagnostics
+                    .iter()
+                    .map(|x| {
+                        let severity = match x.severity {
+                            Severity::Error => MASPACE_SEVERITY_ERROR,
+                            Severity::Warning => MASPACE_SEVERITY_WARNING,
+                        };
+                        (severity, c_string(&x.message), x.range.start, x.range.end)
+                    })
+                    .collect(),
+            }
+        }
+        Err(e) => MaspaceResult {
+            tex: None,
+            diagnostics: vec![(
+                MASPACE_SEVERITY_ERROR,
+                c_string("Input is not valid UTF-8"),
+                e.valid_up_to(),
+                e.valid_up_to() + e.error_len().unwrap_or(0),
+            )],
+        },
+    });
+    Box::into_raw(Box::new(result))
+}
+
+/// Returns the TeX, or null if the conversion failed. Writes its length to `len` unless `len` is null.
+///
+/// # Safety
+///
+/// `result` must be a live handle from `maspace_convert`; `len` must be null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn maspace_result_tex(
+    result: *const MaspaceResult,
+    len: *mut usize,
+) -> *const c_char {
+    catch(ptr::null(), || {
+        let Some(tex) = result.as_ref().and_then(|x| x.tex.as_ref()) else {
+            return ptr::null();
+        };
+        if let Some(len) = len.as_mut() {
+            *len = tex.as_bytes().len();
+        }
+        tex.as_ptr()
+    })
+}
+
+/// # Safety
+///
+/// `result` must be a live handle from `maspace_convert`.
+#[no_mangle]
+pub unsafe extern "C" fn maspace_result_diagnostic_count(result: *const MaspaceResult) -> usize {
+    catch(0, || result.as_ref().map_or(0, |x| x.diagnostics.len()))
+}
+
+/// Fills `out` with the diagnostic at `index`. Returns 0, or -1 if `index` is out of range.
+///
+/// # Safety
+///
+/// `result` must be a live handle from `maspace_convert`; `out` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn maspace_result_diagnostic(
+    result: *const MaspaceResult,
+    index: usize,
+    out: *mut MaspaceDiagnostic,
+) -> c_int {
+    catch(-1, || {
+        let (Some(result), Some(out)) = (result.as_ref(), out.as_mut()) else {
+            return -1;
+        };
+        let Some((severity, message, start, end)) = result.diagnostics.get(index) else {
+            return -1;
+        };
+        *out = MaspaceDiagnostic {
+            severity: *severity,
+            message: message.as_ptr(),
+            start: *start,
+            end: *end,
+        };
+        0
+    })
+}
+
+/// # Safety
+///
+/// `result` must be null or come from `maspace_convert` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn maspace_result_free(result: *mut MaspaceResult) {
+    catch((), || {
+        if !result.is_null() {
+            drop(Box::from_raw(result));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn tex(result: *const MaspaceResult) -> Option<String> {
+        let mut len = 0;
+        let tex = maspace_result_tex(result, &mut len);
+        (!tex.is_null()).then(|| CStr::from_ptr(tex).to_str().unwrap().to_string())
+    }
+
+    #[test]
+    fn test_convert() {
+        unsafe {
+            let input = "a+b /c";
+            let result = maspace_convert(input.as_ptr().cast(), input.len(), ptr::null());
+            assert_eq!(tex(result).as_deref(), Some(r"\frac{a+b}{c}"));
+            assert_eq!(maspace_result_diagnostic_count(result), 0);
+            maspace_result_free(result);
+
+            let options = maspace_options_new();
+            assert_eq!(maspace_options_set_syntax(options, 2), -1);
+            assert_eq!(
+                maspace_options_set_syntax(options, MASPACE_SYNTAX_ASCIIMATH),
+                0
+            );
+            let input = "a_";
+            let result = maspace_convert(input.as_ptr().cast(), input.len(), options);
+            assert_eq!(tex(result), None);
+            let mut diagnostic = MaspaceDiagnostic {
+                severity: -1,
+                message: ptr::null(),
+                start: 0,
+                end: 0,
+            };
+            assert_eq!(maspace_result_diagnostic(result, 0, &mut diagnostic), 0);
+            assert_eq!(diagnostic.severity, MASPACE_SEVERITY_ERROR);
+            assert_eq!((diagnostic.start, diagnostic.end), (2, 2));
+            assert!(!CStr::from_ptr(diagnostic.message).is_empty());
+            assert_eq!(maspace_result_diagnostic(result, 1, &mut diagnostic), -1);
+            maspace_result_free(result);
+            maspace_options_free(options);
+
+            let input = b"a\xff";
+            let result = maspace_convert(input.as_ptr().cast(), input.len(), ptr::null());
+            assert_eq!(maspace_result_diagnostic(result, 0, &mut diagnostic), 0);
+            assert_eq!((diagnostic.start, diagnostic.end), (1, 2));
+            maspace_result_free(result);
+
+            assert_eq!(catch(-1, || panic!("escaped")), -1);
+
+            let version = CStr::from_ptr(maspace_version());
+            assert_eq!(version.to_str().unwrap(), env!("CARGO_PKG_VERSION"));
+        }
+    }
+}
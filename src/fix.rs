@@ -0,0 +1,285 @@
+//! Candidate rewrites of an input that fails to parse.
+//!
+//! Candidates are found by trying small edits around the failure, such as adding
+//! brackets or changing the spaces around an operator, and keeping those that parse.
+
+use std::ops::Range;
+
+use crate::diagnostic::Suggestion;
+use crate::expr::{parse, Frac, Inter, Math, Root, Simple};
+use crate::token::{tokenize, tokenize_with_spans, Token};
+use crate::visit::{self, Visitor};
+
+/// Fixes beyond this many are not returned.
+const MAX_FIXES: usize = 5;
+
+/// A rewritten input that parses, with its TeX.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    pub input: String,
+    pub tex: String,
+}
+
+impl Fix {
+    /// The edit turning `input` into `self.input`, covering every changed byte.
+    pub fn suggestion(&self, input: &str) -> Suggestion {
+        let prefix = input
+            .char_indices()
+            .zip(self.input.chars())
+            .find(|((_, x), y)| x != y)
+            .map_or(input.len().min(self.input.len()), |((i, _), _)| i);
+        let suffix = input[prefix..]
+            .chars()
+            .rev()
+            .zip(self.input[prefix..].chars().rev())
+            .take_while(|(x, y)| x == y)
+            .map(|(x, _)| x.len_utf8())
+            .sum::<usize>();
+        Suggestion {
+            range: prefix..input.len() - suffix,
+            replacement: self.input[prefix..self.input.len() - suffix].to_string(),
+        }
+    }
+}
+
+fn closing_of(open: &str) -> Option<&'static str> {
+    Some(match open.chars().next()? {
+        '(' => ")",
+        '[' => "]",
+        '{' => "}",
+        '⟨' => "⟩",
+        '⌈' => "⌉",
+        '⌊' => "⌋",
+        '⌜' => "⌝",
+        '⌞' => "⌟",
+        _ => return None,
+    })
+}
+
+fn is_operator(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Sub(_)
+            | Token::Sup(_)
+            | Token::Over(_)
+            | Token::Under(_)
+            | Token::Root(_)
+            | Token::Frac(_)
+    )
+}
+
+/// Inputs with one more or one less space around a written operator.
+fn respaced(input: &str, tokens: &[(Token, Range<usize>)]) -> Vec<String> {
+    let mut result = vec![];
+    for (token, range) in tokens {
+        if !is_operator(token) || range.is_empty() {
+            continue;
+        }
+        let text = &input[range.clone()];
+        let op = text.trim_matches(' ');
+        let width = token.order();
+        let before = &input[..range.start];
+        let after = &input[range.end..];
+        for width in [width + 1, width.saturating_sub(1)] {
+            if width != token.order() {
+                result.push(format!("{}{}{}{}", before, " ".repeat(width), op, after));
+            }
+        }
+    }
+    result
+}
+
+/// Inputs with `[` and `]` around a run of tokens next to or across the failure at `failure`.
+fn bracketed(input: &str, tokens: &[(Token, Range<usize>)], failure: usize) -> Vec<String> {
+    let starts = tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, (x, span))| {
+            !span.is_empty() && matches!(x, Token::Symbol(_) | Token::Open(_) | Token::Op(..))
+        })
+        .map(|(i, (_, span))| (i, span.start));
+    let ends = |start: usize| {
+        tokens
+            .iter()
+            .enumerate()
+            .skip(start)
+            .filter(|(_, (x, span))| {
+                !span.is_empty() && matches!(x, Token::Symbol(_) | Token::Close(_))
+            })
+            .map(|(i, (_, span))| (i, span.end))
+    };
+    let mut candidates = vec![];
+    for (i, start) in starts {
+        for (j, end) in ends(i) {
+            let touches = i <= failure && failure <= j + 1;
+            if touches && (i, j + 1) != (0, tokens.len()) {
+                candidates.push((
+                    j - i,
+                    format!(
+                        "{}[{}]{}",
+                        &input[..start],
+                        &input[start..end],
+                        &input[end..]
+                    ),
+                ));
+            }
+        }
+    }
+    // the smallest groups change the meaning least
+    candidates.sort_by_key(|(len, _)| *len);
+    candidates.into_iter().map(|(_, x)| x).collect()
+}
+
+/// The `Inter` a script after `simple` attaches to in TeX, if `simple` prints without braces.
+fn script_base(simple: &Simple) -> Option<&Inter> {
+    let body = match simple {
+        Simple::UnaryExpr { operators, body } if operators.is_empty() => body,
+        Simple::UnaryParened {
+            operators,
+            open,
+            body,
+            close,
+        } if operators.is_empty() && open.is_empty() && close.is_empty() => body,
+        _ => return None,
+    };
+    match body.roots().last()? {
+        Root::Math {
+            body: Frac::Math { body: stack },
+        } if stack.over().is_none() && stack.under().is_none() => {
+            let inter = stack.body();
+            if inter.sup().is_none() && inter.sub().is_none() {
+                script_base(inter.body())
+            } else {
+                Some(inter)
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Finds two scripts of the same kind on one base, such as `a^{b}^{c}`, which TeX rejects.
+struct DoubleScript(bool);
+
+impl Visitor for DoubleScript {
+    fn visit_inter(&mut self, inter: &Inter) {
+        if let Some(base) = script_base(inter.body()) {
+            self.0 |= inter.sup().is_some() && base.sup().is_some()
+                || inter.sub().is_some() && base.sub().is_some();
+        }
+        visit::visit_inter(self, inter)
+    }
+}
+
+fn has_double_script(math: &Math) -> bool {
+    let mut visitor = DoubleScript(false);
+    visitor.visit_math(math);
+    visitor.0
+}
+
+/// Returns rewrites of a maSpace `input` that fail to parse, each with its TeX.
+///
+/// The rewrites add the closing bracket of an unclosed one, with or without changing the
+/// spaces around an operator by one, change those spaces alone, or put brackets around a
+/// group next to the failure, in that order.
+/// Rewrites with the same TeX are listed once, and those whose TeX stacks two scripts of
+/// the same kind on one base, such as `a^{b}^{c}`, are left out. Empty if `input` parses or fails to tokenize.
+pub fn fixes(input: &str) -> Vec<Fix> {
+    let Ok(tokens) = tokenize_with_spans(input) else {
+        return vec![];
+    };
+    let bare = tokens.iter().map(|(x, _)| x.clone()).collect::<Vec<_>>();
+    let failure = match parse(&bare) {
+        Ok(_) => return vec![],
+        Err(e) => bare.len() - e.unconsumed_tokens().len(),
+    };
+    let mut candidates = vec![];
+    // close the innermost bracket left open
+    let mut open = vec![];
+    for (token, span) in &tokens {
+        match token {
+            Token::Open(_) => open.push(&input[span.clone()]),
+            Token::Close(_) => {
+                open.pop();
+            }
+            _ => {}
+        }
+    }
+    if let Some(close) = open.last().and_then(|x| closing_of(x)) {
+        let closed = format!("{}{}", input.trim_end(), close);
+        // the closed input may still need the spaces of an operator changed
+        if let Ok(tokens) = tokenize_with_spans(&closed) {
+            candidates.push(closed.clone());
+            candidates.extend(respaced(&closed, &tokens));
+        }
+    }
+    candidates.extend(respaced(input, &tokens));
+    if tokens.len() <= 64 {
+        candidates.extend(bracketed(input, &tokens, failure));
+    }
+    let mut result: Vec<Fix> = vec![];
+    for input in candidates {
+        let Some(math) = tokenize(&input).ok().and_then(|x| parse(&x).ok()) else {
+            continue;
+        };
+        if has_double_script(&math) {
+            continue;
+        }
+        let tex = math.to_string().trim_end_matches(' ').to_owned();
+        if result.iter().all(|x| x.tex != tex) {
+            result.push(Fix { input, tex });
+        }
+        if result.len() == MAX_FIXES {
+            break;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs(input: &str) -> Vec<String> {
+        fixes(input).into_iter().map(|x| x.input).collect()
+    }
+
+    #[test]
+    fn test_fixes() {
+        // `a^b ^c` and `[a^b]^c` would be `a^{b}^{c}`, a double superscript in TeX
+        assert_eq!(
+            fixes("a^b^c"),
+            [Fix {
+                input: "a ^b^c".to_string(),
+                tex: "a^{b^{c}}".to_string()
+            }]
+        );
+        assert_eq!(inputs("x_i_j"), ["x _i_j"]);
+        assert_eq!(inputs("a /b /c"), ["a  /b /c", "a/b /c"]);
+        assert_eq!(inputs("(a+b"), ["(a+b)"]);
+        assert_eq!(inputs("(a /b /c"), ["(a  /b /c)", "(a/b /c)"]);
+        assert_eq!(inputs("(x_i_j"), ["(x _i_j)"]);
+        assert_eq!(fixes("a+b"), []);
+        assert_eq!(fixes("a `zz"), []);
+    }
+
+    #[test]
+    fn test_bracketed() {
+        let input = "a^b^c";
+        let candidates = bracketed(input, &tokenize_with_spans(input).unwrap(), 3);
+        assert_eq!(candidates[0], "a^[b]^c");
+        assert!(candidates.contains(&"[a^b]^c".to_string()));
+        assert!(candidates.contains(&"a^[b^c]".to_string()));
+    }
+
+    #[test]
+    fn test_suggestion() {
+        let fix = Fix {
+            input: "a^[b^c]".to_string(),
+            tex: String::new(),
+        };
+        let suggestion = fix.suggestion("a^b^c");
+        assert_eq!(suggestion.range, 2..5);
+        assert_eq!(suggestion.replacement, "[b^c]");
+        assert_eq!(suggestion.apply("a^b^c"), "a^[b^c]");
+    }
+}
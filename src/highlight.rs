@@ -0,0 +1,266 @@
+//! Classified ranges of a maSpace input for syntax highlighting.
+//!
+//! Binary operators carry the number of spaces they bind with, so colouring by it
+//! shows how the input groups.
+
+use std::ops::Range;
+
+use unicode_normalization::char::is_combining_mark;
+
+use crate::token::{tokenize_with_spans, Token};
+
+/// The kind of a binary operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    /// Concatenation by spaces.
+    Cat,
+    Sub,
+    Sup,
+    Over,
+    Under,
+    Root,
+    Frac,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Class {
+    Symbol,
+    StringLiteral,
+    /// An accent of a symbol, such as `hat` in `<a hat>` or a combining character.
+    Accent,
+    /// A unary operator such as `√`.
+    Op,
+    Open,
+    Close,
+    /// A binary operator with the number of spaces it binds with.
+    BinOp(BinOp, usize),
+    /// A symbol written as a unicode subscript such as `₁`.
+    UnicodeSub,
+    /// A symbol written as a unicode superscript such as `²`.
+    UnicodeSup,
+}
+
+/// A byte range of the input and what it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Highlight {
+    pub class: Class,
+    pub range: Range<usize>,
+}
+
+/// Byte length of the string literal at the start of `s`, `"…"` or `#"…"#`.
+fn string_literal_len(s: &str) -> Option<usize> {
+    let hashes = s.len() - s.trim_start_matches('#').len();
+    let rest = s[hashes..].strip_prefix('"')?;
+    let end = format!("\"{}", "#".repeat(hashes));
+    rest.find(&end).map(|x| 2 * hashes + 2 + x)
+}
+
+/// Byte length of the longest prefix of `s` whose characters satisfy `f`.
+fn prefix_len(s: &str, f: impl Fn(char) -> bool) -> usize {
+    s.len() - s.trim_start_matches(f).len()
+}
+
+/// Byte length of the symbol name at the start of the inside of `<…>`.
+fn head_len(s: &str) -> usize {
+    if let Some(len) = s.strip_prefix('`').and_then(|x| x.find('`')) {
+        return len + 2;
+    }
+    let alpha = prefix_len(s, |c| c.is_ascii_alphabetic());
+    if alpha > 1 {
+        return alpha;
+    }
+    let digits = |s: &str| prefix_len(s, |c| c.is_ascii_digit());
+    let integer = digits(s);
+    if integer > 0 {
+        return match s[integer..].strip_prefix('.').map(digits) {
+            Some(decimal) if decimal > 0 => integer + 1 + decimal,
+            _ => integer,
+        };
+    }
+    let mut chars = s.char_indices();
+    chars.next();
+    chars
+        .find(|(_, c)| !is_combining_mark(*c))
+        .map_or(s.len(), |(i, _)| i)
+}
+
+/// Ranges of the accents in `s`, each a mark such as `^` or an alphanumeric name.
+fn accents(s: &str, offset: usize) -> Vec<Highlight> {
+    let mut result = vec![];
+    let mut i = 0;
+    while let Some(c) = s[i..].chars().next() {
+        let len = match c {
+            ' ' => {
+                i += 1;
+                continue;
+            }
+            '!' | '^' | '~' | '-' => 1,
+            _ => prefix_len(&s[i..], char::is_alphanumeric).max(c.len_utf8()),
+        };
+        result.push(Highlight {
+            class: Class::Accent,
+            range: offset + i..offset + i + len,
+        });
+        i += len;
+    }
+    result
+}
+
+/// Splits a symbol token `text` starting at `offset` into its name, string literal and accents.
+fn symbol_parts(text: &str, offset: usize) -> Vec<Highlight> {
+    let part = |class, range: Range<usize>| Highlight {
+        class,
+        range: offset + range.start..offset + range.end,
+    };
+    let body = text.trim_end_matches('\'');
+    let mut parts = vec![];
+    if let Some(inside) = body.strip_prefix('<').and_then(|x| x.strip_suffix('>')) {
+        let start = 1 + inside.len() - inside.trim_start_matches(' ').len();
+        let rest = &inside[start - 1..];
+        let (class, len) = match string_literal_len(rest) {
+            Some(len) => (Class::StringLiteral, len),
+            None => (Class::Symbol, head_len(rest)),
+        };
+        // the angle brackets go with the name
+        parts.push(part(class, 0..start + len));
+        parts.extend(accents(&rest[len..], offset + start + len));
+        parts.push(part(class, body.len() - 1..body.len()));
+    } else if string_literal_len(body).is_some() {
+        parts.push(part(Class::StringLiteral, 0..body.len()));
+    } else if body.starts_with('`') {
+        parts.push(part(Class::Symbol, 0..body.len()));
+    } else {
+        let head = body.chars().next().map_or(0, char::len_utf8);
+        parts.push(part(Class::Symbol, 0..head));
+        if head < body.len() {
+            parts.push(part(Class::Accent, head..body.len()));
+        }
+    }
+    if body.len() < text.len() {
+        parts.push(part(Class::Symbol, body.len()..text.len()));
+    }
+    // join the parts of the same class next to each other
+    let mut result: Vec<Highlight> = vec![];
+    for x in parts {
+        match result.last_mut() {
+            Some(last) if last.class == x.class && last.range.end == x.range.start => {
+                last.range.end = x.range.end
+            }
+            _ => result.push(x),
+        }
+    }
+    result
+}
+
+/// Returns the classified ranges of a maSpace `input`, in order.
+///
+/// Binary operators include the spaces around them, so a concatenation by spaces
+/// covers only spaces. Brackets and unary operators do not include theirs.
+/// If `input` fails to tokenize, the part before the failure is classified.
+pub fn highlight(input: &str) -> Vec<Highlight> {
+    let tokens = match tokenize_with_spans(input) {
+        Ok(x) => x,
+        Err(e) => tokenize_with_spans(&input[..e.position()]).unwrap_or_default(),
+    };
+    let mut result = vec![];
+    // the class of the symbols in a unicode script
+    let mut script = None;
+    for (token, range) in tokens {
+        let text = &input[range.clone()];
+        let start = range.start + text.len() - text.trim_start_matches(' ').len();
+        let trimmed = start..start + text.trim_matches(' ').len();
+        let (class, range) = match token {
+            // unicode scripts are symbols between a virtual script and a virtual close
+            Token::Sub(_) if range.is_empty() => {
+                script = Some(Class::UnicodeSub);
+                continue;
+            }
+            Token::Sup(_) if range.is_empty() => {
+                script = Some(Class::UnicodeSup);
+                continue;
+            }
+            Token::Close(_) if range.is_empty() => {
+                script = None;
+                continue;
+            }
+            _ if range.is_empty() => continue,
+            Token::Cat(x) => (Class::BinOp(BinOp::Cat, x), range),
+            Token::Sub(x) => (Class::BinOp(BinOp::Sub, x), range),
+            Token::Sup(x) => (Class::BinOp(BinOp::Sup, x), range),
+            Token::Over(x) => (Class::BinOp(BinOp::Over, x), range),
+            Token::Under(x) => (Class::BinOp(BinOp::Under, x), range),
+            Token::Root(x) => (Class::BinOp(BinOp::Root, x), range),
+            Token::Frac(x) => (Class::BinOp(BinOp::Frac, x), range),
+            Token::Op(..) => (Class::Op, trimmed),
+            Token::Open(_) => (Class::Open, trimmed),
+            Token::Close(_) => (Class::Close, trimmed),
+            Token::Symbol(_) => match script {
+                Some(class) => (class, range),
+                None => {
+                    result.extend(symbol_parts(text, range.start));
+                    continue;
+                }
+            },
+            Token::UnicodeSub(_) | Token::UnicodeSup(_) => continue,
+        };
+        result.push(Highlight { class, range });
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classes(input: &str) -> Vec<(Class, &str)> {
+        highlight(input)
+            .into_iter()
+            .map(|x| (x.class, &input[x.range]))
+            .collect()
+    }
+
+    #[test]
+    fn test_highlight() {
+        use Class::*;
+        assert_eq!(
+            classes("√x² +(a  _b)"),
+            [
+                (Op, "√"),
+                (Symbol, "x"),
+                (UnicodeSup, "²"),
+                (BinOp(super::BinOp::Cat, 2), " "),
+                (Symbol, "+"),
+                (Open, "("),
+                (Symbol, "a"),
+                (BinOp(super::BinOp::Sub, 2), "  _"),
+                (Symbol, "b"),
+                (Close, ")"),
+            ]
+        );
+        assert_eq!(
+            classes("<a hat>' \"ab\" <\"R\" bb>"),
+            [
+                (Symbol, "<a"),
+                (Accent, "hat"),
+                (Symbol, ">'"),
+                (BinOp(super::BinOp::Cat, 1), " "),
+                (StringLiteral, "\"ab\""),
+                (BinOp(super::BinOp::Cat, 1), " "),
+                (StringLiteral, "<\"R\""),
+                (Accent, "bb"),
+                (StringLiteral, ">"),
+            ]
+        );
+        assert_eq!(classes("a\u{302}"), [(Symbol, "a"), (Accent, "\u{302}")]);
+        assert_eq!(classes("<alpha ^ ~>")[1..3], [(Accent, "^"), (Accent, "~")]);
+        // the part before an unknown token is still classified
+        assert_eq!(
+            classes("a^b `zz"),
+            [
+                (Symbol, "a"),
+                (BinOp(super::BinOp::Sup, 0), "^"),
+                (Symbol, "b")
+            ]
+        );
+    }
+}
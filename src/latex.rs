@@ -0,0 +1,297 @@
+//! maSpace math in LaTeX sources.
+//!
+//! Formulas are written as `\maspace{..}`, or one per line in a `maspace` environment.
+//! Comments, `\verb` and verbatim environments are left alone, and the converted
+//! source keeps the line of everything after a formula.
+
+use std::ops::Range;
+
+use crate::{convert, Conversion, ConvertOptions};
+
+/// Environments whose content is not TeX.
+const VERBATIM: [&str; 6] = [
+    "verbatim",
+    "verbatim*",
+    "lstlisting",
+    "minted",
+    "comment",
+    "Verbatim",
+];
+
+const BEGIN: &str = r"\begin{maspace}";
+const END: &str = r"\end{maspace}";
+
+/// How a formula is written in the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MathKind {
+    /// `\maspace{..}` at `command`.
+    Macro { command: Range<usize> },
+    /// A line of the `maspace` environment at `block`, `\begin` and `\end` included.
+    Environment { block: Range<usize> },
+}
+
+/// A formula in a LaTeX source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MathSpan {
+    pub kind: MathKind,
+    /// Byte range of the maSpace.
+    pub range: Range<usize>,
+}
+
+/// Byte offset of the end of the `{..}` group starting at `start`, after its `}`.
+fn group_end(text: &str, start: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in text[start..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 1 => return Some(start + i + 1),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Whether a line of a `maspace` environment is a formula, neither blank nor a comment.
+fn is_formula(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty() && !trimmed.starts_with('%')
+}
+
+/// Returns the formulas of a LaTeX `text`, in order.
+///
+/// A `\maspace{` or `\begin{maspace}` left open is not a formula.
+pub fn math_spans(text: &str) -> Vec<MathSpan> {
+    let mut result = vec![];
+    let mut i = 0;
+    while let Some(c) = text[i..].chars().next() {
+        let rest = &text[i..];
+        if c == '%' {
+            i += rest.find('\n').unwrap_or(rest.len());
+            continue;
+        }
+        if c != '\\' {
+            i += c.len_utf8();
+            continue;
+        }
+        let name_len = rest[1..]
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len() - 1);
+        let name = &rest[1..1 + name_len];
+        let after = i + 1 + name_len;
+        match name {
+            // escaped characters such as `\%` and `\\`
+            "" => i += 1 + rest[1..].chars().next().map_or(0, char::len_utf8),
+            "verb" => {
+                let delimited = text[after..].strip_prefix('*').unwrap_or(&text[after..]);
+                let start = text.len() - delimited.len();
+                i = match delimited.chars().next() {
+                    Some(d) => delimited[d.len_utf8()..]
+                        .find(d)
+                        .map_or(text.len(), |x| start + 2 * d.len_utf8() + x),
+                    None => text.len(),
+                };
+            }
+            "maspace" if text[after..].starts_with('{') => match group_end(text, after) {
+                Some(end) => {
+                    result.push(MathSpan {
+                        kind: MathKind::Macro { command: i..end },
+                        range: after + 1..end - 1,
+                    });
+                    i = end;
+                }
+                None => i = after,
+            },
+            "begin" => {
+                let Some(end) = group_end(text, after).filter(|_| text[after..].starts_with('{'))
+                else {
+                    i = after;
+                    continue;
+                };
+                let environment = &text[after + 1..end - 1];
+                let end_marker = format!("\\end{{{}}}", environment);
+                let Some(content_len) = text[end..].find(&end_marker) else {
+                    i = end;
+                    continue;
+                };
+                let block = i..end + content_len + end_marker.len();
+                if environment == "maspace" {
+                    let mut start = end;
+                    for line in text[end..end + content_len].split('\n') {
+                        if is_formula(line) {
+                            let leading = line.len() - line.trim_start().len();
+                            result.push(MathSpan {
+                                kind: MathKind::Environment {
+                                    block: block.clone(),
+                                },
+                                range: start + leading..start + line.trim_end().len(),
+                            });
+                        }
+                        start += line.len() + 1;
+                    }
+                    i = block.end;
+                } else if VERBATIM.contains(&environment) {
+                    i = block.end;
+                } else {
+                    i = end;
+                }
+            }
+            _ => i = after,
+        }
+    }
+    result
+}
+
+/// A LaTeX source with its formulas converted.
+#[derive(Debug, Clone)]
+pub struct LatexConversion {
+    pub text: String,
+    /// Every formula with its conversion, whose ranges are relative to the formula.
+    pub formulas: Vec<(MathSpan, Conversion)>,
+}
+
+/// Replaces the formulas of a LaTeX `text` with their TeX.
+///
+/// `\maspace{..}` becomes `\ensuremath{..}`, and a `maspace` environment becomes a `gather*`
+/// environment of amsmath with a line per formula. Line breaks are kept, behind `%` where
+/// they would change the output, so that lines after a formula keep their numbers.
+/// Formulas that fail to convert are left as they are, and so is an environment with any of them.
+pub fn convert_latex(text: &str, options: &ConvertOptions) -> LatexConversion {
+    let formulas = math_spans(text)
+        .into_iter()
+        .map(|x| {
+            let conversion = convert(&text[x.range.clone()], options);
+            (x, conversion)
+        })
+        .collect::<Vec<_>>();
+    let mut result = String::new();
+    let mut end = 0;
+    let mut i = 0;
+    while let Some((span, conversion)) = formulas.get(i) {
+        let (range, tex) = match &span.kind {
+            MathKind::Macro { command } => {
+                i += 1;
+                let breaks = "%\n".repeat(text[command.clone()].matches('\n').count());
+                let tex = conversion
+                    .tex
+                    .as_ref()
+                    .map(|x| format!("\\ensuremath{{{}}}{}", x, breaks));
+                (command.clone(), tex)
+            }
+            MathKind::Environment { block } => {
+                let lines = formulas[i..]
+                    .iter()
+                    .take_while(|(x, _)| x.kind == span.kind)
+                    .collect::<Vec<_>>();
+                i += lines.len();
+                let tex = lines
+                    .iter()
+                    .map(|(_, x)| x.tex.as_deref())
+                    .collect::<Option<Vec<_>>>();
+                let content = &text[block.start + BEGIN.len()..block.end - END.len()];
+                (block.clone(), tex.map(|x| gather(content, &x)))
+            }
+        };
+        if let Some(tex) = tex {
+            result.push_str(&text[end..range.start]);
+            result.push_str(&tex);
+            end = range.end;
+        }
+    }
+    result.push_str(&text[end..]);
+    LatexConversion {
+        text: result,
+        formulas,
+    }
+}
+
+/// The `gather*` environment replacing a `maspace` environment with `content`, whose formulas are `texs`.
+fn gather(content: &str, texs: &[&str]) -> String {
+    let lines = content.split('\n').collect::<Vec<_>>();
+    let mut remaining = lines.iter().filter(|x| is_formula(x)).count();
+    let mut texs = texs.iter();
+    let mut result = vec![];
+    for (i, line) in lines.iter().enumerate() {
+        let indent = &line[..line.len() - line.trim_start().len()];
+        result.push(if is_formula(line) {
+            remaining -= 1;
+            let tex = texs.next().copied().unwrap_or_default();
+            match remaining {
+                0 => format!("{}{}", indent, tex),
+                _ => format!("{}{} \\\\", indent, tex),
+            }
+        } else if line.trim().is_empty() && 0 < i && i + 1 < lines.len() {
+            // a blank line would end the math
+            "%".to_string()
+        } else {
+            line.to_string()
+        });
+    }
+    format!("\\begin{{gather*}}{}\\end{{gather*}}", result.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = r"Let \maspace{a+b /c} % not \maspace{x}
+\verb|\maspace{y}| and \verb*+z+.
+\begin{verbatim}
+\maspace{v}
+\end{verbatim}
+\begin{maspace}
+  α^2
+
+  % a comment
+  √x
+\end{maspace}
+Done \maspace{{a} _b}.
+";
+
+    #[test]
+    fn test_math_spans() {
+        let spans = math_spans(TEXT);
+        let formulas = spans
+            .iter()
+            .map(|x| &TEXT[x.range.clone()])
+            .collect::<Vec<_>>();
+        assert_eq!(formulas, ["a+b /c", "α^2", "√x", "{a} _b"]);
+        let command = &TEXT[match &spans[0].kind {
+            MathKind::Macro { command } => command.clone(),
+            _ => unreachable!(),
+        }];
+        assert_eq!(command, r"\maspace{a+b /c}");
+        assert!(math_spans(r"\begin{maspace} a").is_empty());
+    }
+
+    #[test]
+    fn test_convert_latex() {
+        let x = convert_latex(TEXT, &ConvertOptions::default());
+        assert_eq!(
+            x.text,
+            r"Let \ensuremath{\frac{a+b}{c}} % not \maspace{x}
+\verb|\maspace{y}| and \verb*+z+.
+\begin{verbatim}
+\maspace{v}
+\end{verbatim}
+\begin{gather*}
+  \alpha^{2} \\
+%
+  % a comment
+  \sqrt{x}
+\end{gather*}
+Done \ensuremath{\left\{a\right\}_{b}}.
+"
+        );
+        assert_eq!(x.text.lines().count(), TEXT.lines().count());
+        let text = "\\maspace{a^b^c\n} and\n\\begin{maspace}\nx\na /\n\\end{maspace}\n";
+        let x = convert_latex(text, &ConvertOptions::default());
+        assert_eq!(x.text, text);
+        assert_eq!(
+            x.formulas.iter().filter(|(_, x)| x.tex.is_none()).count(),
+            2
+        );
+        let x = convert_latex("\\maspace{a\n +b}\n", &ConvertOptions::default());
+        assert_eq!(x.text.lines().count(), 2);
+    }
+}
@@ -1,30 +1,259 @@
+pub mod asciimath;
+pub mod diagnostic;
+pub mod error;
+pub mod explain;
 pub mod expr;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fix;
+pub mod highlight;
+pub mod latex;
+pub mod lint;
+pub mod markdown;
+pub mod omml;
+pub mod pretty;
+pub mod printer;
+pub mod tex;
 pub mod token;
+pub mod unicode;
+pub mod visit;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
 #[cfg(feature = "wee_alloc")]
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
-use anyhow::{Context, Result};
+use std::ops::Range;
 
-use expr::parse;
-use token::tokenize;
+use asciimath::parse_asciimath;
+use diagnostic::{Diagnostic, Severity};
+pub use error::Error;
+use expr::{parse, parse_recovering, Math};
+use fix::{fixes, Fix};
+use omml::omml;
+use printer::print;
+use tex::parse_tex;
+use token::completion::{complete, Completion};
+use token::reverse::maspace_of_tex;
+use token::{tokenize, tokenize_safe, tokenize_with_spans, Token};
+use unicode::unicode_text;
+use visit::VisitorMut;
 
-pub fn maspace_to_tex(input: &str) -> Result<String> {
-    let tokens = tokenize(input).context("tokenize failed")?;
-    let result = parse(&tokens)
-        .context("parse failed")?
+pub fn maspace_to_tex(input: &str) -> Result<String, Error> {
+    let tokens = tokenize(input)?;
+    let result = parse(&tokens)?.to_string().trim_end_matches(' ').to_owned();
+    Ok(result)
+}
+
+/// Renders a maSpace formula as linear Unicode text such as `x²ⁿ` or `√(a+b)`.
+pub fn maspace_to_unicode(input: &str) -> Result<String, Error> {
+    let math = parse(&tokenize(input)?)?;
+    Ok(unicode_text(&math))
+}
+
+/// Converts a maSpace formula to an `m:oMath` element of OMML, which Word accepts from the clipboard.
+pub fn maspace_to_omml(input: &str) -> Result<String, Error> {
+    let math = parse(&tokenize(input)?)?;
+    Ok(omml(&math))
+}
+
+/// Converts an AsciiMath formula to TeX.
+pub fn asciimath_to_tex(input: &str) -> Result<String, Error> {
+    let result = parse_asciimath(input)?
         .to_string()
         .trim_end_matches(' ')
         .to_owned();
     Ok(result)
 }
 
-#[wasm_bindgen]
-pub fn maspace_to_tex_wasm(input: &str) -> Result<String, String> {
-    maspace_to_tex(input).map_err(|x| x.to_string())
+/// Converts an AsciiMath formula to maSpace.
+pub fn asciimath_to_maspace(input: &str) -> Result<String, Error> {
+    let math = parse_asciimath(input)?;
+    let result = print(&math)?;
+    Ok(result)
+}
+
+/// Converts a TeX math formula to maSpace which `maspace_to_tex` turns back into the same formula.
+pub fn tex_to_maspace(input: &str) -> Result<String, Error> {
+    let math = parse_tex(input)?;
+    let result = print(&math)?;
+    Ok(result)
+}
+
+pub fn tex_to_maspace_spellings(tex: &str) -> Vec<String> {
+    maspace_of_tex(tex)
+}
+
+pub fn maspace_completions(input: &str, cursor: usize) -> Vec<Completion> {
+    complete(input, cursor)
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Syntax {
+    #[default]
+    Maspace,
+    AsciiMath,
+}
+
+/// The TeX dialect written by [`convert`].
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Profile {
+    /// For MathJax and KaTeX in HTML, where `\lt` and `\gt` stand for `<` and `>`.
+    #[default]
+    MathJax,
+    /// For LaTeX, which has no `\lt` and `\gt`.
+    Latex,
+}
+
+/// Writes `<` and `>` instead of `\lt` and `\gt`.
+struct LatexProfile;
+
+impl VisitorMut for LatexProfile {
+    fn visit_symbol_mut(&mut self, symbol: &mut String) {
+        match symbol.trim_end_matches(' ') {
+            r"\lt" => *symbol = "<".to_string(),
+            r"\gt" => *symbol = ">".to_string(),
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ConvertOptions {
+    pub syntax: Syntax,
+    pub profile: Profile,
+    /// Whether to refuse TeX commands that maSpace input passes through by name, see
+    /// [`token::tokenize_safe`].
+    pub safe: bool,
+    /// Whether to add the spacing warnings of [`lint::lint`] for maSpace input.
+    pub lint: bool,
+    /// Whether to render maSpace input with parse errors anyway, see [`expr::parse_recovering`].
+    pub recover: bool,
+}
+
+/// Everything found while converting an input, whether it succeeded or not.
+#[derive(Debug, Clone)]
+pub struct Conversion {
+    pub tex: Option<String>,
+    /// maSpace tokens with their byte ranges, empty for other syntaxes.
+    pub tokens: Vec<(Token, Range<usize>)>,
+    pub math: Option<Math>,
+    pub diagnostics: Vec<Diagnostic>,
+    /// The first error, which is also in `diagnostics`.
+    pub error: Option<Error>,
+    /// Rewrites of a maSpace input that fails to parse, see [`fix::fixes`].
+    pub fixes: Vec<Fix>,
+}
+
+impl Conversion {
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|x| x.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|x| x.severity == Severity::Warning)
+    }
+}
+
+/// Converts `input` to TeX, collecting diagnostics with byte ranges instead of failing.
+pub fn convert(input: &str, options: &ConvertOptions) -> Conversion {
+    let mut conversion = Conversion {
+        tex: None,
+        tokens: vec![],
+        math: None,
+        diagnostics: vec![],
+        error: None,
+        fixes: vec![],
+    };
+    let tokenize_spans = |x: &str| match options.safe {
+        true => tokenize_safe(x),
+        false => tokenize_with_spans(x),
+    };
+    let math = match options.syntax {
+        Syntax::Maspace => match tokenize_spans(input) {
+            Ok(tokens) => {
+                conversion.tokens = tokens;
+                if options.lint {
+                    conversion
+                        .diagnostics
+                        .extend(lint::lint_tokens(input, &conversion.tokens));
+                }
+                let tokens = conversion
+                    .tokens
+                    .iter()
+                    .map(|(x, _)| x.clone())
+                    .collect::<Vec<_>>();
+                let (math, errors) = match options.recover {
+                    true => {
+                        let (math, errors) = parse_recovering(&tokens);
+                        (Some(math), errors)
+                    }
+                    false => match parse(&tokens) {
+                        Ok(math) => (Some(math), vec![]),
+                        Err(x) => (None, vec![x]),
+                    },
+                };
+                if !errors.is_empty() {
+                    conversion.fixes = fixes(input);
+                }
+                for (i, x) in errors.iter().enumerate() {
+                    let range = conversion
+                        .tokens
+                        .get(tokens.len() - x.unconsumed_tokens().len())
+                        .map_or(input.len()..input.len(), |(_, span)| span.clone());
+                    // the fixes rewrite the whole input, so they go with the first error
+                    let suggestion = match i {
+                        0 => conversion.fixes.first().map(|x| x.suggestion(input)),
+                        _ => None,
+                    };
+                    conversion.diagnostics.push(Diagnostic {
+                        severity: Severity::Error,
+                        message: x.description().to_string(),
+                        range,
+                        suggestion,
+                    });
+                }
+                conversion.error = errors.into_iter().next().map(Error::Parse);
+                math
+            }
+            Err(x) => {
+                let error = Diagnostic::error_at(input, x.position(), x.description());
+                conversion.diagnostics.push(error);
+                conversion.error = Some(Error::Tokenize(x));
+                None
+            }
+        },
+        Syntax::AsciiMath => match parse_asciimath(input) {
+            Ok(math) => Some(math),
+            Err(x) => {
+                let error = Diagnostic::error_at(input, x.position(), x.description());
+                conversion.diagnostics.push(error);
+                conversion.error = Some(Error::AsciiMath(x));
+                None
+            }
+        },
+    };
+    if let Some(mut math) = math {
+        if options.profile == Profile::Latex {
+            LatexProfile.visit_math_mut(&mut math);
+        }
+        conversion.tex = Some(math.to_string().trim_end_matches(' ').to_owned());
+        conversion.math = Some(math);
+    }
+    conversion
+        .diagnostics
+        .sort_by_key(|x| (x.range.start, x.range.end));
+    conversion
 }
 
 #[cfg(test)]
@@ -49,5 +278,162 @@ mod tests {
             maspace_to_tex(r"a  _b _c^d  ^e  +  f_g/h").unwrap(),
             r"a^{e}_{b_{c^{d}}}+\frac{f_{g}}{h}"
         );
+        assert!(matches!(maspace_to_tex("a `zz"), Err(Error::Tokenize(_))));
+        assert!(matches!(maspace_to_tex("a^b^c"), Err(Error::Parse(_))));
+    }
+
+    #[test]
+    fn test_tex_to_maspace() {
+        for (tex, expected) in [
+            (
+                r"a+\frac{b^{a+b=c}_{ho}}{c}\alpha\hat{\beta}^{2}\otimes\sqrt[5]{1.23}",
+                r"a+\frac{b^{a+b=c}_{ho}}{c}\alpha\hat{\beta}^{2}\otimes\sqrt[5]{1.23}",
+            ),
+            (
+                r"\frac{a_{b_{c}}^{d^{e+f}_{g}}}{h}",
+                r"\frac{a^{d^{e+f}_{g}}_{b_{c}}}{h}",
+            ),
+            (
+                r"a^{e+f_{\frac{g}{h}}}_{b^{d}_{c}}",
+                r"a^{e+f_{\frac{g}{h}}}_{b^{d}_{c}}",
+            ),
+            (
+                r"\sqrt{\frac{a}{b}} + \sqrt{\alpha} - \hat{x+y}",
+                r"\sqrt{\frac{a}{b}}+\sqrt{\alpha}-\hat{x+y}",
+            ),
+            (
+                r"\left\langle \mathbf{v}, \mathbf{w} \right\rangle = \sum_{i=1}^{n} v_i w_i",
+                r"\left\langle\mathbf{v},\mathbf{w}\right\rangle=\sum^{n}_{i=1}v_{i}w_{i}",
+            ),
+            (
+                r"\underset{x \to 0}{\lim} \frac{\sin x}{x} = 1",
+                r"\underset{x\to0}{\lim}\frac{\sin x}{x}=1",
+            ),
+            (
+                r"f'(x) = \lim_{h \to 0} \frac{f(x+h) - f(x)}{h}",
+                r"f'\left(x\right)=\lim_{h\to0}\frac{f\left(x+h\right)-f\left(x\right)}{h}",
+            ),
+            (
+                r"\overset{\text{def}}{=} \mathrm{d}x",
+                r"\overset{\text{def}}{=}\mathrm{d}x",
+            ),
+            (
+                r"\left\{ x \in \mathbb{R} \mid x^2 < 2 \right\}",
+                r"\left\{x\in\mathbb{R}\mid x^{2}\lt2\right\}",
+            ),
+            (
+                r"\left\langle a \right\rangle b",
+                r"\left\langle a\right\rangle b",
+            ),
+            (
+                r"\left\lvert x \right\rvert y",
+                r"\left\lvert x\right\rvert y",
+            ),
+        ] {
+            let maspace = tex_to_maspace(tex).unwrap();
+            assert_eq!(maspace_to_tex(&maspace).unwrap(), expected, "{}", maspace);
+        }
+        assert_eq!(tex_to_maspace(r"\frac{a+b}{c}").unwrap(), "a+b /c");
+        assert_eq!(tex_to_maspace(r"\alpha_{i}^{2}").unwrap(), "α_i^2");
+    }
+
+    #[test]
+    fn test_error() {
+        let e = maspace_to_tex("`x").unwrap_err();
+        assert_eq!(e.to_string(), "tokenize failed\nunknown token at \"`x\"\n");
+        let source = std::error::Error::source(&e).unwrap();
+        assert_eq!(source.to_string(), "unknown token at \"`x\"\n");
+    }
+
+    #[test]
+    fn test_convert_profile_safe() {
+        let latex = ConvertOptions {
+            profile: Profile::Latex,
+            ..Default::default()
+        };
+        assert_eq!(convert("a`<`b", &latex).tex.as_deref(), Some("a<b"));
+        assert_eq!(
+            convert("a`<`b", &Default::default()).tex.as_deref(),
+            Some(r"a\lt b")
+        );
+        let safe = ConvertOptions {
+            safe: true,
+            ..Default::default()
+        };
+        for input in [
+            "<alpha hat>^2",
+            r#""a\b" <"c" rm>"#,
+            "<'sqrt 3>x +[√a]",
+            "`oo` <∞ dot>",
+            "<a bf> 𝒂",
+        ] {
+            let x = convert(input, &safe);
+            assert!(x.tex.is_some(), "{}: {:?}", input, x.diagnostics);
+        }
+        let x = convert("a <'input>b", &safe);
+        assert_eq!(x.tex, None);
+        assert_eq!(x.errors().next().unwrap().range, 2..3);
+        assert!(matches!(x.error, Some(Error::Tokenize(_))));
+        assert!(convert("<foo>", &safe).tex.is_none());
+        assert!(convert("<foo>", &Default::default()).tex.is_some());
+    }
+
+    #[test]
+    fn test_convert() {
+        let options = ConvertOptions::default();
+        let x = convert("a b  c", &options);
+        assert_eq!(x.tex.as_deref(), Some("abc"));
+        assert_eq!(x.warnings().count(), 0);
+        let x = convert("α ^b ^c", &options);
+        assert_eq!(x.tex, None);
+        assert_eq!(x.errors().next().unwrap().range, 5..7);
+        assert_eq!(x.fixes[0].input, "α  ^b ^c");
+        let suggestion = x.errors().next().unwrap().suggestion.as_ref().unwrap();
+        assert_eq!(suggestion.apply("α ^b ^c"), "α  ^b ^c");
+        assert!(matches!(x.error, Some(Error::Parse(_))));
+        let x = convert("a `zz", &options);
+        assert!(x.tokens.is_empty());
+        assert!(matches!(x.error, Some(Error::Tokenize(_))));
+        assert_eq!(x.errors().next().unwrap().range, 2..3);
+        let x = convert(
+            "a  _ b",
+            &ConvertOptions {
+                lint: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(x.tex.as_deref(), Some("a_{b}"));
+        assert_eq!(x.warnings().count(), 2);
+        let x = convert(
+            "a b  c",
+            &ConvertOptions {
+                lint: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(x.warnings().count(), 1);
+        let x = convert(
+            "a^b^c /",
+            &ConvertOptions {
+                recover: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            x.tex.as_deref(),
+            Some(r"a^{b}\frac{\boxed{?}^{c}}{\boxed{?}}")
+        );
+        let errors = x.errors().map(|x| x.range.clone()).collect::<Vec<_>>();
+        assert_eq!(errors, [3..4, 7..7]);
+        assert!(x.errors().nth(1).unwrap().suggestion.is_none());
+        let options = ConvertOptions {
+            syntax: Syntax::AsciiMath,
+            ..Default::default()
+        };
+        assert_eq!(
+            convert("(a+b)/c", &options).tex.as_deref(),
+            Some(r"\frac{a+b}{c}")
+        );
+        assert_eq!(convert("a_", &options).diagnostics[0].range, 2..2);
     }
 }
@@ -0,0 +1,345 @@
+//! Opt-in warnings about spacing that is valid but rarely means what it seems to.
+//!
+//! The tokenizer only keeps the wider side of the spaces around an operator and
+//! ignores the spaces inside brackets, so differences in them are silently lost.
+//! Every warning comes with a rewrite that keeps the grouping and shows it.
+
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+use crate::diagnostic::Diagnostic;
+use crate::error::Error;
+use crate::maspace_to_tex;
+use crate::token::{tokenize_with_spans, Token};
+
+/// A concatenation or binary operator as written.
+struct Separator<'a> {
+    range: Range<usize>,
+    /// Empty for a concatenation.
+    op: &'a str,
+    left: usize,
+    right: usize,
+    /// Depth of the brackets around it.
+    depth: usize,
+}
+
+impl Separator<'_> {
+    fn is_cat(&self) -> bool {
+        self.op.is_empty()
+    }
+
+    /// Spaces that decide the order of the separator.
+    fn width(&self) -> usize {
+        self.left.max(self.right)
+    }
+
+    fn respaced(&self, left: usize, right: usize) -> String {
+        format!("{}{}{}", " ".repeat(left), self.op, " ".repeat(right))
+    }
+}
+
+fn separators<'a>(input: &'a str, tokens: &[(Token, Range<usize>)]) -> Vec<Separator<'a>> {
+    let mut result = vec![];
+    let mut depth: usize = 0;
+    for (token, range) in tokens {
+        match token {
+            Token::Open(_) => depth += 1,
+            // an unmatched close is left to the parser
+            Token::Close(_) => depth = depth.saturating_sub(1),
+            // virtual tokens and cats between connected symbols are not written
+            _ if range.is_empty() => {}
+            Token::Cat(_)
+            | Token::Sub(_)
+            | Token::Sup(_)
+            | Token::Over(_)
+            | Token::Under(_)
+            | Token::Root(_)
+            | Token::Frac(_) => {
+                let text = &input[range.clone()];
+                let op = text.trim_matches(' ');
+                let left = text.len() - text.trim_start_matches(' ').len();
+                result.push(Separator {
+                    range: range.clone(),
+                    op,
+                    left,
+                    right: text.len() - left - op.len(),
+                    depth,
+                });
+            }
+            _ => {}
+        }
+    }
+    result
+}
+
+/// Warns about operators with spaces of different widths on both sides.
+fn asymmetric(separators: &[Separator<'_>]) -> Vec<Diagnostic> {
+    separators
+        .iter()
+        .filter(|x| !x.is_cat() && x.right > 0 && x.left != x.right)
+        .map(|x| {
+            Diagnostic::warning(
+                x.range.clone(),
+                format!(
+                    "`{}` has {} space(s) before and {} after, but only the wider side counts",
+                    x.op, x.left, x.right
+                ),
+                x.respaced(x.width(), 0),
+            )
+        })
+        .collect()
+}
+
+/// Warns about a level of spaces with no used level right below it, such as the 2 spaces of `a+b  /c`.
+///
+/// All separators and operators on the level are respaced in one suggestion, which is
+/// only made if it gives the same TeX.
+fn skipped_levels(
+    input: &str,
+    tokens: &[(Token, Range<usize>)],
+    separators: &[Separator<'_>],
+) -> Vec<Diagnostic> {
+    let Ok(tex) = maspace_to_tex(input) else {
+        return vec![];
+    };
+    // an operator takes its order from the spaces after it
+    let operators = tokens
+        .iter()
+        .filter(|(token, _)| matches!(token, Token::Op(..)))
+        .map(|(_, range)| {
+            let text = &input[range.clone()];
+            let op = text.trim_end_matches(' ');
+            Separator {
+                range: range.clone(),
+                op,
+                left: 0,
+                right: text.len() - op.len(),
+                depth: 0,
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut spaced = separators
+        .iter()
+        .chain(&operators)
+        .filter(|x| x.width() > 0)
+        .collect::<Vec<_>>();
+    spaced.sort_by_key(|x| x.range.start);
+    let used = spaced.iter().map(|x| x.width()).collect::<BTreeSet<_>>();
+    let mut result = vec![];
+    let mut below = 0;
+    for &level in &used {
+        let width = below + 1;
+        below = level;
+        if width == level {
+            continue;
+        }
+        let on_level = spaced
+            .iter()
+            .filter(|x| x.width() == level)
+            .collect::<Vec<_>>();
+        let range = on_level[0].range.start..on_level[on_level.len() - 1].range.end;
+        let mut replacement = String::new();
+        let mut end = range.start;
+        for x in on_level {
+            replacement += &input[end..x.range.start];
+            replacement += &x.respaced(
+                if x.left > 0 { width } else { 0 },
+                if x.right > 0 { width } else { 0 },
+            );
+            end = x.range.end;
+        }
+        let diagnostic = Diagnostic::warning(
+            range,
+            format!(
+                "{} space(s) skip an unused level: {} group the same",
+                level, width
+            ),
+            replacement,
+        );
+        let rewritten = diagnostic.suggestion.as_ref().map(|x| x.apply(input));
+        if rewritten.and_then(|x| maspace_to_tex(&x).ok()).as_ref() == Some(&tex) {
+            result.push(diagnostic);
+        }
+    }
+    result
+}
+
+/// Warns about concatenations written with spaces of different widths.
+///
+/// Every cat with spaces is widened to the outermost level, so `a b  c` groups
+/// no differently from `a b c` and the narrower spaces have no effect.
+fn uneven_cats(separators: &[Separator<'_>]) -> Vec<Diagnostic> {
+    let cats = separators
+        .iter()
+        .filter(|x| x.is_cat() && x.width() > 0)
+        .collect::<Vec<_>>();
+    let widest = cats.iter().map(|x| x.width()).max().unwrap_or_default();
+    cats.into_iter()
+        .filter(|x| x.width() < widest)
+        .map(|x| {
+            Diagnostic::warning(
+                x.range.clone(),
+                format!(
+                    "{} space(s) between terms act like {}: use brackets to group terms",
+                    x.width(),
+                    widest
+                ),
+                x.respaced(widest, 0),
+            )
+        })
+        .collect()
+}
+
+/// Warns about spaces after an opening or before a closing bracket.
+fn spaces_in_brackets(input: &str, tokens: &[(Token, Range<usize>)]) -> Vec<Diagnostic> {
+    tokens
+        .iter()
+        .filter_map(|(token, range)| {
+            let text = &input[range.clone()];
+            let trimmed = match token {
+                Token::Open(_) => text.trim_end_matches(' '),
+                Token::Close(_) => text.trim_start_matches(' '),
+                _ => return None,
+            };
+            (trimmed.len() < text.len()).then(|| {
+                Diagnostic::warning(
+                    range.clone(),
+                    "spaces inside brackets are ignored".to_string(),
+                    trimmed.to_string(),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Warns about concatenations next to an operator with as many spaces or more.
+///
+/// The operator binds tighter anyway, so `a b ^c` is `a (b^c)` as if written `a  b ^c`.
+fn cat_beside_operator(separators: &[Separator<'_>]) -> Vec<Diagnostic> {
+    let mut result = vec![];
+    // the last separator at each depth of brackets
+    let mut last: Vec<Option<&Separator<'_>>> = vec![None];
+    for x in separators {
+        last.resize(x.depth + 1, None);
+        if let Some(prev) = last[x.depth] {
+            let pair = match (prev.is_cat(), x.is_cat()) {
+                (true, false) => Some((prev, x)),
+                (false, true) => Some((x, prev)),
+                _ => None,
+            };
+            if let Some((cat, op)) = pair.filter(|(cat, op)| cat.width() <= op.width()) {
+                result.push(Diagnostic::warning(
+                    cat.range.clone(),
+                    format!(
+                        "`{}` with {} space(s) binds tighter than this concatenation with {}",
+                        op.op,
+                        op.width(),
+                        cat.width()
+                    ),
+                    " ".repeat(op.width() + 1),
+                ));
+            }
+        }
+        last[x.depth] = Some(x);
+    }
+    result
+}
+
+pub(crate) fn lint_tokens(input: &str, tokens: &[(Token, Range<usize>)]) -> Vec<Diagnostic> {
+    let separators = separators(input, tokens);
+    let mut result = asymmetric(&separators);
+    result.extend(skipped_levels(input, tokens, &separators));
+    result.extend(uneven_cats(&separators));
+    result.extend(spaces_in_brackets(input, tokens));
+    result.extend(cat_beside_operator(&separators));
+    result.sort_by_key(|x| (x.range.start, x.range.end));
+    result
+}
+
+/// Returns the spacing warnings of a maSpace `input`, each with a suggested rewrite.
+pub fn lint(input: &str) -> Result<Vec<Diagnostic>, Error> {
+    Ok(lint_tokens(input, &tokenize_with_spans(input)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixed(input: &str) -> Vec<(String, String)> {
+        lint(input)
+            .unwrap()
+            .into_iter()
+            .map(|x| (x.message, x.suggestion.unwrap().apply(input)))
+            .collect()
+    }
+
+    #[test]
+    fn test_lint() {
+        for input in [
+            "a+b /c",
+            "a b c",
+            "x_i^2",
+            "a _b ^c",
+            "a _ b",
+            "a  b ^c",
+            "x_i^2 + y",
+            "a_b c",
+            "a /b_c",
+        ] {
+            assert_eq!(fixed(input), [], "{}", input);
+        }
+        assert_eq!(
+            fixed("a  _ b c")[0],
+            (
+                "`_` has 2 space(s) before and 1 after, but only the wider side counts".to_string(),
+                "a  _b c".to_string()
+            )
+        );
+        assert_eq!(fixed("a_ b")[0].1, "a _b");
+        assert_eq!(
+            fixed("a+b  /c"),
+            [(
+                "2 space(s) skip an unused level: 1 group the same".to_string(),
+                "a+b /c".to_string()
+            )]
+        );
+        // every separator and operator on the level is respaced together
+        assert_eq!(fixed("<'sqrt>  b  /+")[0].1, "<'sqrt> b /+");
+        assert_eq!(fixed("c  _c  /b")[0].1, "c _c /b");
+        assert_eq!(fixed("a  /+  ^c")[0].1, "a /+ ^c");
+        assert_eq!(
+            fixed("a   b /c"),
+            [(
+                "3 space(s) skip an unused level: 2 group the same".to_string(),
+                "a  b /c".to_string()
+            )]
+        );
+        // unspaced separators are on no level
+        assert_eq!(fixed("a_b  /c")[0].1, "a_b /c");
+        assert_eq!(fixed("(a+b )")[0].1, "(a+b)");
+        assert_eq!(fixed("x^[ a+b]")[0].1, "x^[a+b]");
+        assert_eq!(
+            fixed("a b ^c"),
+            [(
+                "`^` with 1 space(s) binds tighter than this concatenation with 1".to_string(),
+                "a  b ^c".to_string()
+            )]
+        );
+        assert_eq!(fixed("a b  /c")[0].1, "a   b  /c");
+        // only separators in the same brackets are beside each other
+        assert_eq!(fixed("a (b ^c)"), []);
+        assert_eq!(
+            fixed("a b  c"),
+            [(
+                "1 space(s) between terms act like 2: use brackets to group terms".to_string(),
+                "a  b  c".to_string()
+            )]
+        );
+        // unbalanced brackets are reported by the parser, not here
+        assert_eq!(fixed("a)"), []);
+        assert_eq!(fixed("]^"), []);
+        let warnings = lint("a  ^ b").unwrap();
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].range, 1..5);
+    }
+}
@@ -1,15 +1,623 @@
-use maspace::maspace_to_tex;
+mod repl;
+mod serve;
 
-use std::io;
+use maspace::asciimath::parse_asciimath;
+use maspace::diagnostic::Severity;
+use maspace::explain::explain;
+use maspace::expr::{parse, Math};
+use maspace::fix::fixes;
+use maspace::latex::convert_latex;
+use maspace::lint::lint;
+use maspace::markdown::convert_markdown;
+use maspace::omml::omml;
+use maspace::pretty::pretty;
+use maspace::printer::{print, Notation};
+use maspace::token::tokenize;
+use maspace::unicode::unicode_text;
+use maspace::{
+    tex_to_maspace, tex_to_maspace_spellings, Conversion, ConvertOptions, Error, Syntax,
+};
 
-use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::ops::Range;
+use std::process::ExitCode;
+use std::{env, fs};
 
-fn main() -> Result<()> {
-    let mut buffer = String::new();
-    io::stdin()
-        .read_line(&mut buffer)
-        .context("failed to read stdin")?;
-    let result = maspace_to_tex(&buffer)?;
-    println!("{}", result);
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+
+const USAGE: &str = "\
+usage: maspace [OPTIONS] [FILE...]
+       maspace --from-tex <TEX>
+       maspace --to-maspace <TEX>
+       maspace repl
+       maspace serve [--address <ADDR>] [--max-body <BYTES>] [--max-connections <N>]
+
+Converts every line of the files, or of stdin without files or with `-`.
+
+options:
+  --asciimath         read AsciiMath instead of maSpace
+  --blocks            convert blocks separated by blank lines, joining their lines by a space
+  --explain           draw how each input is grouped instead of converting it
+  --format <FORMAT>   output `tex` (default), `maspace`, `unicode` text such as `√(a+b)`,
+                      `pretty` (`pretty-ascii`) with fractions and scripts in rows, or `omml` for Word
+  --lint              warn on stderr about misleading spacing of maSpace input, with a fix
+  --latex             rewrite LaTeX, converting `\\maspace{..}` and maspace environments
+  --markdown          rewrite Markdown, converting `$..$`, `$$..$$` and ```maspace blocks
+  --jsonl             read `{\"id\", \"input\"}` per line and write `{\"id\", \"tex\" | \"error\"}`
+  -o <PATH>           write to PATH instead of stdout
+
+exit status:
+  0 success, 1 I/O error, 2 usage error, 3 tokenize failure, 4 parse failure, 5 print failure;
+  a --jsonl record that is not JSON or lacks a string `input` is a parse failure";
+
+const EXIT_IO: u8 = 1;
+const EXIT_USAGE: u8 = 2;
+const EXIT_TOKENIZE: u8 = 3;
+const EXIT_PARSE: u8 = 4;
+const EXIT_PRINT: u8 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Tex,
+    Maspace,
+    Unicode,
+    Pretty(Notation),
+    Omml,
+    Explain,
+}
+
+impl Format {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Tex => "tex",
+            Self::Maspace => "maspace",
+            Self::Unicode => "unicode",
+            Self::Pretty(_) => "pretty",
+            Self::Omml => "omml",
+            Self::Explain => "explain",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Document {
+    Markdown,
+    Latex,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Options {
+    syntax: Syntax,
+    format: Format,
+    blocks: bool,
+    jsonl: bool,
+    lint: bool,
+    /// Documents with maSpace in them to rewrite, instead of a formula per line.
+    document: Option<Document>,
+    output: Option<String>,
+    files: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    Convert(Options),
+    FromTex(String),
+    ToMaspace(String),
+    Repl,
+    Serve(serve::ServeOptions),
+}
+
+fn parse_args(args: &[String]) -> Result<Command> {
+    match args {
+        [flag, tex] if flag == "--from-tex" => return Ok(Command::FromTex(tex.clone())),
+        [flag, tex] if flag == "--to-maspace" => return Ok(Command::ToMaspace(tex.clone())),
+        [command] if command == "repl" => return Ok(Command::Repl),
+        [command, args @ ..] if command == "serve" => {
+            return Ok(Command::Serve(serve::parse_args(args)?))
+        }
+        _ => {}
+    }
+    let mut options = Options {
+        syntax: Syntax::Maspace,
+        format: Format::Tex,
+        blocks: false,
+        jsonl: false,
+        lint: false,
+        document: None,
+        output: None,
+        files: vec![],
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--asciimath" => options.syntax = Syntax::AsciiMath,
+            "--blocks" => options.blocks = true,
+            "--explain" => options.format = Format::Explain,
+            "--jsonl" => options.jsonl = true,
+            "--lint" => options.lint = true,
+            "--latex" => options.document = Some(Document::Latex),
+            "--markdown" => options.document = Some(Document::Markdown),
+            "--format" => {
+                options.format = match args.next().map(String::as_str) {
+                    Some("tex") => Format::Tex,
+                    Some("maspace") => Format::Maspace,
+                    Some("unicode") => Format::Unicode,
+                    Some("pretty") => Format::Pretty(Notation::Unicode),
+                    Some("pretty-ascii") => Format::Pretty(Notation::Ascii),
+                    Some("omml") => Format::Omml,
+                    Some(x) => bail!("unknown format \"{}\"", x),
+                    None => bail!("missing format"),
+                }
+            }
+            "-o" => options.output = Some(args.next().context("missing output path")?.clone()),
+            x if x.starts_with("--") => bail!("unknown option \"{}\"", x),
+            x => options.files.push(x.to_string()),
+        }
+    }
+    if options.blocks && options.jsonl {
+        bail!("--blocks and --jsonl cannot be used together");
+    }
+    if options.document.is_some()
+        && (options.blocks || options.jsonl || options.format != Format::Tex)
+    {
+        bail!("--latex and --markdown only convert to TeX, one document per file");
+    }
+    Ok(Command::Convert(options))
+}
+
+fn exit_code(error: &Error) -> u8 {
+    match error {
+        Error::Tokenize(_) => EXIT_TOKENIZE,
+        Error::Parse(_) | Error::AsciiMath(_) | Error::Tex(_) => EXIT_PARSE,
+        Error::Print(_) => EXIT_PRINT,
+    }
+}
+
+fn convert(input: &str, options: &Options) -> Result<String, Error> {
+    if options.format == Format::Explain {
+        // AsciiMath is explained through its maSpace spelling
+        return match options.syntax {
+            Syntax::Maspace => explain(input),
+            Syntax::AsciiMath => explain(&print(&parse_asciimath(input)?)?),
+        }
+        .map(|x| x.trim_end().to_owned());
+    }
+    let math: Math = match options.syntax {
+        Syntax::Maspace => parse(&tokenize(input)?)?,
+        Syntax::AsciiMath => parse_asciimath(input)?,
+    };
+    Ok(match options.format {
+        Format::Tex => math.to_string().trim_end_matches(' ').to_owned(),
+        Format::Maspace => print(&math)?,
+        Format::Unicode => unicode_text(&math),
+        Format::Pretty(notation) => pretty(&math, notation),
+        Format::Omml => omml(&math),
+        Format::Explain => unreachable!(),
+    })
+}
+
+/// Splits `text` into the inputs to convert, each with its line number.
+fn inputs(text: &str, blocks: bool) -> Vec<(usize, String)> {
+    let lines = text.lines().map(|x| x.trim_end_matches('\r'));
+    if !blocks {
+        return lines
+            .enumerate()
+            .map(|(i, x)| (i + 1, x.to_string()))
+            .collect();
+    }
+    let mut result: Vec<(usize, String)> = vec![];
+    let mut in_block = false;
+    for (i, line) in lines.enumerate() {
+        match (line.trim().is_empty(), in_block) {
+            (true, _) => in_block = false,
+            (false, false) => {
+                result.push((i + 1, line.trim().to_string()));
+                in_block = true;
+            }
+            (false, true) => {
+                let block = &mut result.last_mut().unwrap().1;
+                block.push(' ');
+                block.push_str(line.trim());
+            }
+        }
+    }
+    result
+}
+
+/// Converts the text of one source, writing a line per input and returning the first failure's exit code.
+fn convert_text(
+    name: &str,
+    text: &str,
+    options: &Options,
+    out: &mut impl Write,
+    err: &mut impl Write,
+) -> io::Result<Option<u8>> {
+    if let Some(document) = options.document {
+        return convert_document(name, text, document, options, out, err);
+    }
+    let mut failure = None;
+    for (line, input) in inputs(text, options.blocks) {
+        if options.jsonl {
+            if input.trim().is_empty() {
+                continue;
+            }
+            let (record, code) = convert_record(&input, options);
+            writeln!(out, "{}", record)?;
+            failure = failure.or(code);
+            continue;
+        }
+        if input.trim().is_empty() {
+            writeln!(out)?;
+            continue;
+        }
+        if options.lint && options.syntax == Syntax::Maspace {
+            write_lints(name, line, &input, err)?;
+        }
+        match convert(&input, options) {
+            Ok(x) => writeln!(out, "{}", x)?,
+            Err(e) => {
+                writeln!(out)?;
+                writeln!(err, "{}:{}: {}", name, line, e.to_string().trim_end())?;
+                if let (Syntax::Maspace, Error::Parse(_)) = (options.syntax, &e) {
+                    for x in fixes(&input) {
+                        writeln!(err, "  help: write `{}` for {}", x.input, x.tex)?;
+                    }
+                }
+                failure = failure.or(Some(exit_code(&e)));
+            }
+        }
+    }
+    Ok(failure)
+}
+
+/// Line and column numbers, from 1, of the byte offset `offset` of `text`.
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let start = text[..offset].rfind('\n').map_or(0, |x| x + 1);
+    (
+        text[..start].matches('\n').count() + 1,
+        text[start..offset].chars().count() + 1,
+    )
+}
+
+/// Converts the formulas of a document, writing the document and returning the first failure's exit code.
+///
+/// Diagnostics are written as `name:line:column: severity: ...` with the line and column in the document.
+fn convert_document(
+    name: &str,
+    text: &str,
+    document: Document,
+    options: &Options,
+    out: &mut impl Write,
+    err: &mut impl Write,
+) -> io::Result<Option<u8>> {
+    let convert_options = ConvertOptions {
+        syntax: options.syntax,
+        lint: options.lint,
+        ..Default::default()
+    };
+    // the converted document and each formula with its range
+    let (converted, formulas): (String, Vec<(Range<usize>, Conversion)>) = match document {
+        Document::Markdown => {
+            let x = convert_markdown(text, &convert_options);
+            let formulas = x.formulas.into_iter().map(|(x, y)| (x.range, y)).collect();
+            (x.text, formulas)
+        }
+        Document::Latex => {
+            let x = convert_latex(text, &convert_options);
+            let formulas = x.formulas.into_iter().map(|(x, y)| (x.range, y)).collect();
+            (x.text, formulas)
+        }
+    };
+    let mut failure = None;
+    for (range, formula) in &formulas {
+        let input = &text[range.clone()];
+        for diagnostic in &formula.diagnostics {
+            let (line, column) = line_column(text, range.start + diagnostic.range.start);
+            let severity = match diagnostic.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            };
+            writeln!(
+                err,
+                "{}:{}:{}: {}: {}",
+                name, line, column, severity, diagnostic.message
+            )?;
+            if let Some(x) = &diagnostic.suggestion {
+                writeln!(err, "  help: write `{}`", x.apply(input))?;
+            }
+        }
+        if formula.tex.is_none() {
+            failure = failure.or(formula.error.as_ref().map(exit_code));
+        }
+    }
+    write!(out, "{}", converted)?;
+    Ok(failure)
+}
+
+/// Writes the spacing warnings of `input` as `name:line:column: warning: ...` with the fixed input.
+fn write_lints(name: &str, line: usize, input: &str, err: &mut impl Write) -> io::Result<()> {
+    // tokenize failures are reported by the conversion
+    for warning in lint(input).unwrap_or_default() {
+        let column = input[..warning.range.start].chars().count() + 1;
+        writeln!(
+            err,
+            "{}:{}:{}: warning: {}",
+            name, line, column, warning.message
+        )?;
+        if let Some(x) = &warning.suggestion {
+            writeln!(err, "  help: write `{}`", x.apply(input))?;
+        }
+    }
     Ok(())
 }
+
+/// Converts a JSON-lines record `{"id", "input"}` into `{"id", <format>}` or `{"id", "error"}`.
+fn convert_record(record: &str, options: &Options) -> (Value, Option<u8>) {
+    let record = match serde_json::from_str::<Value>(record) {
+        Ok(x) => x,
+        Err(e) => {
+            let error = format!("invalid record: {}", e);
+            return (json!({"id": null, "error": error}), Some(EXIT_PARSE));
+        }
+    };
+    let id = record.get("id").cloned().unwrap_or(Value::Null);
+    let Some(input) = record.get("input").and_then(Value::as_str) else {
+        return (
+            json!({"id": id, "error": "missing string \"input\""}),
+            Some(EXIT_PARSE),
+        );
+    };
+    match convert(input, options) {
+        Ok(x) => (json!({"id": id, options.format.name(): x}), None),
+        Err(e) => (
+            json!({"id": id, "error": e.to_string().trim_end()}),
+            Some(exit_code(&e)),
+        ),
+    }
+}
+
+fn run(options: &Options) -> Result<Option<u8>> {
+    let mut out: Box<dyn Write> = match &options.output {
+        Some(path) => Box::new(BufWriter::new(
+            File::create(path).with_context(|| format!("failed to create {}", path))?,
+        )),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+    let mut err = io::stderr().lock();
+    let files = match options.files.is_empty() {
+        true => vec!["-".to_string()],
+        false => options.files.clone(),
+    };
+    let mut failure = None;
+    for file in files {
+        let (name, text) = match file.as_str() {
+            "-" => {
+                let mut text = String::new();
+                io::stdin()
+                    .read_to_string(&mut text)
+                    .context("failed to read stdin")?;
+                ("<stdin>".to_string(), text)
+            }
+            path => (
+                path.to_string(),
+                fs::read_to_string(path).with_context(|| format!("failed to read {}", path))?,
+            ),
+        };
+        let code = convert_text(&name, &text, options, &mut out, &mut err)?;
+        failure = failure.or(code);
+    }
+    out.flush()?;
+    Ok(failure)
+}
+
+fn main() -> ExitCode {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let command = match parse_args(&args) {
+        Ok(x) => x,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+    let result = match command {
+        Command::FromTex(tex) => {
+            tex_to_maspace_spellings(&tex)
+                .into_iter()
+                .for_each(|x| println!("{}", x));
+            Ok(())
+        }
+        Command::ToMaspace(tex) => tex_to_maspace(&tex).map(|x| println!("{}", x)),
+        Command::Repl => {
+            return match repl::run() {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!("Error: {:?}", e);
+                    ExitCode::from(EXIT_IO)
+                }
+            };
+        }
+        Command::Serve(options) => {
+            return match serve::run(&options) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!("Error: {:?}", e);
+                    ExitCode::from(EXIT_IO)
+                }
+            };
+        }
+        Command::Convert(options) => {
+            return match run(&options) {
+                Ok(failure) => ExitCode::from(failure.unwrap_or(0)),
+                Err(e) => {
+                    eprintln!("Error: {:?}", e);
+                    ExitCode::from(EXIT_IO)
+                }
+            };
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e.to_string().trim_end());
+            ExitCode::from(exit_code(&e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(args: &[&str]) -> Options {
+        let args = args.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        match parse_args(&args).unwrap() {
+            Command::Convert(x) => x,
+            _ => unreachable!(),
+        }
+    }
+
+    fn x(text: &str, args: &[&str]) -> (String, String, Option<u8>) {
+        let (mut out, mut err) = (vec![], vec![]);
+        let code = convert_text("in", text, &options(args), &mut out, &mut err).unwrap();
+        (
+            String::from_utf8(out).unwrap(),
+            String::from_utf8(err).unwrap(),
+            code,
+        )
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = [
+            "--asciimath",
+            "--format",
+            "maspace",
+            "-o",
+            "out.tex",
+            "a",
+            "b",
+        ];
+        let x = options(&args);
+        assert_eq!(x.syntax, Syntax::AsciiMath);
+        assert_eq!(x.format, Format::Maspace);
+        assert_eq!(x.output.as_deref(), Some("out.tex"));
+        assert_eq!(x.files, ["a", "b"]);
+        let args = ["--format", "html"].map(String::from);
+        assert!(parse_args(&args).is_err());
+        let args = ["--from-tex", r"\alpha"].map(String::from);
+        assert_eq!(
+            parse_args(&args).unwrap(),
+            Command::FromTex(r"\alpha".to_string())
+        );
+        let args = ["--to-maspace", r"\frac{a}{b}"].map(String::from);
+        assert_eq!(
+            parse_args(&args).unwrap(),
+            Command::ToMaspace(r"\frac{a}{b}".to_string())
+        );
+    }
+
+    #[test]
+    fn test_convert_text() {
+        assert_eq!(
+            x("a+b /c\n\nα^2\n", &[]),
+            (
+                "\\frac{a+b}{c}\n\n\\alpha^{2}\n".to_string(),
+                String::new(),
+                None
+            )
+        );
+        assert_eq!(x("a+b\n/c\n\nx\n", &["--blocks"]).0, "\\frac{a+b}{c}\nx\n");
+        let (out, err, code) = x("a\na^b^c\na `zz\n", &[]);
+        assert_eq!(out, "a\n\n\n");
+        assert!(err.starts_with("in:2: parse failed"));
+        assert!(
+            err.contains("  help: write `a ^b^c` for a^{b^{c}}\n"),
+            "{}",
+            err
+        );
+        assert_eq!(code, Some(4));
+        assert_eq!(x("a `zz\na^b^c\n", &[]).2, Some(3));
+        assert_eq!(
+            x("(a+b)/c", &["--asciimath", "--format", "maspace"]).0,
+            "a+b /c\n"
+        );
+        assert_eq!(x("a+b /c\n", &["--format", "unicode"]).0, "(a+b)/c\n");
+        assert_eq!(
+            x("a+b /c\n", &["--format", "pretty-ascii"]).0,
+            "a+b\n---\n c\n"
+        );
+        let (out, _, _) = x("a /b\n", &["--format", "omml"]);
+        assert!(out.starts_with("<m:oMath xmlns:m="), "{}", out);
+        assert!(out.ends_with("</m:den></m:f></m:oMath>\n"), "{}", out);
+        let (out, _, _) = x("a+b /c\n", &["--explain"]);
+        assert!(out.starts_with("a+b␣/c\n\"a\" Cat(0)"), "{}", out);
+        assert!(out.contains("---stack1---"), "{}", out);
+        let (out, err, code) = x("a  _ b c\n", &["--lint"]);
+        assert_eq!((out.as_str(), code), ("a_{b}c\n", None));
+        assert!(
+            err.starts_with("in:1:2: warning: `_` has 2 space(s) before and 1 after")
+                && err.contains("  help: write `a  _b c`\n"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_convert_markdown() {
+        let text = "# Notes\n\nLet $a+b /c$ and `$x$`,\nbut $x_i$ costs \\$5 and $$a^b^c$$.\n";
+        let (out, err, code) = x(text, &["--markdown"]);
+        assert_eq!(
+            out,
+            "# Notes\n\nLet $\\frac{a+b}{c}$ and `$x$`,\nbut $x_{i}$ costs \\$5 and $$a^b^c$$.\n"
+        );
+        assert!(
+            err.starts_with("in:4:30: error: Double exponent")
+                && err.contains("  help: write `a ^b^c`\n"),
+            "{}",
+            err
+        );
+        assert_eq!(code, Some(4));
+        assert_eq!(x("```maspace\nα `zz\n```\n", &["--markdown"]).2, Some(3));
+        assert!(x("a\n", &["--markdown"]).0 == "a\n");
+        let args = ["--markdown", "--jsonl"].map(String::from);
+        assert!(parse_args(&args).is_err());
+        let text = "See \\maspace{a+b /c}\n% \\maspace{a^b^c}\n\\maspace{a^b^c}\n";
+        let (out, err, code) = x(text, &["--latex"]);
+        assert_eq!(
+            out,
+            "See \\ensuremath{\\frac{a+b}{c}}\n% \\maspace{a^b^c}\n\\maspace{a^b^c}\n"
+        );
+        assert!(
+            err.starts_with("in:3:13: error: Double exponent"),
+            "{}",
+            err
+        );
+        assert_eq!(code, Some(4));
+    }
+
+    #[test]
+    fn test_convert_jsonl() {
+        let (out, _, code) = x(
+            "{\"id\": 1, \"input\": \"a+b /c\"}\n{\"id\": \"q2\", \"input\": \"a^b^c\"}\n",
+            &["--jsonl"],
+        );
+        let mut lines = out
+            .lines()
+            .map(|x| serde_json::from_str::<Value>(x).unwrap());
+        assert_eq!(
+            lines.next().unwrap(),
+            json!({"id": 1, "tex": r"\frac{a+b}{c}"})
+        );
+        let second = lines.next().unwrap();
+        assert_eq!(second["id"], "q2");
+        assert!(second["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("parse failed"));
+        assert_eq!(code, Some(4));
+        assert_eq!(x("not json\n", &["--jsonl"]).2, Some(EXIT_PARSE));
+        assert_eq!(x("{\"id\": 1}\n", &["--jsonl"]).2, Some(EXIT_PARSE));
+    }
+}
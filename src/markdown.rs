@@ -0,0 +1,236 @@
+//! maSpace math in Markdown documents.
+//!
+//! Formulas are written in `$..$` and `$$..$$` on a line, or one per line in fenced
+//! blocks marked `maspace`. Code spans, other fenced blocks and `\$` are left alone.
+
+use std::ops::Range;
+
+use crate::{convert, Conversion, ConvertOptions};
+
+/// How a formula is written in the document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MathKind {
+    /// `$..$`
+    Inline,
+    /// `$$..$$`
+    Display,
+    /// A line of the fenced `maspace` block at `block`, fences and final line break included.
+    Fenced { block: Range<usize> },
+}
+
+/// A formula in a Markdown document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MathSpan {
+    pub kind: MathKind,
+    /// Byte range of the maSpace, without delimiters.
+    pub range: Range<usize>,
+}
+
+/// Byte ranges of the lines of `text` without their line breaks, each with the start of the next.
+fn lines(text: &str) -> impl Iterator<Item = (Range<usize>, usize)> + '_ {
+    let mut start = 0;
+    text.split_inclusive('\n').map(move |line| {
+        let range = start..start + line.trim_end_matches(['\n', '\r']).len();
+        start += line.len();
+        (range, start)
+    })
+}
+
+/// Byte ranges of the formulas of a line outside fenced blocks, with whether they are `$$..$$`.
+fn inline_spans(line: &str) -> Vec<(Range<usize>, bool)> {
+    let mut result = vec![];
+    let mut i = 0;
+    while let Some(c) = line[i..].chars().next() {
+        let rest = &line[i..];
+        if c == '\\' {
+            i += rest.chars().take(2).map(char::len_utf8).sum::<usize>();
+        } else if c == '`' {
+            // skip the code span up to the same number of backticks
+            let ticks = &rest[..rest.len() - rest.trim_start_matches('`').len()];
+            i += match rest[ticks.len()..].find(ticks) {
+                Some(end) => 2 * ticks.len() + end,
+                None => ticks.len(),
+            };
+        } else if c == '$' {
+            let delimiter = if rest.starts_with("$$") { "$$" } else { "$" };
+            let start = i + delimiter.len();
+            match line[start..].find(delimiter) {
+                Some(end) if end > 0 => {
+                    result.push((start..start + end, delimiter == "$$"));
+                    i = start + end + delimiter.len();
+                }
+                _ => i = start,
+            }
+        } else {
+            i += c.len_utf8();
+        }
+    }
+    result
+}
+
+/// Returns the formulas of a Markdown `text`, in order.
+///
+/// Blank lines of fenced blocks are not formulas. A fenced block left open runs to the end.
+pub fn math_spans(text: &str) -> Vec<MathSpan> {
+    let mut result = vec![];
+    // the fence of the code block we are in, whether it is maSpace, and where it starts
+    let mut fence: Option<(&str, bool, usize)> = None;
+    // the lines of the maSpace block we are in
+    let mut formulas = vec![];
+    for (line, next) in lines(text) {
+        let content = &text[line.clone()];
+        let trimmed = content.trim_start();
+        let marker = ["```", "~~~"].into_iter().find(|x| trimmed.starts_with(x));
+        match (fence, marker) {
+            (Some((x, _, start)), Some(y)) if x == y && trimmed.trim_end() == x => {
+                let block = start..next;
+                result.extend(formulas.drain(..).map(|range| MathSpan {
+                    kind: MathKind::Fenced {
+                        block: block.clone(),
+                    },
+                    range,
+                }));
+                fence = None;
+            }
+            (Some((_, true, _)), _) if !content.trim().is_empty() => formulas.push(line),
+            (Some(_), _) => {}
+            (None, Some(x)) => {
+                let maspace = trimmed[x.len()..].trim() == "maspace";
+                fence = Some((x, maspace, line.start));
+            }
+            (None, None) => {
+                result.extend(
+                    inline_spans(content)
+                        .into_iter()
+                        .map(|(x, display)| MathSpan {
+                            kind: match display {
+                                true => MathKind::Display,
+                                false => MathKind::Inline,
+                            },
+                            range: line.start + x.start..line.start + x.end,
+                        }),
+                )
+            }
+        }
+    }
+    if let Some((_, _, start)) = fence {
+        let block = start..text.len();
+        result.extend(formulas.drain(..).map(|range| MathSpan {
+            kind: MathKind::Fenced {
+                block: block.clone(),
+            },
+            range,
+        }));
+    }
+    result
+}
+
+/// A Markdown document with its formulas converted.
+#[derive(Debug, Clone)]
+pub struct MarkdownConversion {
+    pub text: String,
+    /// Every formula with its conversion, whose ranges are relative to the formula.
+    pub formulas: Vec<(MathSpan, Conversion)>,
+}
+
+/// Replaces the formulas of a Markdown `text` with their TeX.
+///
+/// `$..$` and `$$..$$` keep their delimiters, and a fenced block becomes a `$$..$$` line
+/// per formula. Formulas that fail to convert are left as they are, and so is a
+/// fenced block with any of them.
+pub fn convert_markdown(text: &str, options: &ConvertOptions) -> MarkdownConversion {
+    let formulas = math_spans(text)
+        .into_iter()
+        .map(|x| {
+            let conversion = convert(&text[x.range.clone()], options);
+            (x, conversion)
+        })
+        .collect::<Vec<_>>();
+    let mut result = String::new();
+    let mut end = 0;
+    let mut i = 0;
+    while let Some((span, conversion)) = formulas.get(i) {
+        let (range, tex) = match &span.kind {
+            MathKind::Fenced { block } => {
+                let lines = formulas[i..]
+                    .iter()
+                    .take_while(|(x, _)| x.kind == span.kind)
+                    .collect::<Vec<_>>();
+                i += lines.len();
+                let tex = lines
+                    .iter()
+                    .map(|(_, x)| x.tex.as_ref().map(|x| format!("$${}$$\n", x)))
+                    .collect::<Option<String>>();
+                (block.clone(), tex)
+            }
+            _ => {
+                i += 1;
+                (span.range.clone(), conversion.tex.clone())
+            }
+        };
+        if let Some(tex) = tex {
+            result.push_str(&text[end..range.start]);
+            result.push_str(&tex);
+            end = range.end;
+        }
+    }
+    result.push_str(&text[end..]);
+    MarkdownConversion {
+        text: result,
+        formulas,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "\
+Let $x_i$ and `$a$` cost \\$5, $$a+b /c$$.
+```maspace
+α^2
+
+√x
+```
+```
+$y$
+```
+";
+
+    #[test]
+    fn test_math_spans() {
+        let spans = math_spans(TEXT);
+        let formulas = spans
+            .iter()
+            .map(|x| &TEXT[x.range.clone()])
+            .collect::<Vec<_>>();
+        assert_eq!(formulas, ["x_i", "a+b /c", "α^2", "√x"]);
+        assert_eq!(spans[1].kind, MathKind::Display);
+        let block = TEXT.find("```maspace").unwrap()..TEXT.find("```\n```").unwrap() + 4;
+        assert_eq!(spans[2].kind, MathKind::Fenced { block });
+        assert_eq!(math_spans("```maspace\na\n").len(), 1);
+    }
+
+    #[test]
+    fn test_convert_markdown() {
+        let x = convert_markdown(TEXT, &ConvertOptions::default());
+        assert_eq!(
+            x.text,
+            "\
+Let $x_{i}$ and `$a$` cost \\$5, $$\\frac{a+b}{c}$$.
+$$\\alpha^{2}$$
+$$\\sqrt{x}$$
+```
+$y$
+```
+"
+        );
+        let text = "$a^b^c$ and\n```maspace\nx\na /\n```\n";
+        let x = convert_markdown(text, &ConvertOptions::default());
+        assert_eq!(x.text, text);
+        assert_eq!(
+            x.formulas.iter().filter(|(_, x)| x.tex.is_none()).count(),
+            2
+        );
+    }
+}
@@ -0,0 +1,285 @@
+//! Office Math Markup Language (OMML) of formulas, for pasting into Word documents.
+//!
+//! Fractions become `m:f`, roots `m:rad`, scripts `m:sSub`/`m:sSup`/`m:sSubSup`, limits
+//! `m:limLow`/`m:limUpp` and brackets `m:d`. Symbols are written as their Unicode characters.
+
+use unicode_normalization::char::is_combining_mark;
+
+use crate::expr::{Frac, Inter, Math, Root, Simple, Stack};
+use crate::printer::split_command;
+use crate::token::reverse::unicode_of_tex;
+use crate::unicode::{combining, operator_prefix, symbol};
+
+const NAMESPACE: &str = "http://schemas.openxmlformats.org/officeDocument/2006/math";
+
+/// Renders `math` as an `m:oMath` element.
+pub fn omml(math: &Math) -> String {
+    format!(
+        "<m:oMath xmlns:m=\"{}\">{}</m:oMath>",
+        NAMESPACE,
+        self::math(math)
+    )
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// `<m:tag>content</m:tag>`
+fn element(tag: &str, content: &str) -> String {
+    format!("<m:{0}>{1}</m:{0}>", tag, content)
+}
+
+/// `<m:tag m:val="value"/>`
+fn property(tag: &str, value: &str) -> String {
+    format!("<m:{} m:val=\"{}\"/>", tag, escape(value))
+}
+
+/// A run of `text`, upright when `plain` as for names such as `sin`.
+fn run(text: &str, plain: bool) -> String {
+    let style = match plain {
+        true => element("rPr", &property("sty", "p")),
+        false => String::new(),
+    };
+    element("r", &format!("{}{}", style, element("t", &escape(text))))
+}
+
+fn math(x: &Math) -> String {
+    x.0.iter().map(root).collect()
+}
+
+fn root(x: &Root) -> String {
+    match x {
+        Root::Root { root, body } => radical(Some(&frac(root)), &frac(body)),
+        Root::Math { body } => frac(body),
+    }
+}
+
+/// `m:rad` with the degree `index`, hidden for a square root.
+fn radical(index: Option<&str>, body: &str) -> String {
+    let (properties, index) = match index {
+        Some(index) => (String::new(), index),
+        None => (element("radPr", &property("degHide", "1")), ""),
+    };
+    element(
+        "rad",
+        &format!(
+            "{}{}{}",
+            properties,
+            element("deg", index),
+            element("e", body)
+        ),
+    )
+}
+
+fn frac(x: &Frac) -> String {
+    match x {
+        Frac::Frac { nume, denom } => element(
+            "f",
+            &format!(
+                "{}{}",
+                element("num", &stack(nume)),
+                element("den", &stack(denom))
+            ),
+        ),
+        Frac::Math { body } => stack(body),
+    }
+}
+
+fn stack(x: &Stack) -> String {
+    let mut y = inter(x.body());
+    if let Some(under) = x.under() {
+        let limit = format!("{}{}", element("e", &y), element("lim", &inter(under)));
+        y = element("limLow", &limit);
+    }
+    if let Some(over) = x.over() {
+        let limit = format!("{}{}", element("e", &y), element("lim", &inter(over)));
+        y = element("limUpp", &limit);
+    }
+    y
+}
+
+fn inter(x: &Inter) -> String {
+    let body = simple(x.body());
+    let sub = x.sub().map(|x| element("sub", &simple(x)));
+    let sup = x.sup().map(|x| element("sup", &simple(x)));
+    let base = element("e", &body);
+    match (sub, sup) {
+        (None, None) => body,
+        (Some(sub), None) => element("sSub", &format!("{}{}", base, sub)),
+        (None, Some(sup)) => element("sSup", &format!("{}{}", base, sup)),
+        (Some(sub), Some(sup)) => element("sSubSup", &format!("{}{}{}", base, sub, sup)),
+    }
+}
+
+fn simple(x: &Simple) -> String {
+    match x {
+        Simple::UnarySymbol { operators, symbol } => apply(operators, self::symbol_run(symbol)),
+        Simple::UnaryParened {
+            operators,
+            open,
+            body,
+            close,
+        } => {
+            let base = close.trim_end_matches('\'');
+            let primes = "′".repeat(close.len() - base.len());
+            let mut y = match (open.is_empty(), base.is_empty()) {
+                // invisible brackets group without showing
+                (true, true) => math(body),
+                _ => delimiter(open, base, &math(body)),
+            };
+            if !primes.is_empty() {
+                y += &run(&primes, false);
+            }
+            apply(operators, y)
+        }
+        Simple::UnaryExpr { operators, body } => apply(operators, math(body)),
+    }
+}
+
+/// `m:d` between the brackets `open` and `close`, either of which may be empty.
+fn delimiter(open: &str, close: &str, body: &str) -> String {
+    let character = |tex: &str| match tex {
+        "" => String::new(),
+        tex => unicode_of_tex(tex).unwrap_or(tex.trim_start_matches('\\').to_string()),
+    };
+    let properties = format!(
+        "{}{}",
+        property("begChr", &character(open)),
+        property("endChr", &character(close))
+    );
+    element(
+        "d",
+        &format!("{}{}", element("dPr", &properties), element("e", body)),
+    )
+}
+
+/// A symbol as a run of its Unicode text, or an `m:acc` for an accent without a composed character.
+fn symbol_run(tex: &str) -> String {
+    let base = tex.trim_end_matches('\'').trim_end_matches(' ');
+    let primes = "′".repeat(tex.trim_end_matches(' ').len() - base.len());
+    let text = symbol(base);
+    let is_char = text.chars().filter(|c| !is_combining_mark(*c)).count() == 1;
+    let accented = split_command(base)
+        .filter(|_| !is_char)
+        .and_then(|(command, argument)| Some(accent(&combining(command)?, &symbol_run(argument))));
+    if let Some(accented) = accented {
+        return match primes.is_empty() {
+            true => accented,
+            false => accented + &run(&primes, false),
+        };
+    }
+    // names such as `sin` and string literals are upright
+    let plain = text.chars().filter(|c| c.is_alphabetic()).count() > 1;
+    run(&format!("{}{}", text, primes), plain)
+}
+
+/// `m:acc` putting the combining character `mark` over `body`.
+fn accent(mark: &str, body: &str) -> String {
+    element(
+        "acc",
+        &format!(
+            "{}{}",
+            element("accPr", &property("chr", mark)),
+            element("e", body)
+        ),
+    )
+}
+
+/// Applies `operators` to `operand`, the last one first.
+fn apply(operators: &[String], operand: String) -> String {
+    operators
+        .iter()
+        .rev()
+        .fold(operand, |x, operator| self::operator(operator, &x))
+}
+
+fn operator(tex: &str, operand: &str) -> String {
+    if tex == r"\sqrt" {
+        return radical(None, operand);
+    }
+    if let Some(index) = tex
+        .strip_prefix(r"\sqrt[")
+        .and_then(|x| x.strip_suffix(']'))
+    {
+        return radical(Some(&run(index, false)), operand);
+    }
+    let position = match tex {
+        r"\overline" => Some("top"),
+        r"\underline" => Some("bot"),
+        _ => None,
+    };
+    if let Some(position) = position {
+        let properties = element("barPr", &property("pos", position));
+        return element("bar", &format!("{}{}", properties, element("e", operand)));
+    }
+    if let Some(mark) = combining(tex) {
+        return accent(&mark, operand);
+    }
+    if let Some(prefix) = operator_prefix(tex) {
+        return format!("{}{}", run(&prefix, false), operand);
+    }
+    let name = run(tex.trim_start_matches('\\'), true);
+    element(
+        "func",
+        &format!("{}{}", element("fName", &name), element("e", operand)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::maspace_to_omml;
+
+    fn x(input: &str) -> String {
+        let y = maspace_to_omml(input).unwrap();
+        let y = y.strip_prefix("<m:oMath xmlns:m=\"").unwrap();
+        let start = y.find('>').unwrap() + 1;
+        y[start..].strip_suffix("</m:oMath>").unwrap().to_string()
+    }
+
+    #[test]
+    fn test_omml() {
+        assert_eq!(
+            x("a+b /c"),
+            "<m:f><m:num><m:r><m:t>a</m:t></m:r><m:r><m:t>+</m:t></m:r><m:r><m:t>b</m:t></m:r></m:num>\
+             <m:den><m:r><m:t>c</m:t></m:r></m:den></m:f>"
+        );
+        assert_eq!(
+            x("x_i^α"),
+            "<m:sSubSup><m:e><m:r><m:t>x</m:t></m:r></m:e><m:sub><m:r><m:t>i</m:t></m:r></m:sub>\
+             <m:sup><m:r><m:t>α</m:t></m:r></m:sup></m:sSubSup>"
+        );
+        assert_eq!(
+            x("√x"),
+            "<m:rad><m:radPr><m:degHide m:val=\"1\"/></m:radPr><m:deg></m:deg>\
+             <m:e><m:r><m:t>x</m:t></m:r></m:e></m:rad>"
+        );
+        assert_eq!(
+            x("3 _/x"),
+            "<m:rad><m:deg><m:r><m:t>3</m:t></m:r></m:deg><m:e><m:r><m:t>x</m:t></m:r></m:e></m:rad>"
+        );
+        assert_eq!(
+            x("<lim>__[n→∞]"),
+            "<m:limLow><m:e><m:r><m:rPr><m:sty m:val=\"p\"/></m:rPr><m:t>lim</m:t></m:r></m:e>\
+             <m:lim><m:r><m:t>n</m:t></m:r><m:r><m:t>→</m:t></m:r><m:r><m:t>∞</m:t></m:r></m:lim></m:limLow>"
+        );
+        assert_eq!(
+            x("⟨a⟩"),
+            "<m:d><m:dPr><m:begChr m:val=\"⟨\"/><m:endChr m:val=\"⟩\"/></m:dPr>\
+             <m:e><m:r><m:t>a</m:t></m:r></m:e></m:d>"
+        );
+        assert_eq!(x("<v bf hat>"), "<m:r><m:t>𝐯\u{302}</m:t></m:r>");
+        assert_eq!(
+            x("<'hat>[a+b]"),
+            "<m:acc><m:accPr><m:chr m:val=\"\u{302}\"/></m:accPr><m:e><m:r><m:t>a</m:t></m:r>\
+             <m:r><m:t>+</m:t></m:r><m:r><m:t>b</m:t></m:r></m:e></m:acc>"
+        );
+        assert_eq!(
+            x("\"a<b\""),
+            "<m:r><m:rPr><m:sty m:val=\"p\"/></m:rPr><m:t>a&lt;b</m:t></m:r>"
+        );
+    }
+}
@@ -0,0 +1,454 @@
+//! Two-dimensional text of formulas for terminals and logs, like `pprint` of sympy.
+//!
+//! Fractions are stacked over a bar, roots get a radical sign, scripts are raised or
+//! lowered and limits are set over and under, in the rows of a monospace text block.
+
+use unicode_normalization::char::is_combining_mark;
+
+use crate::expr::{Frac, Inter, Math, Root, Simple, Stack};
+use crate::printer::{spell_symbol, Notation};
+use crate::unicode::{bracket, combining, is_word_boundary, operator_prefix, symbol, Level};
+
+/// A block of text with the row that lines up with its neighbours.
+#[derive(Debug, Clone)]
+struct Block {
+    /// Rows of the same number of cells, each a character with its combining marks.
+    rows: Vec<Vec<String>>,
+    baseline: usize,
+    level: Level,
+}
+
+impl Block {
+    fn text(s: &str, level: Level) -> Self {
+        let mut cells: Vec<String> = vec![];
+        for c in s.chars() {
+            match cells.last_mut() {
+                Some(last) if is_combining_mark(c) => last.push(c),
+                _ => cells.push(c.to_string()),
+            }
+        }
+        Self {
+            rows: vec![cells],
+            baseline: 0,
+            level,
+        }
+    }
+
+    fn width(&self) -> usize {
+        self.rows.first().map_or(0, Vec::len)
+    }
+
+    fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn baseline_text(&self) -> String {
+        self.rows[self.baseline].concat()
+    }
+}
+
+/// Draws `parts` with their top rows at `row` and left cells at `column`,
+/// `row` counting from the baseline of the result.
+fn compose(parts: Vec<(Block, isize, usize)>, level: Level) -> Block {
+    let top = parts
+        .iter()
+        .map(|x| x.1)
+        .chain([0])
+        .min()
+        .unwrap_or_default();
+    let bottom = parts
+        .iter()
+        .map(|(x, row, _)| row + x.height() as isize)
+        .chain([1])
+        .max()
+        .unwrap_or(1);
+    let width = parts
+        .iter()
+        .map(|(x, _, column)| column + x.width())
+        .max()
+        .unwrap_or_default();
+    let mut rows = vec![vec![" ".to_string(); width]; (bottom - top) as usize];
+    for (block, row, column) in parts {
+        for (i, cells) in block.rows.into_iter().enumerate() {
+            for (j, cell) in cells.into_iter().enumerate() {
+                if cell != " " {
+                    rows[(row - top) as usize + i][column + j] = cell;
+                }
+            }
+        }
+    }
+    Block {
+        rows,
+        baseline: (-top) as usize,
+        level,
+    }
+}
+
+/// Lines up `blocks` side by side on their baselines.
+fn hcat(blocks: Vec<Block>, level: Level) -> Block {
+    let mut column = 0;
+    let parts = blocks
+        .into_iter()
+        .map(|x| {
+            let part = (column, -(x.baseline as isize));
+            column += x.width();
+            (x, part.1, part.0)
+        })
+        .collect();
+    compose(parts, level)
+}
+
+/// Renders `math` as rows of monospace text, joined by line breaks.
+///
+/// The ASCII notation draws with `-`, `/` and `\` and spells symbols without one in ASCII,
+/// e.g. `alpha` for `α`.
+pub fn pretty(math: &Math, notation: Notation) -> String {
+    Pretty { notation }
+        .math(math)
+        .rows
+        .iter()
+        .map(|x| x.concat().trim_end().to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+struct Pretty {
+    notation: Notation,
+}
+
+impl Pretty {
+    fn math(&self, x: &Math) -> Block {
+        match &x.0[..] {
+            [x] => self.root(x),
+            roots => {
+                let mut blocks: Vec<Block> = vec![];
+                for x in roots {
+                    let y = self.root(x);
+                    if let Some(last) = blocks.last() {
+                        // keep names and stacked blocks apart
+                        if last.height() > 1
+                            || y.height() > 1
+                            || is_word_boundary(&last.baseline_text(), &y.baseline_text())
+                        {
+                            blocks.push(Block::text(" ", Level::Symbol));
+                        }
+                    }
+                    blocks.push(y);
+                }
+                hcat(blocks, Level::Concat)
+            }
+        }
+    }
+
+    fn root(&self, x: &Root) -> Block {
+        match x {
+            Root::Root { root, body } => self.radical(Some(self.frac(root)), self.frac(body)),
+            Root::Math { body } => self.frac(body),
+        }
+    }
+
+    /// Draws a radical sign before `body` and a bar over it, with `index` over the sign.
+    fn radical(&self, index: Option<Block>, body: Block) -> Block {
+        let (rising, falling) = match self.notation {
+            Notation::Unicode => ("╱", "╲"),
+            Notation::Ascii => ("/", "\\"),
+        };
+        let height = body.height();
+        let mut rows = (0..height)
+            .map(|i| {
+                let mut row = vec![" ".to_string(); height + 1];
+                row[height - i] = rising.to_string();
+                row
+            })
+            .collect::<Vec<_>>();
+        rows[height - 1][0] = falling.to_string();
+        let sign = Block {
+            rows,
+            baseline: 0,
+            level: Level::Symbol,
+        };
+        let bar = Block::text(&"_".repeat(body.width()), Level::Symbol);
+        let index_width = index.as_ref().map_or(0, Block::width);
+        // the index sits on the row of the bar, over the sign
+        let shift = index_width.saturating_sub(height);
+        let top = -(body.baseline as isize);
+        let mut parts = vec![
+            (bar, top - 1, shift + height + 1),
+            (sign, top, shift),
+            (body, top, shift + height + 1),
+        ];
+        if let Some(index) = index {
+            let row = top - index.height() as isize;
+            parts.push((index, row, shift + height - index_width));
+        }
+        compose(parts, Level::Atom)
+    }
+
+    fn frac(&self, x: &Frac) -> Block {
+        match x {
+            Frac::Frac { nume, denom } => {
+                let (nume, denom) = (self.stack(nume), self.stack(denom));
+                let width = nume.width().max(denom.width());
+                let line = match self.notation {
+                    Notation::Unicode => "─",
+                    Notation::Ascii => "-",
+                };
+                let bar = Block::text(&line.repeat(width), Level::Symbol);
+                let parts = vec![
+                    (
+                        nume.clone(),
+                        -(nume.height() as isize),
+                        (width - nume.width()) / 2,
+                    ),
+                    (bar, 0, 0),
+                    (denom.clone(), 1, (width - denom.width()) / 2),
+                ];
+                compose(parts, Level::Frac)
+            }
+            Frac::Math { body } => self.stack(body),
+        }
+    }
+
+    fn stack(&self, x: &Stack) -> Block {
+        let body = self.inter(x.body());
+        let over = x.over().map(|x| self.inter(x));
+        let under = x.under().map(|x| self.inter(x));
+        if over.is_none() && under.is_none() {
+            return body;
+        }
+        let width = [Some(&body), over.as_ref(), under.as_ref()]
+            .into_iter()
+            .flatten()
+            .map(Block::width)
+            .max()
+            .unwrap_or_default();
+        let top = -(body.baseline as isize);
+        let bottom = top + body.height() as isize;
+        let mut parts = vec![];
+        if let Some(over) = over {
+            let column = (width - over.width()) / 2;
+            parts.push((over.clone(), top - over.height() as isize, column));
+        }
+        if let Some(under) = under {
+            parts.push((under.clone(), bottom, (width - under.width()) / 2));
+        }
+        let column = (width - body.width()) / 2;
+        parts.push((body, top, column));
+        compose(parts, Level::Script)
+    }
+
+    fn inter(&self, x: &Inter) -> Block {
+        let base = self.simple(x.body());
+        if x.sub().is_none() && x.sup().is_none() {
+            return base;
+        }
+        let base = self.within(base, Level::Script);
+        let top = -(base.baseline as isize);
+        let bottom = top + base.height() as isize;
+        let column = base.width();
+        let mut parts = vec![(base, top, 0)];
+        // a superscript ends above the base and a subscript starts below it
+        if let Some(sup) = x.sup().map(|x| self.simple(x)) {
+            let row = top - sup.height() as isize;
+            parts.push((sup, row, column));
+        }
+        if let Some(sub) = x.sub().map(|x| self.simple(x)) {
+            parts.push((sub, bottom, column));
+        }
+        compose(parts, Level::Script)
+    }
+
+    fn simple(&self, x: &Simple) -> Block {
+        match x {
+            Simple::UnarySymbol { operators, symbol } => {
+                let symbol = Block::text(&self.symbol(symbol), Level::Symbol);
+                self.apply(operators, symbol)
+            }
+            Simple::UnaryParened {
+                operators,
+                open,
+                body,
+                close,
+            } => {
+                let body = self.math(body);
+                let base = close.trim_end_matches('\'');
+                let primes = close.len() - base.len();
+                let y = match (open.is_empty(), base.is_empty()) {
+                    // invisible brackets group without showing
+                    (true, true) if primes == 0 => body,
+                    (true, true) => self.primed(self.parened(body, "(", ")"), primes),
+                    _ => {
+                        let (open, close) = (bracket(open, true), bracket(base, false));
+                        self.primed(self.parened(body, &open, &close), primes)
+                    }
+                };
+                self.apply(operators, y)
+            }
+            Simple::UnaryExpr { operators, body } => self.apply(operators, self.math(body)),
+        }
+    }
+
+    fn symbol(&self, tex: &str) -> String {
+        let text = symbol(tex);
+        if self.notation == Notation::Unicode || text.is_ascii() {
+            return text;
+        }
+        let base = tex.trim_end_matches('\'');
+        let primes = "'".repeat(tex.len() - base.len());
+        let spelling = spell_symbol(base, Notation::Ascii).unwrap_or(base.to_string());
+        let name = spelling
+            .strip_prefix('<')
+            .and_then(|x| x.strip_suffix('>'))
+            .filter(|x| x.chars().all(|c| c.is_ascii_alphanumeric()));
+        format!("{}{}", name.unwrap_or(&spelling), primes)
+    }
+
+    /// `block` with `primes` primes after its top row.
+    fn primed(&self, block: Block, primes: usize) -> Block {
+        if primes == 0 {
+            return block;
+        }
+        let prime = match self.notation {
+            Notation::Unicode => "′",
+            Notation::Ascii => "'",
+        };
+        let column = block.width();
+        let row = -(block.baseline as isize);
+        let primes = Block::text(&prime.repeat(primes), Level::Symbol);
+        compose(vec![(block, row, 0), (primes, row, column)], Level::Atom)
+    }
+
+    /// `block` in parentheses if it binds looser than `level`.
+    fn within(&self, block: Block, level: Level) -> Block {
+        match block.level <= level {
+            true => block,
+            false => self.parened(block, "(", ")"),
+        }
+    }
+
+    /// `body` between brackets as tall as it.
+    fn parened(&self, body: Block, open: &str, close: &str) -> Block {
+        let (height, baseline) = (body.height(), body.baseline);
+        let mut blocks = vec![];
+        if !open.is_empty() {
+            blocks.push(self.delimiter(open, true, height, baseline));
+        }
+        blocks.push(body);
+        if !close.is_empty() {
+            blocks.push(self.delimiter(close, false, height, baseline));
+        }
+        hcat(blocks, Level::Atom)
+    }
+
+    /// A bracket of `height` rows, drawn from pieces for the common ones and repeated otherwise.
+    fn delimiter(&self, text: &str, open: bool, height: usize, baseline: usize) -> Block {
+        let pieces: Option<[&str; 4]> = match (self.notation, text) {
+            _ if height == 1 => None,
+            // top, middle, bottom and the row of the centre
+            (Notation::Unicode, "(") => Some(["⎛", "⎜", "⎝", "⎜"]),
+            (Notation::Unicode, ")") => Some(["⎞", "⎟", "⎠", "⎟"]),
+            (Notation::Unicode, "[") => Some(["⎡", "⎢", "⎣", "⎢"]),
+            (Notation::Unicode, "]") => Some(["⎤", "⎥", "⎦", "⎥"]),
+            (Notation::Unicode, "{") => Some(["⎧", "⎪", "⎩", "⎨"]),
+            (Notation::Unicode, "}") => Some(["⎫", "⎪", "⎭", "⎬"]),
+            (Notation::Unicode, "⌈") => Some(["⎡", "⎢", "⎢", "⎢"]),
+            (Notation::Unicode, "⌉") => Some(["⎤", "⎥", "⎥", "⎥"]),
+            (Notation::Unicode, "⌊") => Some(["⎢", "⎢", "⎣", "⎢"]),
+            (Notation::Unicode, "⌋") => Some(["⎥", "⎥", "⎦", "⎥"]),
+            (Notation::Unicode, "|") => Some(["│"; 4]),
+            (Notation::Ascii, "(") => Some(["/", "|", "\\", "|"]),
+            (Notation::Ascii, ")") => Some(["\\", "|", "/", "|"]),
+            (Notation::Ascii, "{") => Some(["/", "|", "\\", "<"]),
+            (Notation::Ascii, "}") => Some(["\\", "|", "/", ">"]),
+            _ => None,
+        };
+        let text = match (self.notation, open) {
+            (Notation::Ascii, true) if !text.is_ascii() => "(",
+            (Notation::Ascii, false) if !text.is_ascii() => ")",
+            _ => text,
+        };
+        let rows = (0..height)
+            .map(|i| match pieces {
+                Some([top, _, _, _]) if i == 0 => top.to_string(),
+                Some([_, _, bottom, _]) if i + 1 == height => bottom.to_string(),
+                Some([_, _, _, centre]) if i == (height - 1) / 2 => centre.to_string(),
+                Some([_, middle, _, _]) => middle.to_string(),
+                None => text.to_string(),
+            })
+            .map(|x| vec![x])
+            .collect();
+        Block {
+            rows,
+            baseline,
+            level: Level::Symbol,
+        }
+    }
+
+    /// Applies `operators` to `operand`, the last one first.
+    fn apply(&self, operators: &[String], operand: Block) -> Block {
+        operators
+            .iter()
+            .rev()
+            .fold(operand, |x, operator| self.operator(operator, x))
+    }
+
+    fn operator(&self, tex: &str, operand: Block) -> Block {
+        if tex == r"\sqrt" {
+            return self.radical(None, operand);
+        }
+        if let Some(index) = tex
+            .strip_prefix(r"\sqrt[")
+            .and_then(|x| x.strip_suffix(']'))
+        {
+            return self.radical(Some(Block::text(index, Level::Symbol)), operand);
+        }
+        let prefix = operator_prefix(tex).filter(|_| self.notation == Notation::Unicode);
+        if let Some(prefix) = prefix {
+            let operand = self.within(operand, Level::Prefix);
+            return hcat(
+                vec![Block::text(&prefix, Level::Symbol), operand],
+                Level::Prefix,
+            );
+        }
+        let mark = combining(tex).filter(|_| self.notation == Notation::Unicode);
+        match mark {
+            Some(mark) if operand.width() == 1 && operand.height() == 1 => {
+                let mut operand = operand;
+                operand.rows[0][0].push_str(&mark);
+                operand
+            }
+            _ => {
+                let name = Block::text(tex.trim_start_matches('\\'), Level::Symbol);
+                hcat(vec![name, self.parened(operand, "(", ")")], Level::Atom)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::parse;
+    use crate::token::tokenize;
+
+    fn x(input: &str, notation: Notation) -> String {
+        pretty(&parse(&tokenize(input).unwrap()).unwrap(), notation)
+    }
+
+    #[test]
+    fn test_pretty() {
+        assert_eq!(x("a+b /c", Notation::Unicode), "a+b\n───\n c");
+        assert_eq!(x("x^2", Notation::Unicode), " 2\nx");
+        assert_eq!(x("x_i^2", Notation::Unicode), " 2\nx\n i");
+        assert_eq!(x("√[a+b]", Notation::Unicode), "  ___\n╲╱a+b");
+        assert_eq!(x("3 _/x", Notation::Unicode), "3 _\n╲╱x");
+        assert_eq!(x("[a /b]^2", Notation::Unicode), "   2\n⎛a⎞\n⎜─⎟\n⎝b⎠");
+        assert_eq!(x("<lim>__[n→∞] a_n", Notation::Unicode), "lim a\nn→∞  n");
+        assert_eq!(x("1 /2 x", Notation::Ascii), "1\n- x\n2");
+        assert_eq!(x("α /2", Notation::Ascii), "alpha\n-----\n  2");
+        assert_eq!(x("<'hat>x", Notation::Unicode), "x\u{302}");
+        assert_eq!(x("<'hat>x", Notation::Ascii), "hat(x)");
+        assert_eq!(x("{a /b}", Notation::Unicode), "⎧a⎫\n⎨─⎬\n⎩b⎭");
+        assert_eq!(x("√[a /b]", Notation::Ascii), "    _\n   /a\n  / -\n\\/  b");
+    }
+}
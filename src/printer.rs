@@ -0,0 +1,783 @@
+use super::expr::{Frac, Inter, Math, Root, Simple, Stack};
+use super::token::reverse::{maspace_of_tex, PLACEHOLDER};
+use super::token::unicode_subsup::{take_unicode_sub, take_unicode_sup};
+use super::token::{tokenize, Token};
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone)]
+pub struct PrintError {
+    description: String,
+}
+
+impl Display for PrintError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.description)
+    }
+}
+
+impl std::error::Error for PrintError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Notation {
+    #[default]
+    Unicode,
+    Ascii,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PrintOptions {
+    /// `α` or `<alpha>`. Operators and brackets follow the same choice, e.g. `√` or `<'sqrt>`.
+    pub symbols: Notation,
+    /// `bᵃ⁺ᵇ` or `b^[a+b]` where a script is made of symbols in brackets.
+    pub scripts: Notation,
+}
+
+/// Serializes `math` back to maSpace source with the default options.
+///
+/// Every node gets the smallest spacing order which keeps its grouping.
+/// A concatenation which would need spaces below the top level is enclosed in `[..]`.
+pub fn print(math: &Math) -> Result<String, PrintError> {
+    print_with_options(math, &PrintOptions::default())
+}
+
+pub fn print_with_options(math: &Math, options: &PrintOptions) -> Result<String, PrintError> {
+    Printer { options }.body(math)
+}
+
+struct Printer<'a> {
+    options: &'a PrintOptions,
+}
+
+impl Printer<'_> {
+    /// Prints the whole input or the inside of brackets, where any spacing order is allowed.
+    fn body(&self, x: &Math) -> Result<String, PrintError> {
+        match x.as_simple() {
+            Some(Simple::UnaryExpr { operators, body }) if operators.is_empty() => self.body(body),
+            _ => self.math(x),
+        }
+    }
+
+    fn math(&self, x: &Math) -> Result<String, PrintError> {
+        if x.0.is_empty() {
+            return Err(PrintError {
+                description: "Failed to print Math: empty expression".to_string(),
+            });
+        }
+        let sep = if is_concat_without_space(x) { "" } else { " " };
+        Ok(x.0
+            .iter()
+            .map(|x| self.root(x))
+            .collect::<Result<Vec<_>, _>>()?
+            .join(sep))
+    }
+
+    fn root(&self, x: &Root) -> Result<String, PrintError> {
+        Ok(match x {
+            Root::Root { root, body } => format!(
+                "{}{}_/{}",
+                self.frac(root)?,
+                spaces(level_of_root(x)),
+                self.frac(body)?
+            ),
+            Root::Math { body } => self.frac(body)?,
+        })
+    }
+
+    fn frac(&self, x: &Frac) -> Result<String, PrintError> {
+        Ok(match x {
+            Frac::Frac { nume, denom } => format!(
+                "{}{}/{}",
+                self.stack(nume)?,
+                spaces(level_of_frac(x)),
+                self.stack(denom)?
+            ),
+            Frac::Math { body } => self.stack(body)?,
+        })
+    }
+
+    fn stack(&self, x: &Stack) -> Result<String, PrintError> {
+        let mut y = self.inter(&x.body)?;
+        if let Some(under) = &x.under {
+            y += &format!("{}__{}", spaces(level_of_stack(x)), self.inter(under)?);
+        }
+        if let Some(over) = &x.over {
+            y += &format!("{}^^{}", spaces(level_of_stack(x)), self.inter(over)?);
+        }
+        Ok(y)
+    }
+
+    fn inter(&self, x: &Inter) -> Result<String, PrintError> {
+        let mut y = self.simple(&x.body)?;
+        let unicode = self.options.scripts == Notation::Unicode && level_of_inter(x) == 0;
+        if let Some(sub) = &x.sub {
+            y += &match unicode_script(sub, unicode_subscripts()).filter(|_| unicode) {
+                Some(script) => script,
+                None => format!("{}_{}", spaces(level_of_inter(x)), self.simple(sub)?),
+            };
+        }
+        if let Some(sup) = &x.sup {
+            y += &match unicode_script(sup, unicode_superscripts()).filter(|_| unicode) {
+                Some(script) => script,
+                None => format!("{}^{}", spaces(level_of_inter(x)), self.simple(sup)?),
+            };
+        }
+        Ok(y)
+    }
+
+    fn simple(&self, x: &Simple) -> Result<String, PrintError> {
+        let notation = self.options.symbols;
+        let operators = |operators: &[String], order: usize| {
+            operators
+                .iter()
+                .map(|x| Ok(format!("{}{}", spell_operator(x, notation)?, spaces(order))))
+                .collect::<Result<String, PrintError>>()
+        };
+        Ok(match x {
+            Simple::UnarySymbol {
+                operators: ops,
+                symbol,
+            } => {
+                format!("{}{}", operators(ops, 0)?, spell_symbol(symbol, notation)?)
+            }
+            Simple::UnaryParened {
+                operators: ops,
+                open,
+                body,
+                close,
+            } => format!(
+                "{}{}{}{}",
+                operators(ops, 0)?,
+                spell_open(open, notation)?,
+                self.body(body)?,
+                spell_close(close, notation)?
+            ),
+            Simple::UnaryExpr {
+                operators: ops,
+                body,
+            } => match body.as_simple() {
+                Some(x) if ops.is_empty() => self.simple(x)?,
+                _ if needs_brackets(body) => {
+                    format!("{}[{}]", operators(ops, 0)?, self.body(body)?)
+                }
+                _ => format!(
+                    "{}{}",
+                    operators(ops, level_of_math(body) + 1)?,
+                    self.math(body)?
+                ),
+            },
+        })
+    }
+}
+
+/// Spells a script like `[a+b]` with unicode sub/superscript characters when every symbol has one.
+fn unicode_script(x: &Simple, table: &HashMap<String, char>) -> Option<String> {
+    let body = match x {
+        Simple::UnaryParened {
+            operators,
+            open,
+            body,
+            close,
+        } if operators.is_empty() && open.is_empty() && close.is_empty() => body,
+        _ => return None,
+    };
+    let mut body = body;
+    while let Some(Simple::UnaryExpr { operators, body: x }) = body.as_simple() {
+        if !operators.is_empty() {
+            return None;
+        }
+        body = x;
+    }
+    if !is_concat_without_space(body) {
+        return None;
+    }
+    body.0
+        .iter()
+        .map(|x| match x.as_simple() {
+            Some(Simple::UnarySymbol { operators, symbol }) if operators.is_empty() => {
+                table.get(symbol).copied()
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn spaces(order: usize) -> String {
+    " ".repeat(order)
+}
+
+/// Whether the roots of `x` can be concatenated with `Cat(0)`, i.e. without spaces.
+fn is_concat_without_space(x: &Math) -> bool {
+    x.0.iter().all(|x| level_of_root(x) == 0)
+        && x.0.iter().skip(1).all(|x| !starts_with_operator(x))
+}
+
+/// Whether `x` needs `[..]` when it is not at the top level.
+///
+/// `Cat(n)` with `n > 0` is always promoted to the maximum order by the tokenizer.
+fn needs_brackets(x: &Math) -> bool {
+    x.0.len() > 1 && !is_concat_without_space(x)
+}
+
+fn starts_with_operator(x: &Root) -> bool {
+    let frac = match x {
+        Root::Root { root, .. } => root,
+        Root::Math { body } => body,
+    };
+    let stack = match frac {
+        Frac::Frac { nume, .. } => nume,
+        Frac::Math { body } => body,
+    };
+    match &stack.body.body {
+        Simple::UnaryExpr { operators, body } => {
+            !operators.is_empty()
+                || (!needs_brackets(body) && body.0.first().is_some_and(starts_with_operator))
+        }
+        Simple::UnarySymbol { operators, .. } | Simple::UnaryParened { operators, .. } => {
+            !operators.is_empty()
+        }
+    }
+}
+
+fn level_of_math(x: &Math) -> usize {
+    match &x.0[..] {
+        [root] => level_of_root(root),
+        _ => 0,
+    }
+}
+
+fn level_of_root(x: &Root) -> usize {
+    match x {
+        Root::Root { root, body } => level_of_frac(root).max(level_of_frac(body)),
+        Root::Math { body } => level_of_frac(body),
+    }
+}
+
+fn level_of_frac(x: &Frac) -> usize {
+    match x {
+        Frac::Frac { nume, denom } => level_of_stack(nume).max(level_of_stack(denom)),
+        Frac::Math { body } => level_of_stack(body),
+    }
+}
+
+fn level_of_stack(x: &Stack) -> usize {
+    [Some(&x.body), x.over.as_ref(), x.under.as_ref()]
+        .into_iter()
+        .flatten()
+        .map(level_of_inter)
+        .max()
+        .unwrap_or_default()
+}
+
+fn level_of_inter(x: &Inter) -> usize {
+    [Some(&x.body), x.sup.as_ref(), x.sub.as_ref()]
+        .into_iter()
+        .flatten()
+        .map(level_of_simple)
+        .max()
+        .unwrap_or_default()
+}
+
+fn level_of_simple(x: &Simple) -> usize {
+    match x {
+        Simple::UnaryExpr { operators, body } => match body.as_simple() {
+            Some(x) if operators.is_empty() => level_of_simple(x),
+            _ if needs_brackets(body) => 0,
+            _ => level_of_math(body) + 1,
+        },
+        Simple::UnarySymbol { .. } | Simple::UnaryParened { .. } => 0,
+    }
+}
+
+// ----
+
+/// Returns the maSpace spellings which may produce the symbol `tex`, shortest first.
+pub(crate) fn spellings_of_symbol(tex: &str) -> Vec<String> {
+    let base = tex.trim_end_matches('\'');
+    let primes = &tex[base.len()..];
+    let base = base.trim_end_matches(' ');
+    let mut spellings = maspace_of_tex(base)
+        .into_iter()
+        .filter(|x| !x.contains(PLACEHOLDER))
+        .collect::<Vec<_>>();
+    if let Some((command, argument)) = split_command(base) {
+        if let Some(literal) = string_literal(command, argument) {
+            spellings.push(literal);
+        }
+        let accents = maspace_of_tex(command)
+            .into_iter()
+            .filter(|x| x.starts_with(PLACEHOLDER) || x.starts_with(&format!("<{}", PLACEHOLDER)))
+            .collect::<Vec<_>>();
+        for inner in spellings_of_symbol(argument) {
+            for accent in &accents {
+                let core = match inner.strip_prefix('<').and_then(|x| x.strip_suffix('>')) {
+                    Some(x) if !x.starts_with(['"', '#']) => x.trim(),
+                    _ => &inner,
+                };
+                if accent.starts_with(PLACEHOLDER) {
+                    spellings.push(accent.replace(PLACEHOLDER, &inner));
+                } else {
+                    spellings.push(accent.replace(PLACEHOLDER, core));
+                }
+            }
+        }
+    }
+    if base.contains('.')
+        && base
+            .split('.')
+            .all(|x| x.chars().all(|c| c.is_ascii_digit()))
+    {
+        spellings.push(format!("<{}>", base));
+    }
+    spellings.sort_by_key(|x| x.chars().count());
+    spellings
+        .into_iter()
+        .map(|x| format!("{}{}", x, primes))
+        .collect()
+}
+
+/// Splits `\command{argument}` into `\command` and `argument`.
+pub(crate) fn split_command(tex: &str) -> Option<(&str, &str)> {
+    let open = tex.find('{')?;
+    let (command, argument) = (&tex[..open], tex[open + 1..].strip_suffix('}')?);
+    let mut depth = 0;
+    for c in argument.chars() {
+        depth += match c {
+            '{' => 1,
+            '}' => -1,
+            _ => 0,
+        };
+        if depth < 0 {
+            return None;
+        }
+    }
+    (command.len() > 1
+        && command.starts_with('\\')
+        && command[1..].chars().all(|c| c.is_ascii_alphabetic())
+        && depth == 0)
+        .then_some((command, argument))
+}
+
+pub(crate) fn string_literal(command: &str, content: &str) -> Option<String> {
+    let style = match command.strip_prefix("\\math") {
+        Some(style) => style,
+        None if command == r"\text" => "text",
+        None => return None,
+    };
+    let content = unescape_tex_string(content);
+    let hashes = (0..)
+        .map(|n| "#".repeat(n))
+        .find(|x| !content.contains(&format!("\"{}", x)))?;
+    Some(match (style, hashes.as_str()) {
+        ("rm", "") => format!("\"{}\"", content),
+        (style, hashes) => format!("<{}\"{}\"{} {}>", hashes, content, hashes, style),
+    })
+}
+
+pub(crate) fn unescape_tex_string(s: &str) -> String {
+    [
+        (r"{\textasciitilde}", "~"),
+        (r"{\textasciicircum}", "^"),
+        (r"{\backslash}", r"\"),
+        (r"\#", "#"),
+        (r"\$", "$"),
+        (r"\%", "%"),
+        (r"\_", "_"),
+        (r"\{", "{"),
+        (r"\}", "}"),
+        (r"\\", r"\"),
+    ]
+    .into_iter()
+    .fold(s.to_string(), |s, (from, to)| s.replace(from, to))
+}
+
+pub(crate) fn spell_symbol(tex: &str, notation: Notation) -> Result<String, PrintError> {
+    let spellings = prefer(spellings_of_symbol(tex), notation, tex);
+    let produces = |spelling: &&String, exact: bool| match &tokenize(spelling).ok().as_deref() {
+        Some([Token::Symbol(x)]) if exact => x == tex,
+        Some([Token::Symbol(x)]) => x.trim_end_matches(' ') == tex.trim_end_matches(' '),
+        _ => false,
+    };
+    spellings
+        .iter()
+        .find(|x| produces(x, true))
+        .or_else(|| spellings.iter().find(|x| produces(x, false)))
+        .cloned()
+        .ok_or(PrintError {
+            description: format!("Failed to print Symbol: no spelling for {:?}", tex),
+        })
+}
+
+fn spell_operator(tex: &str, notation: Notation) -> Result<String, PrintError> {
+    let mut spellings = maspace_of_tex(tex)
+        .into_iter()
+        .filter_map(|x| x.strip_suffix(PLACEHOLDER).map(String::from))
+        .filter(|x| !x.contains(PLACEHOLDER))
+        .collect::<Vec<_>>();
+    if let Some(n) = tex
+        .strip_prefix(r"\sqrt[")
+        .and_then(|x| x.strip_suffix(']'))
+    {
+        spellings.push(format!("<'root{}>", n));
+    }
+    if let Some(name) = tex.strip_prefix('\\') {
+        spellings.push(format!("<'{}>", name));
+    }
+    prefer(spellings, notation, tex)
+        .into_iter()
+        .find(|x| {
+            matches!(
+                &tokenize(&format!("{}x", x)).ok().as_deref(),
+                Some([Token::Op(op, 0), Token::Symbol(_)]) if op == tex
+            )
+        })
+        .ok_or(PrintError {
+            description: format!("Failed to print operator: no spelling for {:?}", tex),
+        })
+}
+
+pub(crate) fn spell_open(tex: &str, notation: Notation) -> Result<String, PrintError> {
+    if tex.is_empty() {
+        return Ok("[".to_string());
+    }
+    prefer(maspace_of_tex(tex), notation, tex)
+        .into_iter()
+        .find(|x| matches!(&tokenize(x).ok().as_deref(), Some([Token::Open(y)]) if y == tex))
+        .ok_or(PrintError {
+            description: format!("Failed to print bracket: no spelling for {:?}", tex),
+        })
+}
+
+pub(crate) fn spell_close(tex: &str, notation: Notation) -> Result<String, PrintError> {
+    let base = tex.trim_end_matches('\'');
+    let primes = &tex[base.len()..];
+    if base.is_empty() {
+        return Ok(format!("]{}", primes));
+    }
+    prefer(maspace_of_tex(base), notation, base)
+        .into_iter()
+        .map(|x| format!("{}{}", x, primes))
+        .find(|x| matches!(&tokenize(x).ok().as_deref(), Some([Token::Close(y)]) if y == tex))
+        .ok_or(PrintError {
+            description: format!("Failed to print bracket: no spelling for {:?}", tex),
+        })
+}
+
+/// Orders `spellings` of `tex` by preference. The ASCII notation puts ASCII spellings first
+/// and among them those naming the command in full, e.g. `<alpha>` before `<al>`.
+fn prefer(spellings: Vec<String>, notation: Notation, tex: &str) -> Vec<String> {
+    if notation == Notation::Unicode {
+        return spellings;
+    }
+    let mut base = tex.trim_end_matches('\'').trim_end_matches(' ');
+    while let Some((_, argument)) = split_command(base) {
+        base = argument.trim_end_matches(' ');
+    }
+    let name = base
+        .strip_prefix('\\')
+        .filter(|x| x.len() > 1 && x.chars().all(|c| c.is_ascii_alphabetic()));
+    let mut spellings = spellings;
+    spellings.sort_by_key(|x| {
+        (
+            !x.is_ascii(),
+            name.is_some_and(|name| !x.contains(name)),
+            x.chars().count(),
+        )
+    });
+    spellings
+}
+
+pub(crate) fn unicode_subscripts() -> &'static HashMap<String, char> {
+    static TABLE: OnceLock<HashMap<String, char>> = OnceLock::new();
+    TABLE.get_or_init(|| unicode_scripts(|s| take_unicode_sub(s).ok().map(|(_, x)| x)))
+}
+
+pub(crate) fn unicode_superscripts() -> &'static HashMap<String, char> {
+    static TABLE: OnceLock<HashMap<String, char>> = OnceLock::new();
+    TABLE.get_or_init(|| unicode_scripts(|s| take_unicode_sup(s).ok().map(|(_, x)| x)))
+}
+
+fn unicode_scripts(take: impl Fn(&str) -> Option<Token>) -> HashMap<String, char> {
+    let mut table = HashMap::new();
+    for c in '\u{80}'..='\u{FFFF}' {
+        if let Some(Token::UnicodeSub(x) | Token::UnicodeSup(x)) = take(&c.to_string()) {
+            if let Token::Symbol(symbol) = *x {
+                table.entry(symbol).or_insert(c);
+            }
+        }
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::parse;
+
+    use proptest::option;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_print() {
+        fn x(s: &str) -> String {
+            print(&parse(&tokenize(s).unwrap()).unwrap()).unwrap()
+        }
+        assert_eq!(x("a+b /c"), "a+b /c");
+        assert_eq!(x("a _b^c"), "a _b^c");
+        assert_eq!(x("a_b^c"), "a_b^c");
+        assert_eq!(x("a   _b"), "a_b");
+        assert_eq!(x("<alpha>   + b"), "α+b");
+        assert_eq!(x("a _b_c  ^d ^e+f _g  /h"), "a _b_c  ^d _g ^e+f  /h");
+        assert_eq!(x("a _b_c^d ^[e+f _g/h]"), "a _b_c^d ^[e+f _g/h]");
+        assert_eq!(x("<'sqrt>[3+4]"), "√[3+4]");
+        assert_eq!(x("<'sqrt> 3+4"), "√ 3+4");
+        assert_eq!(x("3 _/ 123"), "3 _/123");
+        assert_eq!(x("<alpha hat!>'"), "<α̂!>'");
+        assert_eq!(x("`(`a`)`"), "<(>a<)>");
+        assert_eq!(x(r#"<"a b" bf>"#), r#"<"a b" bf>"#);
+        assert_eq!(x(r##"<#"a"b"# it>"##), r##"<#"a"b"# it>"##);
+        assert_eq!(x("<1.23 hat>"), "<1.23^>");
+        assert_eq!(x("⟨a⟩'"), "⟨a⟩'");
+    }
+
+    #[test]
+    fn test_print_with_options() {
+        fn x(s: &str, symbols: Notation, scripts: Notation) -> String {
+            let options = PrintOptions { symbols, scripts };
+            print_with_options(&parse(&tokenize(s).unwrap()).unwrap(), &options).unwrap()
+        }
+        use Notation::*;
+        assert_eq!(x("<alpha>ᵃ⁺ᵇ", Unicode, Unicode), "αᵃ⁺ᵇ");
+        assert_eq!(x("<alpha>ᵃ⁺ᵇ", Ascii, Ascii), "<alpha>^[a+b]");
+        assert_eq!(x("<alpha>^[a+b]", Ascii, Unicode), "<alpha>ᵃ⁺ᵇ");
+        assert_eq!(x("x^2", Unicode, Unicode), "x^2");
+        assert_eq!(x("xₐ", Unicode, Ascii), "x_[a]");
+        assert_eq!(x("√⟨a⟩", Ascii, Ascii), "<'sqrt>`[<`a`>]`");
+        assert_eq!(x("<alpha hat>", Ascii, Ascii), "<alpha^>");
+        assert_eq!(x("↠ℝ", Ascii, Ascii), "<twoheadrightarrow><R bb>");
+        assert_eq!(x("<'overline>x", Unicode, Unicode), "<'overline>x");
+    }
+
+    /// Removes the wrappers which only stand for spacing orders, and reads `[..]` as a group.
+    fn normalize(x: Math) -> Math {
+        let x = Math(
+            x.0.into_iter()
+                .map(|x| {
+                    let x = match x {
+                        Root::Root { root, body } => Root::Root {
+                            root: normalize_frac(root),
+                            body: normalize_frac(body),
+                        },
+                        Root::Math { body } => Root::Math {
+                            body: normalize_frac(body),
+                        },
+                    };
+                    match x.as_simple().and_then(wrapped_root) {
+                        Some(root) => root.clone(),
+                        None => x,
+                    }
+                })
+                .collect(),
+        );
+        match x.as_simple() {
+            Some(Simple::UnaryExpr { operators, body }) if operators.is_empty() => body.clone(),
+            _ => x,
+        }
+    }
+
+    /// Returns the only root wrapped by `x` when it has no operators.
+    fn wrapped_root(x: &Simple) -> Option<&Root> {
+        match x {
+            Simple::UnaryExpr { operators, body } if operators.is_empty() && body.0.len() == 1 => {
+                Some(&body.0[0])
+            }
+            _ => None,
+        }
+    }
+
+    fn normalize_frac(x: Frac) -> Frac {
+        let x = match x {
+            Frac::Frac { nume, denom } => Frac::Frac {
+                nume: normalize_stack(nume),
+                denom: normalize_stack(denom),
+            },
+            Frac::Math { body } => Frac::Math {
+                body: normalize_stack(body),
+            },
+        };
+        match Math::from(x.clone()).as_simple().and_then(wrapped_root) {
+            Some(Root::Math { body }) => body.clone(),
+            _ => x,
+        }
+    }
+
+    fn normalize_stack(x: Stack) -> Stack {
+        let x = Stack {
+            body: normalize_inter(x.body),
+            over: x.over.map(normalize_inter),
+            under: x.under.map(normalize_inter),
+        };
+        match Math::from(x.clone()).as_simple().and_then(wrapped_root) {
+            Some(Root::Math {
+                body: Frac::Math { body },
+            }) => body.clone(),
+            _ => x,
+        }
+    }
+
+    fn normalize_inter(x: Inter) -> Inter {
+        let x = Inter {
+            body: normalize_simple(x.body),
+            sup: x.sup.map(normalize_simple),
+            sub: x.sub.map(normalize_simple),
+        };
+        match Math::from(Stack::from(x.clone()))
+            .as_simple()
+            .and_then(wrapped_root)
+        {
+            Some(Root::Math {
+                body:
+                    Frac::Math {
+                        body:
+                            Stack {
+                                body,
+                                over: None,
+                                under: None,
+                            },
+                    },
+            }) => body.clone(),
+            _ => x,
+        }
+    }
+
+    fn normalize_simple(x: Simple) -> Simple {
+        match x {
+            Simple::UnaryParened {
+                operators,
+                open,
+                body,
+                close,
+            } if open.is_empty() && close.is_empty() => {
+                normalize_simple(Simple::UnaryExpr { operators, body })
+            }
+            Simple::UnaryParened {
+                operators,
+                open,
+                body,
+                close,
+            } => Simple::UnaryParened {
+                operators,
+                open,
+                body: normalize(body),
+                close,
+            },
+            Simple::UnaryExpr { operators, body } => {
+                let body = normalize(body);
+                if operators.is_empty() && body.as_simple().is_some() {
+                    body.into_simple()
+                } else {
+                    Simple::UnaryExpr { operators, body }
+                }
+            }
+            x => x,
+        }
+    }
+
+    fn arb_symbol() -> impl Strategy<Value = String> {
+        prop::sample::select(vec![
+            "a",
+            "b",
+            "1",
+            "+",
+            "=",
+            r"\alpha ",
+            r"\infty ",
+            "a'",
+            r"\hat{a}",
+            r"\mathbf{a}",
+            r"\mathrm{ab}",
+        ])
+        .prop_map(String::from)
+    }
+
+    fn arb_operators() -> impl Strategy<Value = Vec<String>> {
+        let operator = prop::sample::select(vec![r"\sqrt", r"\hat", r"\overline"]);
+        prop::collection::vec(operator.prop_map(String::from), 0..2)
+    }
+
+    fn arb_math() -> impl Strategy<Value = Math> {
+        let leaf = (arb_operators(), arb_symbol())
+            .prop_map(|(operators, symbol)| Math::from(Simple::UnarySymbol { operators, symbol }));
+        leaf.prop_recursive(4, 32, 3, |math| {
+            let brackets = prop::sample::select(vec![
+                ("", ""),
+                ("(", ")"),
+                ("(", ")'"),
+                (r"\langle", r"\rangle"),
+            ]);
+            let simple = prop_oneof![
+                (arb_operators(), arb_symbol())
+                    .prop_map(|(operators, symbol)| Simple::UnarySymbol { operators, symbol }),
+                (arb_operators(), brackets, math.clone()).prop_map(
+                    |(operators, (open, close), body)| Simple::UnaryParened {
+                        operators,
+                        open: open.to_string(),
+                        body,
+                        close: close.to_string(),
+                    }
+                ),
+                (arb_operators(), math)
+                    .prop_map(|(operators, body)| Simple::UnaryExpr { operators, body }),
+            ]
+            .boxed();
+            let inter = (
+                simple.clone(),
+                option::weighted(0.2, simple.clone()),
+                option::weighted(0.2, simple),
+            )
+                .prop_map(|(body, sup, sub)| Inter { body, sup, sub })
+                .boxed();
+            let stack = (
+                inter.clone(),
+                option::weighted(0.1, inter.clone()),
+                option::weighted(0.1, inter),
+            )
+                .prop_map(|(body, over, under)| Stack { body, over, under })
+                .boxed();
+            let frac = prop_oneof![
+                4 => stack.clone().prop_map(Frac::from),
+                1 => (stack.clone(), stack).prop_map(|(nume, denom)| Frac::Frac { nume, denom }),
+            ]
+            .boxed();
+            let root = prop_oneof![
+                4 => frac.clone().prop_map(Root::from),
+                1 => (frac.clone(), frac).prop_map(|(root, body)| Root::Root { root, body }),
+            ];
+            prop::collection::vec(root, 1..4).prop_map(Math)
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_print_parse_round_trip(math in arb_math()) {
+            for notation in [Notation::Unicode, Notation::Ascii] {
+                let options = PrintOptions {
+                    symbols: notation,
+                    scripts: notation,
+                };
+                let printed = print_with_options(&math, &options).unwrap();
+                let parsed = parse(&tokenize(&printed).unwrap()).unwrap();
+                let (tex, expected) = (parsed.to_string(), math.to_string());
+                prop_assert_eq!(
+                    tex.trim_end_matches(' '),
+                    expected.trim_end_matches(' '),
+                    "{}",
+                    printed
+                );
+                prop_assert_eq!(normalize(parsed), normalize(math.clone()), "{}", printed);
+            }
+        }
+    }
+}
@@ -0,0 +1,294 @@
+//! `maspace repl`: shows how each input is tokenized, grouped and converted.
+
+use maspace::diagnostic::Severity;
+use maspace::expr::{Frac, Inter, Math, Root, Simple, Stack};
+use maspace::highlight::{highlight, Class};
+use maspace::{convert, maspace_completions, ConvertOptions};
+
+use std::borrow::Cow;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+struct MaspaceHelper;
+
+impl Completer for MaspaceHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let completions = maspace_completions(line, pos);
+        let start = completions.first().map_or(pos, |x| x.start);
+        Ok((
+            start,
+            completions
+                .into_iter()
+                .map(|x| Pair {
+                    display: format!("{}  {}", x.text, x.tex),
+                    replacement: x.text,
+                })
+                .collect(),
+        ))
+    }
+}
+
+impl Hinter for MaspaceHelper {
+    type Hint = String;
+}
+
+impl Highlighter for MaspaceHelper {
+    fn highlight<'l>(&self, line: &'l str, _: usize) -> Cow<'l, str> {
+        Cow::Owned(colored(line))
+    }
+
+    fn highlight_char(&self, _: &str, _: usize, _: bool) -> bool {
+        true
+    }
+}
+
+impl Validator for MaspaceHelper {}
+
+impl Helper for MaspaceHelper {}
+
+/// Background colours of binary operators by the number of spaces they bind with.
+const ORDER_COLORS: [&str; 4] = ["43", "46", "45", "44"];
+
+/// `line` with ANSI colours, showing how binary operators group by their background.
+fn colored(line: &str) -> String {
+    let mut result = String::new();
+    let mut end = 0;
+    for x in highlight(line) {
+        let color = match x.class {
+            Class::Symbol => continue,
+            Class::StringLiteral => "32".to_string(),
+            Class::Accent => "35".to_string(),
+            Class::Op | Class::Open | Class::Close => "1".to_string(),
+            Class::BinOp(_, order) => format!("30;{}", ORDER_COLORS[order % ORDER_COLORS.len()]),
+            Class::UnicodeSub | Class::UnicodeSup => "36".to_string(),
+        };
+        result.push_str(&line[end..x.range.start]);
+        result.push_str(&format!("\x1b[{}m{}\x1b[0m", color, &line[x.range.clone()]));
+        end = x.range.end;
+    }
+    result.push_str(&line[end..]);
+    result
+}
+
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|x| PathBuf::from(x).join(".maspace_history"))
+}
+
+pub fn run() -> Result<()> {
+    let mut editor = Editor::new()?;
+    editor.set_helper(Some(MaspaceHelper));
+    let history = history_path();
+    if let Some(path) = &history {
+        // there is no history on the first run
+        let _ = editor.load_history(path);
+    }
+    loop {
+        match editor.readline("maspace> ") {
+            Ok(line) if line.trim().is_empty() => {}
+            Ok(line) => {
+                editor.add_history_entry(line.as_str())?;
+                print!("{}", report(&line));
+            }
+            Err(ReadlineError::Interrupted | ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    if let Some(path) = &history {
+        editor.save_history(path)?;
+    }
+    Ok(())
+}
+
+/// Describes the tokens, the grouping, the TeX and the diagnostics of `input`.
+pub fn report(input: &str) -> String {
+    let options = ConvertOptions {
+        lint: true,
+        recover: true,
+        ..Default::default()
+    };
+    let conversion = convert(input, &options);
+    let mut lines = vec![];
+    if !conversion.tokens.is_empty() {
+        let tokens = conversion
+            .tokens
+            .iter()
+            .map(|(x, _)| format!("{:?}", x))
+            .collect::<Vec<_>>();
+        lines.push(format!("tokens: {}", tokens.join(" ")));
+    }
+    if let Some(math) = &conversion.math {
+        lines.push("tree:".to_string());
+        tree(Node::Math(math), "", "", &mut lines);
+    }
+    if let Some(tex) = &conversion.tex {
+        lines.push(format!("tex: {}", tex));
+    }
+    for diagnostic in &conversion.diagnostics {
+        let severity = match diagnostic.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        let column = input[..diagnostic.range.start].chars().count();
+        let width = input[diagnostic.range.clone()].chars().count().max(1);
+        lines.push(format!(
+            "         {}{}",
+            " ".repeat(column),
+            "^".repeat(width)
+        ));
+        lines.push(format!("{}: {}", severity, diagnostic.message));
+        if let Some(x) = &diagnostic.suggestion {
+            lines.push(format!("help: write `{}`", x.apply(input)));
+        }
+    }
+    // the first fix is the suggestion of the error
+    for fix in conversion.fixes.iter().skip(1) {
+        lines.push(format!("  or: write `{}`", fix.input));
+    }
+    lines.iter().map(|x| format!("{}\n", x)).collect()
+}
+
+#[derive(Clone, Copy)]
+enum Node<'a> {
+    Math(&'a Math),
+    Root(&'a Root),
+    Frac(&'a Frac),
+    Stack(&'a Stack),
+    Inter(&'a Inter),
+    Simple(&'a Simple),
+}
+
+/// Returns the label of `node` and its children, skipping the levels that group nothing.
+fn label_and_children(node: Node<'_>) -> (String, Vec<(&'static str, Node<'_>)>) {
+    match node {
+        Node::Math(x) => match x.roots() {
+            [root] => label_and_children(Node::Root(root)),
+            roots => (
+                "cat".to_string(),
+                roots.iter().map(|x| ("", Node::Root(x))).collect(),
+            ),
+        },
+        Node::Root(Root::Root { root, body }) => (
+            "root".to_string(),
+            vec![("index ", Node::Frac(root)), ("", Node::Frac(body))],
+        ),
+        Node::Root(Root::Math { body }) => label_and_children(Node::Frac(body)),
+        Node::Frac(Frac::Frac { nume, denom }) => (
+            "frac".to_string(),
+            vec![("", Node::Stack(nume)), ("", Node::Stack(denom))],
+        ),
+        Node::Frac(Frac::Math { body }) => label_and_children(Node::Stack(body)),
+        Node::Stack(x) if x.over().is_none() && x.under().is_none() => {
+            label_and_children(Node::Inter(x.body()))
+        }
+        Node::Stack(x) => {
+            let mut children = vec![("", Node::Inter(x.body()))];
+            children.extend(x.over().map(|x| ("over ", Node::Inter(x))));
+            children.extend(x.under().map(|x| ("under ", Node::Inter(x))));
+            ("stack".to_string(), children)
+        }
+        Node::Inter(x) if x.sup().is_none() && x.sub().is_none() => {
+            label_and_children(Node::Simple(x.body()))
+        }
+        Node::Inter(x) => {
+            let mut children = vec![("", Node::Simple(x.body()))];
+            children.extend(x.sup().map(|x| ("^ ", Node::Simple(x))));
+            children.extend(x.sub().map(|x| ("_ ", Node::Simple(x))));
+            ("script".to_string(), children)
+        }
+        Node::Simple(Simple::UnaryExpr { operators, body }) if operators.is_empty() => {
+            label_and_children(Node::Math(body))
+        }
+        Node::Simple(Simple::UnaryExpr { operators, body }) => {
+            (operators.join(" "), vec![("", Node::Math(body))])
+        }
+        Node::Simple(Simple::UnarySymbol { operators, symbol }) => {
+            let symbol = symbol.trim_end_matches(' ');
+            match operators.is_empty() {
+                true => (symbol.to_string(), vec![]),
+                false => (format!("{} {}", operators.join(" "), symbol), vec![]),
+            }
+        }
+        Node::Simple(Simple::UnaryParened {
+            operators,
+            open,
+            body,
+            close,
+        }) => {
+            let brackets = format!("{}…{}", open, close);
+            let label = match operators.is_empty() {
+                true => brackets,
+                false => format!("{} {}", operators.join(" "), brackets),
+            };
+            (label, vec![("", Node::Math(body))])
+        }
+    }
+}
+
+fn tree(node: Node<'_>, label: &str, indent: &str, lines: &mut Vec<String>) {
+    let (name, children) = label_and_children(node);
+    lines.push(format!("{}{}{}", indent, label, name));
+    let indent = indent.replace("├─ ", "│  ").replace("└─ ", "   ");
+    for (i, (label, child)) in children.iter().enumerate() {
+        let branch = match i + 1 == children.len() {
+            true => "└─ ",
+            false => "├─ ",
+        };
+        tree(*child, label, &format!("{}{}", indent, branch), lines);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        assert_eq!(
+            report("a+b /c"),
+            "\
+tokens: Symbol(\"a\") Cat(0) Symbol(\"+\") Cat(0) Symbol(\"b\") Frac(1) Symbol(\"c\")
+tree:
+frac
+├─ cat
+│  ├─ a
+│  ├─ +
+│  └─ b
+└─ c
+tex: \\frac{a+b}{c}
+"
+        );
+        let x = report("a^b^c");
+        assert!(x.contains("            ^\nerror: Double exponent"), "{}", x);
+        assert!(x.ends_with("help: write `a ^b^c`\n"), "{}", x);
+        let x = report("a_b^c_d");
+        assert!(
+            x.contains("help: write `a _b^c_d`\n  or: write `a_b ^c_d`\n"),
+            "{}",
+            x
+        );
+        assert!(report("a b ^c").ends_with("help: write `a  b ^c`\n"));
+        assert!(report("x_i^2").contains("script\n├─ x\n├─ ^ 2\n└─ _ i\n"));
+    }
+
+    #[test]
+    fn test_colored() {
+        assert_eq!(
+            colored("a _b  /\"c\""),
+            "a\x1b[30;46m _\x1b[0mb\x1b[30;45m  /\x1b[0m\x1b[32m\"c\"\x1b[0m"
+        );
+    }
+}
@@ -0,0 +1,446 @@
+//! `maspace serve`: converts formulas over HTTP for other local programs.
+//!
+//! `POST /convert` takes `{"input", "syntax", "format", "profile", "safe", "lint", "recover"}`
+//! and returns the output with the diagnostics and fixes of the conversion. Every connection serves one
+//! request, bodies and headers are limited in size, and connections beyond the cap are
+//! turned away with 503 instead of waiting.
+
+use maspace::diagnostic::Severity;
+use maspace::explain::explain;
+use maspace::omml::omml;
+use maspace::printer::print;
+use maspace::unicode::unicode_text;
+use maspace::{convert, ConvertOptions, Profile, Syntax};
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Map, Value};
+
+/// Headers beyond this many bytes are refused with 431.
+const MAX_HEADER: usize = 8 * 1024;
+/// A client sending nothing for this long is disconnected.
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// How long and how much is read from a client after answering it, before closing.
+const LINGER: Duration = Duration::from_millis(100);
+const MAX_LINGER: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServeOptions {
+    pub address: String,
+    /// Bodies beyond this many bytes are refused with 413.
+    pub max_body: usize,
+    /// Connections served at the same time.
+    pub max_connections: usize,
+}
+
+impl Default for ServeOptions {
+    fn default() -> Self {
+        Self {
+            address: "127.0.0.1:8000".to_string(),
+            max_body: 64 * 1024,
+            max_connections: 16,
+        }
+    }
+}
+
+pub fn parse_args(args: &[String]) -> Result<ServeOptions> {
+    let mut options = ServeOptions::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .with_context(|| format!("missing value of {}", arg))
+        };
+        match arg.as_str() {
+            "--address" => options.address = value()?.clone(),
+            "--max-body" => options.max_body = value()?.parse().context("invalid --max-body")?,
+            "--max-connections" => {
+                options.max_connections = value()?.parse().context("invalid --max-connections")?
+            }
+            x => bail!("unknown option \"{}\"", x),
+        }
+    }
+    Ok(options)
+}
+
+/// Counts a connection as served until dropped.
+struct Slot(Arc<AtomicUsize>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Serves the connections of `listener` forever.
+pub fn serve(listener: TcpListener, options: &ServeOptions) -> Result<()> {
+    let active = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let Ok(mut stream) = stream else {
+            continue;
+        };
+        if active.fetch_add(1, Ordering::SeqCst) >= options.max_connections {
+            active.fetch_sub(1, Ordering::SeqCst);
+            let _ = respond(&mut stream, 503, &json!({"error": "too many connections"}));
+            close(&stream);
+            continue;
+        }
+        let slot = Slot(active.clone());
+        let max_body = options.max_body;
+        thread::spawn(move || {
+            let _slot = slot;
+            // the client is gone when writing fails, and there is nobody to tell
+            let _ = handle(stream, max_body);
+        });
+    }
+    Ok(())
+}
+
+pub fn run(options: &ServeOptions) -> Result<()> {
+    let listener = TcpListener::bind(&options.address)
+        .with_context(|| format!("failed to listen on {}", options.address))?;
+    eprintln!("listening on http://{}/convert", listener.local_addr()?);
+    serve(listener, options)
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        411 => "Length Required",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        _ => "Service Unavailable",
+    }
+}
+
+fn respond(stream: &mut TcpStream, status: u16, body: &Value) -> io::Result<()> {
+    let body = body.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason(status),
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn error(status: u16, message: &str) -> (u16, Value) {
+    (status, json!({"error": message}))
+}
+
+/// Reads one request from `stream` and answers it.
+fn handle(mut stream: TcpStream, max_body: usize) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    let (status, body) = match read_request(&stream, max_body) {
+        Ok(Ok((method, path, body))) => route(&method, &path, &body),
+        Ok(Err(x)) => x,
+        Err(e)
+            if matches!(
+                e.kind(),
+                io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+            ) =>
+        {
+            error(408, "timed out")
+        }
+        Err(e) => return Err(e),
+    };
+    respond(&mut stream, status, &body)?;
+    close(&stream);
+    Ok(())
+}
+
+/// Closes `stream` after briefly reading what the client still sends, such as a refused body,
+/// which closing at once would answer with a reset that can lose the response.
+fn close(stream: &TcpStream) {
+    let _ = stream.shutdown(Shutdown::Write);
+    let _ = stream.set_read_timeout(Some(LINGER));
+    let start = Instant::now();
+    let mut buffer = [0; 4096];
+    let mut read = 0;
+    while let Ok(n @ 1..) = (&*stream).read(&mut buffer) {
+        read += n;
+        if read > MAX_LINGER || start.elapsed() > LINGER {
+            break;
+        }
+    }
+}
+
+/// Reads the method, the path and the body of a request, or the response refusing it.
+#[allow(clippy::type_complexity)]
+fn read_request(
+    stream: &TcpStream,
+    max_body: usize,
+) -> io::Result<Result<(String, String, Vec<u8>), (u16, Value)>> {
+    let mut reader = BufReader::new(stream).take(MAX_HEADER as u64);
+    let mut lines = vec![];
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || !line.ends_with('\n') {
+            return Ok(Err(error(431, "headers too large or incomplete")));
+        }
+        let line = line.trim_end().to_string();
+        if line.is_empty() {
+            break;
+        }
+        lines.push(line);
+    }
+    let mut request_line = lines.first().map_or("", |x| x.as_str()).split(' ');
+    let (Some(method), Some(path)) = (request_line.next(), request_line.next()) else {
+        return Ok(Err(error(400, "invalid request line")));
+    };
+    let length = lines[1..].iter().find_map(|x| {
+        let (name, value) = x.split_once(':')?;
+        name.eq_ignore_ascii_case("content-length")
+            .then(|| value.trim().parse::<usize>().ok())
+    });
+    let mut body = vec![];
+    if method == "POST" {
+        match length {
+            None => return Ok(Err(error(411, "missing Content-Length"))),
+            Some(None) => return Ok(Err(error(400, "invalid Content-Length"))),
+            Some(Some(x)) if x > max_body => {
+                let message = format!("body larger than {} bytes", max_body);
+                return Ok(Err(error(413, &message)));
+            }
+            Some(Some(x)) => {
+                body.resize(x, 0);
+                let mut reader = reader.into_inner();
+                reader.read_exact(&mut body)?;
+            }
+        }
+    }
+    Ok(Ok((method.to_string(), path.to_string(), body)))
+}
+
+/// Answers a request with its status and JSON body.
+fn route(method: &str, path: &str, body: &[u8]) -> (u16, Value) {
+    match (method, path) {
+        ("POST", "/convert") => match serde_json::from_slice::<Value>(body) {
+            Ok(Value::Object(request)) => match convert_request(&request) {
+                Ok(x) => (200, x),
+                Err(e) => error(400, &e.to_string()),
+            },
+            Ok(_) => error(400, "the body must be a JSON object"),
+            Err(e) => error(400, &format!("invalid JSON: {}", e)),
+        },
+        (_, "/convert") => error(405, "use POST"),
+        _ => error(404, "not found"),
+    }
+}
+
+fn string_field<'a>(
+    request: &'a Map<String, Value>,
+    name: &str,
+    default: &'a str,
+) -> Result<&'a str> {
+    match request.get(name) {
+        None => Ok(default),
+        Some(Value::String(x)) => Ok(x),
+        Some(_) => bail!("\"{}\" must be a string", name),
+    }
+}
+
+fn bool_field(request: &Map<String, Value>, name: &str) -> Result<bool> {
+    match request.get(name) {
+        None => Ok(false),
+        Some(Value::Bool(x)) => Ok(*x),
+        Some(_) => bail!("\"{}\" must be a boolean", name),
+    }
+}
+
+/// Converts a request `{"input", "syntax", "format", "profile", "safe", "lint", "recover"}`.
+///
+/// Offsets of the diagnostics count UTF-16 code units, as in JavaScript.
+fn convert_request(request: &Map<String, Value>) -> Result<Value> {
+    let fields = [
+        "input", "syntax", "format", "profile", "safe", "lint", "recover",
+    ];
+    if let Some(x) = request.keys().find(|x| !fields.contains(&x.as_str())) {
+        bail!("unknown field \"{}\"", x);
+    }
+    let Some(Value::String(input)) = request.get("input") else {
+        bail!("missing string \"input\"");
+    };
+    let syntax = match string_field(request, "syntax", "maspace")? {
+        "maspace" => Syntax::Maspace,
+        "asciimath" => Syntax::AsciiMath,
+        x => bail!("unknown syntax \"{}\"", x),
+    };
+    let format = string_field(request, "format", "tex")?;
+    if !["tex", "maspace", "unicode", "omml", "explain"].contains(&format) {
+        bail!("unknown format \"{}\"", format);
+    }
+    let profile = match string_field(request, "profile", "mathjax")? {
+        "mathjax" => Profile::MathJax,
+        "latex" => Profile::Latex,
+        x => bail!("unknown profile \"{}\"", x),
+    };
+    let options = ConvertOptions {
+        syntax,
+        profile,
+        safe: bool_field(request, "safe")?,
+        lint: bool_field(request, "lint")?,
+        recover: bool_field(request, "recover")?,
+    };
+    let conversion = convert(input, &options);
+    let output = match format {
+        "maspace" => conversion.math.as_ref().and_then(|x| print(x).ok()),
+        "unicode" => conversion.math.as_ref().map(unicode_text),
+        "omml" => conversion.math.as_ref().map(omml),
+        "explain" => match syntax {
+            Syntax::Maspace => explain(input).ok(),
+            // AsciiMath is explained through its maSpace spelling
+            Syntax::AsciiMath => conversion
+                .math
+                .as_ref()
+                .and_then(|x| explain(&print(x).ok()?).ok()),
+        },
+        _ => conversion.tex.clone(),
+    };
+    let utf16 = |x: usize| input[..x].encode_utf16().count();
+    let diagnostics = conversion
+        .diagnostics
+        .iter()
+        .map(|x| {
+            json!({
+                "severity": match x.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                },
+                "message": x.message,
+                "start": utf16(x.range.start),
+                "end": utf16(x.range.end),
+                "fixed": x.suggestion.as_ref().map(|y| y.apply(input)),
+            })
+        })
+        .collect::<Vec<_>>();
+    let fixes = conversion
+        .fixes
+        .iter()
+        .map(|x| json!({"input": x.input, "tex": x.tex}))
+        .collect::<Vec<_>>();
+    Ok(json!({
+        "output": output,
+        "tex": conversion.tex,
+        "diagnostics": diagnostics,
+        "fixes": fixes,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(address: &str, request: &str) -> (u16, Value) {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let body = response.split_once("\r\n\r\n").unwrap().1;
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    fn convert_body(body: &str) -> String {
+        format!(
+            "POST /convert HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    }
+
+    #[test]
+    fn test_serve() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let options = ServeOptions {
+            max_body: 100,
+            ..Default::default()
+        };
+        thread::spawn(move || serve(listener, &options));
+
+        let (status, x) = post(&address, &convert_body(r#"{"input": "a+b /c"}"#));
+        assert_eq!(status, 200);
+        assert_eq!(x["output"], r"\frac{a+b}{c}");
+        assert_eq!(x["diagnostics"], json!([]));
+        let (status, x) = post(
+            &address,
+            &convert_body(r#"{"input": "𝐚^b^c", "recover": true}"#),
+        );
+        assert_eq!(status, 200);
+        assert_eq!(x["tex"], r"\mathbf{a}^{b}\boxed{?}^{c}");
+        assert_eq!(x["diagnostics"][0]["start"], 4);
+        assert_eq!(x["fixes"][0]["input"], "𝐚 ^b^c");
+        let (_, x) = post(
+            &address,
+            &convert_body(r#"{"input": "(a+b)/c", "syntax": "asciimath", "format": "maspace"}"#),
+        );
+        assert_eq!(x["output"], "a+b /c");
+
+        let (_, x) = post(
+            &address,
+            &convert_body(r#"{"input": "a`<`b", "profile": "latex"}"#),
+        );
+        assert_eq!(x["output"], "a<b");
+        let (status, x) = post(
+            &address,
+            &convert_body(r#"{"input": "<'input>x", "safe": true}"#),
+        );
+        assert_eq!(status, 200);
+        assert_eq!(x["output"], Value::Null);
+        assert_eq!(
+            x["diagnostics"][0]["message"],
+            r"unknown command `\input` refused in safe mode"
+        );
+        let (status, x) = post(&address, &convert_body(r#"{"input": "a", "mode": 1}"#));
+        assert_eq!(
+            (status, x["error"].as_str()),
+            (400, Some("unknown field \"mode\""))
+        );
+        assert_eq!(post(&address, &convert_body("[")).0, 400);
+        let long = format!(r#"{{"input": "{}"}}"#, "a".repeat(100));
+        assert_eq!(post(&address, &convert_body(&long)).0, 413);
+        assert_eq!(post(&address, "POST /convert HTTP/1.1\r\n\r\n").0, 411);
+        assert_eq!(post(&address, "GET /convert HTTP/1.1\r\n\r\n").0, 405);
+        assert_eq!(post(&address, "GET / HTTP/1.1\r\n\r\n").0, 404);
+    }
+
+    #[test]
+    fn test_max_connections() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let options = ServeOptions {
+            max_connections: 1,
+            ..Default::default()
+        };
+        thread::spawn(move || serve(listener, &options));
+        // holds the only connection by not finishing its request
+        let mut idle = TcpStream::connect(&address).unwrap();
+        idle.write_all(b"POST /convert HTTP/1.1\r\n").unwrap();
+        assert_eq!(post(&address, &convert_body(r#"{"input": "a"}"#)).0, 503);
+        drop(idle);
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = ["--address", "127.0.0.1:9000", "--max-connections", "4"].map(String::from);
+        let x = parse_args(&args).unwrap();
+        assert_eq!(
+            (x.address.as_str(), x.max_connections),
+            ("127.0.0.1:9000", 4)
+        );
+        assert!(parse_args(&["--max-body".to_string()]).is_err());
+    }
+}
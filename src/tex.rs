@@ -0,0 +1,613 @@
+use super::expr::{Frac, Inter, Math, Root, Simple, Stack};
+use super::printer::{spell_close, spell_open, spell_symbol, spellings_of_symbol, Notation};
+use super::token::reverse::{maspace_of_tex, PLACEHOLDER};
+use super::token::{tokenize, Token};
+
+use std::fmt::Display;
+
+#[derive(Debug, Clone)]
+pub struct TexParseError {
+    description: String,
+    position: usize,
+}
+
+impl TexParseError {
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Byte offset in the input where the error was found.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl Display for TexParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.description)?;
+        writeln!(f, "at byte {}", self.position)?;
+        Ok(())
+    }
+}
+
+impl std::error::Error for TexParseError {}
+
+/// Commands taking one argument besides the accents known to the tokenizer.
+const ARGUMENT_COMMANDS: &[&str] = &[
+    "text",
+    "textrm",
+    "textbf",
+    "textit",
+    "operatorname",
+    "overline",
+    "underline",
+    "widehat",
+    "widetilde",
+    "overrightarrow",
+    "overleftarrow",
+    "overbrace",
+    "underbrace",
+    "boldsymbol",
+    "bm",
+    "cancel",
+    "mathrm",
+    "mathbf",
+    "mathit",
+    "mathsf",
+    "mathtt",
+    "mathbb",
+    "mathcal",
+    "mathfrak",
+    "mathscr",
+];
+
+/// Spacing which maSpace has no spelling for. It is dropped.
+const SPACING_COMMANDS: &[&str] = &[",", ":", ";", "!", " "];
+
+/// Parses a formula written in a subset of TeX math into the `expr` AST.
+///
+/// Supported are `\frac`, `\sqrt[n]{..}`, `^`, `_`, `'`, `\overset`, `\underset`,
+/// `\left..\right`, accents, font commands and the symbols known to the tokenizer.
+/// Alignment (`&`, `\\`) and environments are rejected.
+pub fn parse_tex(input: &str) -> Result<Math, TexParseError> {
+    let mut parser = Parser {
+        input,
+        tokens: lex(input)?,
+        pos: 0,
+    };
+    let math = parser.math(Until::End)?;
+    if math.0.is_empty() {
+        return Err(TexParseError {
+            description: "Input is empty".to_string(),
+            position: 0,
+        });
+    }
+    Ok(math)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tok<'a> {
+    Command(&'a str),
+    Char(char),
+    Begin,
+    End,
+    Sup,
+    Sub,
+    Prime,
+}
+
+fn lex(input: &str) -> Result<Vec<(usize, Tok<'_>)>, TexParseError> {
+    let mut tokens = vec![];
+    let mut chars = input.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let tok = match c {
+            '\\' => {
+                let start = i + 1;
+                let mut end = start;
+                while let Some((j, c)) = chars.next_if(|(_, c)| c.is_ascii_alphabetic()) {
+                    end = j + c.len_utf8();
+                }
+                if end == start {
+                    match chars.next() {
+                        Some((j, c)) => end = j + c.len_utf8(),
+                        None => {
+                            return Err(TexParseError {
+                                description: "Missing command name after \"\\\"".to_string(),
+                                position: i,
+                            })
+                        }
+                    }
+                }
+                Tok::Command(&input[start..end])
+            }
+            '{' => Tok::Begin,
+            '}' => Tok::End,
+            '^' => Tok::Sup,
+            '_' => Tok::Sub,
+            '\'' => Tok::Prime,
+            '&' | '$' | '#' | '%' => {
+                return Err(TexParseError {
+                    description: format!("Unsupported character \"{}\"", c),
+                    position: i,
+                })
+            }
+            c if c.is_whitespace() => continue,
+            c => Tok::Char(c),
+        };
+        tokens.push((i, tok));
+    }
+    Ok(tokens)
+}
+
+/// What ends the expression being parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Until {
+    End,
+    Brace,
+    Bracket,
+    Paren,
+    Right,
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<(usize, Tok<'a>)>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Tok<'a>> {
+        self.tokens.get(self.pos).map(|(_, x)| *x)
+    }
+
+    fn next(&mut self) -> Option<Tok<'a>> {
+        let tok = self.peek();
+        self.pos += 1;
+        tok
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .map_or(self.input.len(), |(i, _)| *i)
+    }
+
+    fn error<T>(&self, position: usize, description: &str) -> Result<T, TexParseError> {
+        Err(TexParseError {
+            description: description.to_string(),
+            position,
+        })
+    }
+
+    fn math(&mut self, until: Until) -> Result<Math, TexParseError> {
+        let mut roots = vec![];
+        loop {
+            match (self.peek(), until) {
+                (None, Until::End)
+                | (Some(Tok::End), Until::Brace)
+                | (Some(Tok::Char(']')), Until::Bracket)
+                | (Some(Tok::Char(')')), Until::Paren)
+                | (Some(Tok::Command("right")), Until::Right) => return Ok(Math(roots)),
+                (None, _) => {
+                    let description = match until {
+                        Until::Brace => "Missing \"}\"",
+                        Until::Bracket => "Missing \"]\"",
+                        Until::Paren => "Missing \")\"",
+                        _ => "Missing \"\\right\"",
+                    };
+                    return self.error(self.position(), description);
+                }
+                (Some(Tok::End), _) => return self.error(self.position(), "Unmatched \"}\""),
+                (Some(Tok::Command("right")), _) => {
+                    return self.error(self.position(), "\"\\right\" without \"\\left\"")
+                }
+                _ => {}
+            }
+            let atom = self.atom()?;
+            let atom = self.scripts(atom)?;
+            roots.extend(atom.0);
+        }
+    }
+
+    fn atom(&mut self) -> Result<Math, TexParseError> {
+        let position = self.position();
+        match self.next() {
+            Some(Tok::Begin) => {
+                let math = self.math(Until::Brace)?;
+                self.next();
+                Ok(math)
+            }
+            Some(Tok::Char('~')) => Ok(Math(vec![])),
+            Some(Tok::Char('(')) if self.has_matching_paren() => {
+                let body = self.math(Until::Paren)?;
+                self.next();
+                if body.0.is_empty() {
+                    return self.error(position, "Empty brackets are not supported");
+                }
+                Ok(Math::from(Simple::UnaryParened {
+                    operators: vec![],
+                    open: "(".to_string(),
+                    body,
+                    close: ")".to_string(),
+                }))
+            }
+            Some(Tok::Char(c)) => match symbol_of_char(c) {
+                Some(symbol) => Ok(Math::from(Simple::UnarySymbol {
+                    operators: vec![],
+                    symbol,
+                })),
+                None => self.error(position, &format!("Unsupported character \"{}\"", c)),
+            },
+            Some(Tok::Command(name)) => self.command(name, position),
+            Some(Tok::Sup | Tok::Sub) => self.error(position, "Missing base of a script"),
+            Some(Tok::Prime) => self.error(position, "Prime must follow a symbol or a bracket"),
+            Some(Tok::End) | None => self.error(position, "Unexpected end of input"),
+        }
+    }
+
+    fn scripts(&mut self, mut base: Math) -> Result<Math, TexParseError> {
+        let (mut sup, mut sub) = (None, None);
+        loop {
+            let position = self.position();
+            match self.peek() {
+                Some(Tok::Prime) => {
+                    self.next();
+                    if sup.is_some() || sub.is_some() {
+                        return self.error(position, "Prime after a script is not supported");
+                    }
+                    base = match add_prime(base) {
+                        Some(x) => x,
+                        None => {
+                            return self.error(position, "Prime must follow a symbol or a bracket")
+                        }
+                    };
+                }
+                Some(Tok::Sup) => {
+                    self.next();
+                    if sup.is_some() {
+                        return self.error(position, "Double exponent: use braces to clarify");
+                    }
+                    sup = Some(self.argument()?.into_simple());
+                }
+                Some(Tok::Sub) => {
+                    self.next();
+                    if sub.is_some() {
+                        return self.error(position, "Double subscripts: use braces to clarify");
+                    }
+                    sub = Some(self.argument()?.into_simple());
+                }
+                _ => break,
+            }
+        }
+        if sup.is_none() && sub.is_none() {
+            return Ok(base);
+        }
+        if base.0.is_empty() {
+            return self.error(self.position(), "Missing base of a script");
+        }
+        Ok(Math::from(Stack::from(Inter {
+            body: base.into_simple(),
+            sup,
+            sub,
+        })))
+    }
+
+    /// Parses a braced group or a single token, as the argument of a command or a script.
+    fn argument(&mut self) -> Result<Math, TexParseError> {
+        let position = self.position();
+        let math = match self.peek() {
+            Some(Tok::Char(c)) => {
+                self.next();
+                match symbol_of_char(c) {
+                    Some(symbol) => Math::from(Simple::UnarySymbol {
+                        operators: vec![],
+                        symbol,
+                    }),
+                    None => {
+                        return self.error(position, &format!("Unsupported character \"{}\"", c))
+                    }
+                }
+            }
+            Some(Tok::Begin | Tok::Command(_)) => self.atom()?,
+            _ => return self.error(position, "Missing argument"),
+        };
+        if math.0.is_empty() {
+            return self.error(position, "Empty argument is not supported");
+        }
+        Ok(math)
+    }
+
+    /// Returns the source of the next argument without consuming it.
+    fn raw_argument(&self) -> Option<&'a str> {
+        let (start, tok) = *self.tokens.get(self.pos)?;
+        match tok {
+            Tok::Char(c) => Some(&self.input[start..start + c.len_utf8()]),
+            Tok::Begin => {
+                let mut depth = 0;
+                for (end, tok) in &self.tokens[self.pos..] {
+                    match tok {
+                        Tok::Begin => depth += 1,
+                        Tok::End if depth == 1 => return Some(&self.input[start + 1..*end]),
+                        Tok::End => depth -= 1,
+                        _ => {}
+                    }
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether a bare `(` just consumed is closed by `)` in the same group.
+    fn has_matching_paren(&self) -> bool {
+        let (mut braces, mut parens, mut lefts) = (0, 0, 0);
+        let mut tokens = self.tokens[self.pos..].iter().map(|(_, x)| x);
+        while let Some(tok) = tokens.next() {
+            match tok {
+                Tok::Begin => braces += 1,
+                Tok::End if braces == 0 => return false,
+                Tok::End => braces -= 1,
+                Tok::Command("left") => {
+                    lefts += 1;
+                    tokens.next();
+                }
+                Tok::Command("right") if lefts == 0 => return false,
+                Tok::Command("right") => {
+                    lefts -= 1;
+                    tokens.next();
+                }
+                Tok::Char('(') if braces == 0 && lefts == 0 => parens += 1,
+                Tok::Char(')') if braces == 0 && lefts == 0 && parens == 0 => return true,
+                Tok::Char(')') if braces == 0 && lefts == 0 => parens -= 1,
+                _ => {}
+            }
+        }
+        false
+    }
+
+    fn command(&mut self, name: &'a str, position: usize) -> Result<Math, TexParseError> {
+        let tex = format!("\\{}", name);
+        Ok(match name {
+            "frac" | "dfrac" | "tfrac" => {
+                let nume = self.argument()?;
+                let denom = self.argument()?;
+                Math::from(Frac::Frac {
+                    nume: nume.into_stack(),
+                    denom: denom.into_stack(),
+                })
+            }
+            "sqrt" if self.peek() == Some(Tok::Char('[')) => {
+                self.next();
+                let root = self.math(Until::Bracket)?;
+                self.next();
+                if root.0.is_empty() {
+                    return self.error(position, "Empty root index is not supported");
+                }
+                let body = self.argument()?;
+                Math::from(Root::Root {
+                    root: root.into_frac(),
+                    body: body.into_frac(),
+                })
+            }
+            "overset" => {
+                let over = self.argument()?;
+                let body = self.argument()?;
+                Math::from(Stack {
+                    body: body.into_inter(),
+                    over: Some(over.into_inter()),
+                    under: None,
+                })
+            }
+            "underset" => {
+                let under = Some(self.argument()?.into_inter());
+                Math::from(match self.argument()?.into_stack() {
+                    Stack {
+                        body,
+                        over,
+                        under: None,
+                    } => Stack { body, over, under },
+                    stack => Stack {
+                        body: Inter::from(Simple::from(Math::from(stack))),
+                        over: None,
+                        under,
+                    },
+                })
+            }
+            "left" => {
+                let open = self.delimiter(true)?;
+                let body = self.math(Until::Right)?;
+                self.next();
+                let close = self.delimiter(false)?;
+                if body.0.is_empty() {
+                    return self.error(position, "Empty brackets are not supported");
+                }
+                Math::from(Simple::UnaryParened {
+                    operators: vec![],
+                    open,
+                    body,
+                    close,
+                })
+            }
+            "\\" => return self.error(position, "Line breaks are not supported"),
+            "begin" | "end" => return self.error(position, "Environments are not supported"),
+            _ if SPACING_COMMANDS.contains(&name) => Math(vec![]),
+            _ if name == "sqrt" || takes_argument(&tex) => {
+                let raw = self.raw_argument();
+                let argument = self.argument()?;
+                Math::from(apply_command(&tex, raw, argument))
+            }
+            _ => match symbol_of_command(&tex) {
+                Some(symbol) => Math::from(Simple::UnarySymbol {
+                    operators: vec![],
+                    symbol,
+                }),
+                None => return self.error(position, &format!("Unsupported command \"{}\"", tex)),
+            },
+        })
+    }
+
+    fn delimiter(&mut self, open: bool) -> Result<String, TexParseError> {
+        let position = self.position();
+        let (left, right) = match self.next() {
+            Some(Tok::Char('.')) => return Ok(String::new()),
+            Some(Tok::Char('|') | Tok::Command("vert")) => {
+                (r"\lvert".to_string(), r"\rvert".to_string())
+            }
+            Some(Tok::Command("|" | "Vert")) => (r"\lVert".to_string(), r"\rVert".to_string()),
+            Some(Tok::Command("lbrace" | "rbrace")) => (r"\{".to_string(), r"\}".to_string()),
+            Some(Tok::Char(c)) => (c.to_string(), c.to_string()),
+            Some(Tok::Command(x)) => (format!("\\{}", x), format!("\\{}", x)),
+            _ => return self.error(position, "Missing delimiter"),
+        };
+        let (tex, spelled) = if open {
+            let spelled = spell_open(&left, Notation::Unicode).is_ok();
+            (left, spelled)
+        } else {
+            let spelled = spell_close(&right, Notation::Unicode).is_ok();
+            (right, spelled)
+        };
+        if !spelled {
+            return self.error(position, &format!("Unsupported delimiter \"{}\"", tex));
+        }
+        Ok(tex)
+    }
+}
+
+fn takes_argument(tex: &str) -> bool {
+    ARGUMENT_COMMANDS.contains(&&tex[1..])
+        || maspace_of_tex(tex).iter().any(|x| x.contains(PLACEHOLDER))
+}
+
+/// Applies an accent or a font: a single symbol becomes a symbol such as `\hat{a}` or
+/// `\mathbf{abc}` if maSpace can spell it, anything else is wrapped by an operator.
+pub(crate) fn apply_command(tex: &str, raw: Option<&str>, argument: Math) -> Simple {
+    let symbol = match argument.as_simple() {
+        Some(Simple::UnarySymbol { operators, symbol }) if operators.is_empty() => {
+            Some(format!("{}{{{}}}", tex, symbol.trim_end_matches(' ')))
+        }
+        _ => None,
+    };
+    let literal = raw.map(|x| format!("{}{{{}}}", tex, x));
+    if let Some(symbol) = symbol
+        .into_iter()
+        .chain(literal)
+        .find(|x| spell_symbol(x, Notation::Unicode).is_ok())
+    {
+        return Simple::UnarySymbol {
+            operators: vec![],
+            symbol,
+        };
+    }
+    match argument.into_simple() {
+        Simple::UnaryExpr {
+            mut operators,
+            body,
+        } => {
+            operators.insert(0, tex.to_string());
+            Simple::UnaryExpr { operators, body }
+        }
+        Simple::UnarySymbol {
+            mut operators,
+            symbol,
+        } => {
+            operators.insert(0, tex.to_string());
+            Simple::UnarySymbol { operators, symbol }
+        }
+        Simple::UnaryParened {
+            mut operators,
+            open,
+            body,
+            close,
+        } => {
+            operators.insert(0, tex.to_string());
+            Simple::UnaryParened {
+                operators,
+                open,
+                body,
+                close,
+            }
+        }
+    }
+}
+
+pub(crate) fn add_prime(math: Math) -> Option<Math> {
+    math.as_simple()?;
+    Some(Math::from(match math.into_simple() {
+        Simple::UnarySymbol { operators, symbol } if operators.is_empty() => Simple::UnarySymbol {
+            operators,
+            symbol: format!("{}'", symbol.trim_end_matches(' ')),
+        },
+        Simple::UnaryParened {
+            operators,
+            open,
+            body,
+            close,
+        } if operators.is_empty() => Simple::UnaryParened {
+            operators,
+            open,
+            body,
+            close: format!("{}'", close),
+        },
+        _ => return None,
+    }))
+}
+
+/// Returns the symbol as the tokenizer writes it, e.g. `\alpha ` for `\alpha`.
+pub(crate) fn symbol_of_command(tex: &str) -> Option<String> {
+    spellings_of_symbol(tex)
+        .iter()
+        .find_map(|x| match tokenize(x).ok().as_deref() {
+            Some([Token::Symbol(y)]) if y.trim_end_matches(' ') == tex => Some(y.to_owned()),
+            _ => None,
+        })
+}
+
+pub(crate) fn symbol_of_char(c: char) -> Option<String> {
+    match c {
+        '<' => return symbol_of_command(r"\lt"),
+        '>' => return symbol_of_command(r"\gt"),
+        _ => {}
+    }
+    match tokenize(&c.to_string()).ok().as_deref() {
+        Some([Token::Symbol(x)]) if !c.is_ascii() || *x == c.to_string() => Some(x.to_owned()),
+        _ => symbol_of_command(&c.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tex() {
+        fn x(s: &str) -> String {
+            parse_tex(s)
+                .unwrap()
+                .to_string()
+                .trim_end_matches(' ')
+                .to_string()
+        }
+        assert_eq!(x(r"\frac{a}{b}"), r"\frac{a}{b}");
+        assert_eq!(x(r"\frac12"), r"\frac{1}{2}");
+        assert_eq!(x(r"\sqrt[3]{x+1}"), r"\sqrt[3]{x+1}");
+        assert_eq!(x(r"\sqrt{x+1}"), r"\sqrt{x+1}");
+        assert_eq!(x(r"a^{b}_c"), r"a^{b}_{c}");
+        assert_eq!(x(r"\alpha'"), r"\alpha'");
+        assert_eq!(x(r"\hat{\alpha}"), r"\hat{\alpha}");
+        assert_eq!(x(r"\hat{a+b}"), r"\hat{a+b}");
+        assert_eq!(x(r"\mathbf{abc}"), r"\mathbf{abc}");
+        assert_eq!(x(r"\left( a \right."), r"\left(a\right.");
+        assert_eq!(x(r"\left| a \right|"), r"\left\lvert a\right\rvert");
+        assert_eq!(
+            x(r"\underset{u}{\overset{o}{=}}"),
+            r"\underset{u}{\overset{o}{=}}"
+        );
+        assert_eq!(x(r"f(x)"), r"f\left(x\right)");
+        assert_eq!(x(r"[0, 1)"), r"[0,1)");
+        assert_eq!(x(r"a < b"), r"a\lt b");
+        assert!(parse_tex(r"a^b^c").is_err());
+        assert!(parse_tex(r"a & b").is_err());
+        assert!(parse_tex(r"\frac{a}").is_err());
+        assert_eq!(parse_tex(r"{a").unwrap_err().position, 2);
+    }
+}
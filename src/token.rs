@@ -1,14 +1,15 @@
 pub mod binop;
+pub mod completion;
 pub mod grouping;
+pub mod reverse;
 pub mod symbol;
 pub mod unaryop;
 pub mod unicode_subsup;
 pub mod util;
 
 use nom::branch::alt;
-use nom::combinator::{eof, not};
-use nom::multi::many0;
-use nom::sequence::{preceded, terminated};
+use nom::IResult;
+use std::ops::Range;
 use unicode_normalization::UnicodeNormalization;
 
 use binop::take_binop;
@@ -18,6 +19,8 @@ use unaryop::take_op;
 use unicode_subsup::{take_unicode_sub, take_unicode_sup};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum Token {
     Cat(usize),
     Sub(usize),
@@ -50,48 +53,106 @@ impl Token {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TokenizeError {
     description: String,
     detail: Option<String>,
+    position: usize,
+}
+
+impl TokenizeError {
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Byte offset in the input where tokenizing stopped.
+    pub fn position(&self) -> usize {
+        self.position
+    }
 }
 
 impl std::fmt::Display for TokenizeError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "{}", self.description)?;
+        write!(f, "{}", self.description)?;
         if let Some(detail) = &self.detail {
-            writeln!(f, "{}", detail)?;
+            write!(f, " {}", detail)?;
         }
-        Ok(())
+        writeln!(f)
     }
 }
 
 impl std::error::Error for TokenizeError {}
 
+/// Tokenizes like [`tokenize_with_spans`], but refuses a TeX command the symbol tables do not
+/// write, such as `\input` of `<'input>`, instead of passing it through.
+pub fn tokenize_safe(s: &str) -> Result<Vec<(Token, Range<usize>)>, TokenizeError> {
+    let tokens = tokenize_with_spans(s)?;
+    for (token, span) in &tokens {
+        let (Token::Symbol(tex) | Token::Op(tex, _) | Token::Open(tex) | Token::Close(tex)) = token
+        else {
+            continue;
+        };
+        if let Some(name) = reverse::commands(tex).find(|x| !reverse::is_known_command(x)) {
+            return Err(TokenizeError {
+                description: format!("unknown command `\\{}` refused in safe mode", name),
+                detail: None,
+                position: span.start,
+            });
+        }
+    }
+    Ok(tokens)
+}
+
+pub(crate) fn take_token(s: &str) -> IResult<&str, Token> {
+    alt((
+        take_symbol,
+        take_op,
+        take_open,
+        take_close,
+        take_unicode_sub,
+        take_unicode_sup,
+        take_binop,
+    ))(s)
+}
+
 pub fn tokenize(s: &str) -> Result<Vec<Token>, TokenizeError> {
-    // normalize
-    let s = s.nfd().to_string();
-    let s = s.trim();
+    Ok(tokenize_with_spans(s)?
+        .into_iter()
+        .map(|(token, _)| token)
+        .collect())
+}
+
+/// Same as [`tokenize`] but pairs each token with its byte range in `s`.
+///
+/// Virtual tokens, such as the cat⁰ between connected symbols or the brackets
+/// around unicode scripts, get an empty range where they are inserted.
+pub fn tokenize_with_spans(s: &str) -> Result<Vec<(Token, Range<usize>)>, TokenizeError> {
+    // normalize, keeping the offset in `s` of every byte of the normalized string
+    let mut offsets = vec![];
+    for (i, c) in s.char_indices() {
+        offsets.extend(std::iter::repeat_n(i, c.nfd().map(char::len_utf8).sum()));
+    }
+    offsets.push(s.len());
+    let normalized = s.nfd().to_string();
+    let trimmed = normalized.trim();
+    // every rest is a suffix of `trimmed`, which ends where the trailing spaces start
+    let trimmed_end = normalized.trim_end().len();
+    let offset = |rest: &str| offsets[trimmed_end - rest.len()];
     // tokenize
-    let (_, nom_outputs) = terminated(
-        many0(preceded(
-            not(eof),
-            alt((
-                take_symbol,
-                take_op,
-                take_open,
-                take_close,
-                take_unicode_sub,
-                take_unicode_sup,
-                take_binop,
-            )),
-        )),
-        eof,
-    )(&s)
-    .map_err(|x| TokenizeError {
-        description: "tokenize failed".to_string(),
-        detail: Some(format!("{:?}", x)),
-    })?;
+    let mut nom_outputs = vec![];
+    let mut rest = trimmed;
+    while !rest.is_empty() {
+        let (next, token) = take_token(rest)
+            .ok()
+            .filter(|(next, _)| next.len() < rest.len())
+            .ok_or_else(|| TokenizeError {
+                description: "unknown token".to_string(),
+                detail: Some(format!("at {:?}", rest)),
+                position: offset(rest),
+            })?;
+        nom_outputs.push((token, offset(rest)..offset(next)));
+        rest = next;
+    }
     // remove unicode sub/sup
     let mut no_unicode_sub_sup = vec![];
     enum Mode {
@@ -100,93 +161,122 @@ pub fn tokenize(s: &str) -> Result<Vec<Token>, TokenizeError> {
         Normal,
     }
     let mut mode = Mode::Normal;
-    for x in nom_outputs {
+    let mut end = offset(trimmed);
+    for (x, span) in nom_outputs {
+        let here = || span.start..span.start;
         match x {
             Token::UnicodeSub(y) => {
                 match mode {
                     Mode::Normal => {
-                        no_unicode_sub_sup.push(Token::Sub(0));
-                        no_unicode_sub_sup.push(Token::Open("".to_string()));
+                        no_unicode_sub_sup.push((Token::Sub(0), here()));
+                        no_unicode_sub_sup.push((Token::Open("".to_string()), here()));
                     }
                     Mode::Sub => {}
                     Mode::Sup => {
-                        no_unicode_sub_sup.push(Token::Close("".to_string()));
-                        no_unicode_sub_sup.push(Token::Sub(0));
-                        no_unicode_sub_sup.push(Token::Open("".to_string()));
+                        no_unicode_sub_sup.push((Token::Close("".to_string()), here()));
+                        no_unicode_sub_sup.push((Token::Sub(0), here()));
+                        no_unicode_sub_sup.push((Token::Open("".to_string()), here()));
                     }
                 };
-                no_unicode_sub_sup.push(*y);
+                no_unicode_sub_sup.push((*y, span.clone()));
                 mode = Mode::Sub;
             }
             Token::UnicodeSup(y) => {
                 match mode {
                     Mode::Normal => {
-                        no_unicode_sub_sup.push(Token::Sup(0));
-                        no_unicode_sub_sup.push(Token::Open("".to_string()));
+                        no_unicode_sub_sup.push((Token::Sup(0), here()));
+                        no_unicode_sub_sup.push((Token::Open("".to_string()), here()));
                     }
                     Mode::Sub => {
-                        no_unicode_sub_sup.push(Token::Close("".to_string()));
-                        no_unicode_sub_sup.push(Token::Sup(0));
-                        no_unicode_sub_sup.push(Token::Open("".to_string()));
+                        no_unicode_sub_sup.push((Token::Close("".to_string()), here()));
+                        no_unicode_sub_sup.push((Token::Sup(0), here()));
+                        no_unicode_sub_sup.push((Token::Open("".to_string()), here()));
                     }
                     Mode::Sup => {}
                 };
-                no_unicode_sub_sup.push(*y);
+                no_unicode_sub_sup.push((*y, span.clone()));
                 mode = Mode::Sup;
             }
             _ => {
                 match mode {
-                    Mode::Sub | Mode::Sup => no_unicode_sub_sup.push(Token::Close("".to_string())),
+                    Mode::Sub | Mode::Sup => {
+                        no_unicode_sub_sup.push((Token::Close("".to_string()), here()))
+                    }
                     Mode::Normal => {}
                 }
-                no_unicode_sub_sup.push(x);
+                no_unicode_sub_sup.push((x, span.clone()));
                 mode = Mode::Normal;
             }
         };
+        end = span.end;
     }
     match mode {
-        Mode::Sub | Mode::Sup => no_unicode_sub_sup.push(Token::Close("".to_string())),
+        Mode::Sub | Mode::Sup => no_unicode_sub_sup.push((Token::Close("".to_string()), end..end)),
         Mode::Normal => {}
     }
     // insert Cat(0) between adjacent symbol
     let mut cat_inserted = vec![];
     let mut after_symbol = false;
-    for x in no_unicode_sub_sup {
+    for (x, span) in no_unicode_sub_sup {
+        let here = span.start..span.start;
         match x {
             Token::Symbol(_) => {
                 if after_symbol {
-                    cat_inserted.push(Token::Cat(0));
+                    cat_inserted.push((Token::Cat(0), here));
                 }
-                cat_inserted.push(x);
+                cat_inserted.push((x, span));
                 after_symbol = true;
             }
             Token::Open(_) => {
                 if after_symbol {
-                    cat_inserted.push(Token::Cat(0));
+                    cat_inserted.push((Token::Cat(0), here));
                 }
-                cat_inserted.push(x);
+                cat_inserted.push((x, span));
                 after_symbol = false;
             }
             Token::Close(_) => {
-                cat_inserted.push(x);
+                cat_inserted.push((x, span));
                 after_symbol = true;
             }
             _ => {
-                cat_inserted.push(x);
+                cat_inserted.push((x, span));
                 after_symbol = false;
             }
         }
     }
     let max_order = cat_inserted
         .iter()
-        .map(|x| x.order())
+        .map(|(x, _)| x.order())
         .max()
         .unwrap_or_default();
     Ok(cat_inserted
         .into_iter()
-        .map(|x| match x {
-            Token::Cat(ord) if ord > 0 => Token::Cat(max_order),
-            _ => x,
+        .map(|(x, span)| match x {
+            Token::Cat(ord) if ord > 0 => (Token::Cat(max_order), span),
+            _ => (x, span),
         })
         .collect::<Vec<_>>())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_with_spans() {
+        let input = " â²+b  /c";
+        let spans = tokenize_with_spans(input).unwrap();
+        let x = |i: usize| (&spans[i].0, &input[spans[i].1.clone()]);
+        assert_eq!(x(0), (&Token::Symbol(r"\hat{a}".to_string()), "â"));
+        assert_eq!(x(1), (&Token::Sup(0), ""));
+        assert_eq!(x(2), (&Token::Open("".to_string()), ""));
+        assert_eq!(x(3), (&Token::Symbol("2".to_string()), "²"));
+        assert_eq!(x(4), (&Token::Close("".to_string()), ""));
+        assert_eq!(x(5), (&Token::Cat(0), ""));
+        assert_eq!(x(6), (&Token::Symbol("+".to_string()), "+"));
+        assert_eq!(x(9), (&Token::Frac(2), "  /"));
+        assert_eq!(spans[10].1, 10..11);
+        assert_eq!(tokenize("a `zz").unwrap_err().position(), 2);
+        assert_eq!(tokenize_with_spans("a^b ").unwrap()[2].1, 2..3);
+    }
+}
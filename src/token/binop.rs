@@ -10,25 +10,25 @@ pub fn take_binop(s: &str) -> IResult<&str, Token> {
 }
 
 fn take_sub(s: &str) -> IResult<&str, Token> {
-    map(count_space_around(char('_')), |x| Token::Sub(x))(s)
+    map(count_space_around(char('_')), Token::Sub)(s)
 }
 fn take_under(s: &str) -> IResult<&str, Token> {
-    map(count_space_around(tag("__")), |x| Token::Under(x))(s)
+    map(count_space_around(tag("__")), Token::Under)(s)
 }
 fn take_sup(s: &str) -> IResult<&str, Token> {
-    map(count_space_around(char('^')), |x| Token::Sup(x))(s)
+    map(count_space_around(char('^')), Token::Sup)(s)
 }
 fn take_over(s: &str) -> IResult<&str, Token> {
-    map(count_space_around(tag("^^")), |x| Token::Over(x))(s)
+    map(count_space_around(tag("^^")), Token::Over)(s)
 }
 fn take_root(s: &str) -> IResult<&str, Token> {
-    map(count_space_around(tag("_/")), |x| Token::Root(x))(s)
+    map(count_space_around(tag("_/")), Token::Root)(s)
 }
 fn take_frac(s: &str) -> IResult<&str, Token> {
-    map(count_space_around(char('/')), |x| Token::Frac(x))(s)
+    map(count_space_around(char('/')), Token::Frac)(s)
 }
 fn take_cat(s: &str) -> IResult<&str, Token> {
-    map(num_space, |n| Token::Cat(n))(s)
+    map(num_space, Token::Cat)(s)
 }
 
 #[cfg(test)]
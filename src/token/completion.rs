@@ -0,0 +1,211 @@
+use super::grouping::*;
+use super::reverse::*;
+use super::symbol::*;
+use super::take_token;
+
+use nom::{
+    bytes::complete::{is_not, tag},
+    character::complete::{alpha0, space0},
+    combinator::{all_consuming, map, opt},
+    multi::many0,
+    sequence::{pair, preceded},
+    IResult,
+};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Completion {
+    /// Text replacing `input[start..cursor]`.
+    pub text: String,
+    /// Byte offset where the completed word starts.
+    pub start: usize,
+    /// TeX produced by the completed spelling.
+    pub tex: String,
+}
+
+/// Returns candidate completions for the word ending at the byte offset `cursor`.
+///
+/// The context is worked out from the unfinished token before the cursor:
+/// a symbol name after `<`, an accent name after `<symbol `, an operator name
+/// after `<'` or an ascii art after `` ` ``.
+pub fn complete(input: &str, cursor: usize) -> Vec<Completion> {
+    let before = match input.get(..cursor) {
+        Some(x) => x,
+        None => return vec![],
+    };
+    let rest = unfinished_token(before);
+    let at = |prefix: &str| cursor - prefix.len();
+    if let Ok((_, prefix)) = take_op_name_prefix(rest) {
+        return rank(prefix, op_names(), &[])
+            .into_iter()
+            .map(|(text, tex)| Completion {
+                text,
+                start: at(prefix),
+                tex,
+            })
+            .collect();
+    }
+    if let Ok((_, prefix)) = take_ascii_art_prefix(rest) {
+        let mut arts = ASCII_ART_SYMBOLS
+            .iter()
+            .chain(ASCII_ART_OPENS)
+            .chain(ASCII_ART_CLOSES)
+            .filter(|(art, _)| art.starts_with(prefix))
+            .collect::<Vec<_>>();
+        arts.sort_by_key(|(art, _)| art.len());
+        return arts
+            .into_iter()
+            .map(|(art, tex)| Completion {
+                text: art.to_string(),
+                start: at(prefix),
+                tex: tex.trim_end_matches(' ').to_string(),
+            })
+            .collect();
+    }
+    if let Ok((_, prefix)) = take_symbol_name_prefix(rest) {
+        return rank(prefix, symbol_names(), SYMBOL_NAME_ABBREVIATIONS)
+            .into_iter()
+            .map(|(text, tex)| Completion {
+                text,
+                start: at(prefix),
+                tex,
+            })
+            .collect();
+    }
+    if let Ok((accents, _)) = take_symbol_head_in_angle_brackets(rest) {
+        let completed = accents.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+        let word = &accents[completed.len()..];
+        if completed.ends_with(' ') && take_accents(completed.trim_end()).is_ok() {
+            let abbreviations = ACCENT_NAME_ABBREVIATIONS
+                .iter()
+                .map(|(abbr, tex)| (*abbr, tex.trim_start_matches('\\')))
+                .collect::<Vec<_>>();
+            return rank(word, accent_names(), &abbreviations)
+                .into_iter()
+                .map(|(text, tex)| Completion {
+                    text,
+                    start: at(word),
+                    tex,
+                })
+                .collect();
+        }
+    }
+    vec![]
+}
+
+fn take_op_name_prefix(s: &str) -> IResult<&str, &str> {
+    all_consuming(preceded(pair(tag("<'"), space0), alpha0))(s)
+}
+
+fn take_ascii_art_prefix(s: &str) -> IResult<&str, &str> {
+    all_consuming(preceded(
+        tag("`"),
+        map(opt(is_not("`")), Option::unwrap_or_default),
+    ))(s)
+}
+
+fn take_symbol_name_prefix(s: &str) -> IResult<&str, &str> {
+    all_consuming(preceded(pair(tag("<"), space0), alpha0))(s)
+}
+
+fn take_accents(s: &str) -> IResult<&str, Vec<String>> {
+    all_consuming(many0(take_accent_in_angle_brackets))(s)
+}
+
+/// Skips complete tokens with the tokenizer and returns what is left.
+fn unfinished_token(s: &str) -> &str {
+    let mut rest = s;
+    loop {
+        match take_token(rest) {
+            Ok((next, _)) if next.len() < rest.len() => rest = next,
+            _ => return rest,
+        }
+    }
+}
+
+/// Ranks names matching `prefix`: exact abbreviations first, then names
+/// starting with `prefix`, then names with an abbreviation starting with `prefix`.
+fn rank(prefix: &str, names: Vec<String>, abbreviations: &[(&str, &str)]) -> Vec<(String, String)> {
+    let tier = |name: &str| {
+        let abbrs = abbreviations.iter().filter(|(_, x)| *x == name);
+        if abbrs.clone().any(|(abbr, _)| *abbr == prefix) {
+            Some(0)
+        } else if name.starts_with(prefix) {
+            Some(1)
+        } else if abbrs.clone().any(|(abbr, _)| abbr.starts_with(prefix)) {
+            Some(2)
+        } else {
+            None
+        }
+    };
+    let mut ranked = names
+        .into_iter()
+        .filter_map(|name| tier(&name).map(|t| (t, name)))
+        .collect::<Vec<_>>();
+    ranked.sort_by(|(t, x), (s, y)| t.cmp(s).then(x.len().cmp(&y.len())).then(x.cmp(y)));
+    ranked
+        .into_iter()
+        .map(|(_, name)| {
+            let tex = format!("\\{}", name);
+            (name, tex)
+        })
+        .collect()
+}
+
+fn names(f: impl Fn(&str) -> bool) -> Vec<String> {
+    let mut names = index()
+        .iter()
+        .filter(|(_, spellings)| spellings.iter().any(|x| f(x)))
+        .filter_map(|(tex, _)| tex.strip_prefix('\\'))
+        .filter(|x| x.len() > 1 && x.chars().all(|c| c.is_ascii_alphabetic()))
+        .map(String::from)
+        .collect::<Vec<_>>();
+    names.sort();
+    names.dedup();
+    names
+}
+
+fn symbol_names() -> Vec<String> {
+    names(|x| !x.contains(PLACEHOLDER))
+}
+
+fn accent_names() -> Vec<String> {
+    names(|x| x.starts_with(PLACEHOLDER) || x.starts_with(&format!("<{}", PLACEHOLDER)))
+}
+
+fn op_names() -> Vec<String> {
+    let mut names = names(|x| x.contains(PLACEHOLDER));
+    names.push("root".to_string());
+    names.sort();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete() {
+        fn x(s: &str) -> Vec<String> {
+            complete(s, s.len()).into_iter().map(|x| x.text).collect()
+        }
+        assert_eq!(x("<alp")[0], "alpha");
+        assert_eq!(x("a + <al")[0], "alpha");
+        assert_eq!(x("<vep")[0], "varepsilon");
+        assert_eq!(x("`--")[..2], ["-->", "---"]);
+        assert_eq!(x("<'sq"), ["sqrt"]);
+        assert_eq!(x("<a d")[..2], ["dot", "ddot"]);
+        assert_eq!(x("<alpha hat ti"), ["tilde"]);
+        assert!(x("<a ").contains(&"hat".to_string()));
+        assert!(x("alp").is_empty());
+        assert!(x("<alpha>").is_empty());
+        assert_eq!(
+            complete("<al> + b", 3)[0],
+            Completion {
+                text: "alpha".to_string(),
+                start: 1,
+                tex: r"\alpha".to_string(),
+            }
+        );
+        assert!(complete("α", 1).is_empty());
+    }
+}
@@ -20,7 +20,7 @@ pub fn take_open(s: &str) -> IResult<&str, Token> {
                 ),
                 map_res(anychar, tex_of_char_open),
             )),
-            |x| Token::Open(x),
+            Token::Open,
         ),
         opt(is_a(" ")),
     )(s)
@@ -46,7 +46,7 @@ pub fn take_close(s: &str) -> IResult<&str, Token> {
     )(s)
 }
 
-fn tex_of_char_open(c: char) -> Result<String, ()> {
+pub(super) fn tex_of_char_open(c: char) -> Result<String, ()> {
     Ok(match c {
         '[' => "",
         '(' => "(",
@@ -61,7 +61,7 @@ fn tex_of_char_open(c: char) -> Result<String, ()> {
     .to_string())
 }
 
-fn tex_of_char_close(c: char) -> Result<String, ()> {
+pub(super) fn tex_of_char_close(c: char) -> Result<String, ()> {
     Ok(match c {
         ']' => "",
         ')' => ")",
@@ -76,42 +76,50 @@ fn tex_of_char_close(c: char) -> Result<String, ()> {
     .to_string())
 }
 
+pub(super) const ASCII_ART_OPENS: &[(&str, &str)] = &[
+    ("[[", "["),
+    ("[<", r"\langle"),
+    ("[|", r"\lvert"),
+    ("[||", r"\lVert"),
+    ("[^", r"\lceil"),
+    ("[_", r"\lfloor"),
+    ("[|^", r"\ulcorner"),
+    ("[|_", r"\llcorner"),
+    ("[[]", "]"),
+    ("[[)", ")"),
+    ("[[}", r"\}"),
+    ("[[>", r"\rangle"),
+];
+
 fn tex_of_ascii_art_open(s: &str) -> Result<String, ()> {
-    Ok(match s {
-        "[[" => "[",
-        "[<" => r"\langle",
-        "[|" => r"\lvert",
-        "[||" => r"\lVert",
-        "[^" => r"\lceil",
-        "[_" => r"\lfloor",
-        "[|^" => r"\ulcorner",
-        "[|_" => r"\llcorner",
-        "[[]" => "]",
-        "[[)" => ")",
-        "[[}" => r"\}",
-        "[[>" => r"\rangle",
-        _ => return Err(()),
-    }
-    .to_string())
+    ASCII_ART_OPENS
+        .iter()
+        .find(|(art, _)| *art == s)
+        .map(|(_, tex)| tex.to_string())
+        .ok_or(())
 }
 
+pub(super) const ASCII_ART_CLOSES: &[(&str, &str)] = &[
+    ("]]", "]"),
+    (">]", r"\rangle"),
+    ("|]", r"\rvert"),
+    ("||]", r"\rVert"),
+    ("^]", r"\rceil"),
+    ("_]", r"\rfloor"),
+    ("^|]", r"\urcorner"),
+    ("_|]", r"\lrcorner"),
+    ("[]]", "["),
+    ("(]]", "("),
+    ("{]]", r"\{"),
+    ("<]]", r"\langle"),
+];
+
 fn tex_of_ascii_art_close(s: &str) -> Result<String, ()> {
-    Ok(match s {
-        "]]" => "]",
-        ">]" => r"\rangle",
-        "|]" => r"\rvert",
-        "||]" => r"\rVert",
-        "^]" => r"\rceil",
-        "_]" => r"\rfloor",
-        "^|]" => r"\urcorner",
-        "_|]" => r"\lrcorner",
-        "[]]" => "[",
-        "(]]" => "(",
-        "{]]" => r"\{",
-        "<]]" => r"\langle",
-        _ => return Err(()),
-    }
-    .to_string())
+    ASCII_ART_CLOSES
+        .iter()
+        .find(|(art, _)| *art == s)
+        .map(|(_, tex)| tex.to_string())
+        .ok_or(())
 }
 
 #[cfg(test)]
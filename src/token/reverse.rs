@@ -0,0 +1,211 @@
+use super::grouping::*;
+use super::symbol::*;
+use super::tokenize;
+use super::unaryop::*;
+use super::Token;
+
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+/// Stands for the argument of an accent or an operator, e.g. `<◌ hat>` or `<'hat>◌`.
+pub const PLACEHOLDER: char = '◌';
+
+/// Returns every maSpace spelling that produces the TeX command `tex`, shortest first.
+///
+/// Symbols and delimiters are spelled as they are (`α`, `` `->>` ``, `` `[<` ``).
+/// Accents and operators need an argument, which is written as [`PLACEHOLDER`].
+pub fn maspace_of_tex(tex: &str) -> Vec<String> {
+    let tex = tex.trim();
+    let mut spellings = index().get(tex).cloned().unwrap_or_default();
+    if let Some(name) = tex
+        .strip_prefix('\\')
+        .filter(|x| x.len() > 1 && x.chars().all(|c| c.is_ascii_alphabetic()))
+    {
+        let candidates = if is_accent(tex) {
+            vec![
+                format!("<{} {}>", PLACEHOLDER, name),
+                format!("<'{}>{}", name, PLACEHOLDER),
+            ]
+        } else {
+            vec![format!("<{}>", name)]
+        };
+        spellings.extend(candidates.into_iter().filter(|x| produces(x, tex)));
+    }
+    let mut unique = vec![];
+    for x in spellings {
+        if !unique.contains(&x) {
+            unique.push(x);
+        }
+    }
+    unique.sort_by_key(|x| x.chars().count());
+    unique
+}
+
+/// Returns the Unicode character of the TeX command `tex`, e.g. `α` for `\alpha` and `⟨` for `\langle`.
+///
+/// Accents give their combining character, `\u{302}` for `\hat`, and operators their sign, `√` for `\sqrt`.
+/// Commands spelled as a single character of ASCII art give that character, `<` for `\lt`.
+pub fn unicode_of_tex(tex: &str) -> Option<String> {
+    maspace_of_tex(tex).into_iter().find_map(|x| {
+        let y = x.replace(PLACEHOLDER, "");
+        let y = match y.strip_prefix('`').and_then(|y| y.strip_suffix('`')) {
+            Some(art) => art.to_string(),
+            None => y,
+        };
+        (y.chars().count() == 1).then_some(y)
+    })
+}
+
+/// Returns the names of the TeX commands in `tex`, e.g. `mathbf` and `alpha` for `\mathbf{\alpha}`.
+pub(crate) fn commands(tex: &str) -> impl Iterator<Item = &str> {
+    tex.split('\\').skip(1).filter_map(|x| {
+        let end = x
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(x.len());
+        (end > 0).then(|| &x[..end])
+    })
+}
+
+/// Returns whether the symbol tables write the TeX command `name`, given without its backslash.
+///
+/// Other names are passed through as written by `<name>` and `<'name>`.
+pub fn is_known_command(name: &str) -> bool {
+    static KNOWN: OnceLock<HashSet<String>> = OnceLock::new();
+    KNOWN
+        .get_or_init(|| {
+            // string literals are escaped and wrapped by commands of their own
+            let strings = [r#""\^~""#, r#"<"\^~">"#]
+                .into_iter()
+                .flat_map(|x| tokenize(x).unwrap_or_default())
+                .filter_map(|x| match x {
+                    Token::Symbol(x) => Some(x),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            index()
+                .keys()
+                .chain(&strings)
+                .flat_map(|x| commands(x))
+                .map(String::from)
+                .collect()
+        })
+        .contains(name)
+}
+
+fn is_accent(tex: &str) -> bool {
+    tex == r"\sqrt"
+        || ACCENT_NAME_ABBREVIATIONS.iter().any(|(_, x)| *x == tex)
+        || ('\u{0300}'..='\u{20FF}').any(|c| tex_of_unicode_accent(c).is_ok_and(|x| x == tex))
+}
+
+pub(super) fn index() -> &'static HashMap<String, Vec<String>> {
+    static INDEX: OnceLock<HashMap<String, Vec<String>>> = OnceLock::new();
+    INDEX.get_or_init(|| {
+        let mut candidates = vec![];
+        for c in '\0'..='\u{1FFFF}' {
+            for tex in [tex_of_char(c), tex_of_char_open(c), tex_of_char_close(c)]
+                .into_iter()
+                .flatten()
+            {
+                candidates.push((tex, c.to_string()));
+            }
+            if let Ok(tex) = tex_of_unicode_accent(c) {
+                candidates.push((tex, format!("{}{}", PLACEHOLDER, c)));
+            }
+            if let Ok(tex) = tex_of_unicode_op(c) {
+                candidates.push((tex, format!("{}{}", c, PLACEHOLDER)));
+            }
+        }
+        for c in ['^', '_', '{', '}', '/', '[', ']', '(', ')'] {
+            candidates.push((format!("\\{}", c), format!("<{}>", c)));
+            candidates.push((c.to_string(), format!("<{}>", c)));
+        }
+        for (art, tex) in ASCII_ART_SYMBOLS
+            .iter()
+            .chain(ASCII_ART_OPENS)
+            .chain(ASCII_ART_CLOSES)
+        {
+            candidates.push((tex.to_string(), format!("`{}`", art)));
+        }
+        for (abbr, name) in SYMBOL_NAME_ABBREVIATIONS {
+            candidates.push((format!("\\{}", name), format!("<{}>", abbr)));
+        }
+        for (abbr, tex) in ACCENT_NAME_ABBREVIATIONS {
+            let sep = if abbr.chars().all(|c| c.is_ascii_alphanumeric()) {
+                " "
+            } else {
+                ""
+            };
+            candidates.push((tex.to_string(), format!("<{}{}{}>", PLACEHOLDER, sep, abbr)));
+        }
+        let mut index: HashMap<String, Vec<String>> = HashMap::new();
+        for (tex, spelling) in candidates {
+            let tex = tex.trim_end_matches(' ');
+            if produces(&spelling, tex) {
+                index.entry(tex.to_string()).or_default().push(spelling);
+            }
+        }
+        index
+    })
+}
+
+fn produces(spelling: &str, tex: &str) -> bool {
+    let arg = "x".to_string();
+    let tokens = match tokenize(&spelling.replace(PLACEHOLDER, &arg)) {
+        Ok(tokens) => tokens,
+        Err(_) => return false,
+    };
+    match &tokens[..] {
+        [Token::Symbol(x) | Token::Open(x) | Token::Close(x)]
+            if !spelling.contains(PLACEHOLDER) =>
+        {
+            x.trim_end_matches(' ') == tex
+        }
+        [Token::Symbol(x)] => *x == format!("{}{{{}}}", tex, arg),
+        [Token::Op(x, 0), Token::Symbol(y)] => x == tex && *y == arg,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maspace_of_tex() {
+        fn x(tex: &str) -> Vec<String> {
+            maspace_of_tex(tex)
+        }
+        assert_eq!(x(r"\alpha")[0], "α");
+        assert!(x(r"\alpha").ends_with(&["<al>", "<alp>", "<alpha>"].map(String::from)));
+        assert_eq!(x(r"\alpha "), x(r"\alpha"));
+        assert_eq!(
+            x(r"\twoheadrightarrow"),
+            ["↠", "`->>`", "<twoheadrightarrow>"]
+        );
+        assert_eq!(x(r"\infty")[..2], ["∞", "`oo`"]);
+        assert!(x(r"\langle").contains(&"`[<`".to_string()));
+        assert!(x(r"\langle").contains(&"`<]]`".to_string()));
+        assert_eq!(x(r"\hat")[..2], ["◌̂", "<◌^>"]);
+        assert!(x(r"\hat").contains(&"<◌ hat>".to_string()));
+        assert!(x(r"\hat").contains(&"<'hat>◌".to_string()));
+        assert_eq!(x(r"\sqrt"), ["√◌", "<◌ sqrt>", "<'sqrt>◌"]);
+        assert!(x(r"\mathbf").contains(&"<◌ bf>".to_string()));
+        assert_eq!(x(r"\notacommand"), ["<notacommand>"]);
+        assert!(x(r"\").is_empty());
+    }
+
+    #[test]
+    fn test_unicode_of_tex() {
+        let x = |tex: &str| unicode_of_tex(tex);
+        assert_eq!(x(r"\alpha").as_deref(), Some("α"));
+        assert_eq!(x(r"\langle").as_deref(), Some("⟨"));
+        assert_eq!(x(r"\mathbf{v}").as_deref(), Some("𝐯"));
+        assert_eq!(x(r"\hat").as_deref(), Some("\u{302}"));
+        assert_eq!(x(r"\sqrt").as_deref(), Some("√"));
+        assert_eq!(x("+").as_deref(), Some("+"));
+        assert_eq!(x(r"\lt").as_deref(), Some("<"));
+        assert_eq!(x(r"\gt").as_deref(), Some(">"));
+        assert_eq!(x(r"\lim"), None);
+    }
+}
@@ -35,7 +35,7 @@ pub fn take_symbol(s: &str) -> IResult<&str, Token> {
     )(s)
 }
 
-////
+// ----
 
 fn take_symbol_from_single_char(s: &str) -> IResult<&str, String> {
     flat_map(map_res(anychar, tex_of_char), |tex| {
@@ -56,6 +56,19 @@ fn take_symbol_from_ascii_art(s: &str) -> IResult<&str, String> {
 }
 
 fn take_symbol_in_angle_brackets(s: &str) -> IResult<&str, String> {
+    flat_map(take_symbol_head_in_angle_brackets, |tex| {
+        terminated(
+            fold_many0(
+                take_accent_in_angle_brackets,
+                move || String::from(&tex),
+                |tex, accent| format!("{}{{{}}}", accent, tex.trim_end_matches(' ')),
+            ),
+            pair(many0(tag(" ")), tag(">")),
+        )
+    })(s)
+}
+
+pub(super) fn take_symbol_head_in_angle_brackets(s: &str) -> IResult<&str, String> {
     fn take_symbol_from_alpha2(s: &str) -> IResult<&str, String> {
         map_res(alpha1, |x: &str| {
             (x.len() > 1)
@@ -90,35 +103,27 @@ fn take_symbol_in_angle_brackets(s: &str) -> IResult<&str, String> {
             },
         )(s)
     }
-    flat_map(
-        preceded(
-            pair(tag("<"), many0(tag(" "))),
-            alt((
-                take_symbol_from_alpha2,
-                take_symbol_from_ascii_art,
-                take_number_in_brackets,
-                take_symbol_from_single_char_in_brackets,
-            )),
-        ),
-        |tex| {
-            terminated(
-                fold_many0(
-                    map(
-                        alt((
-                            preceded(
-                                many0(tag(" ")),
-                                alt((tag("!"), tag("^"), tag("~"), tag("-"))),
-                            ),
-                            preceded(many1(tag(" ")), alphanumeric1),
-                        )),
-                        tex_of_maybe_abbreviated_accent_name,
-                    ),
-                    move || String::from(&tex),
-                    |tex, accent| format!("{}{{{}}}", accent, tex.trim_end_matches(' ')),
-                ),
-                pair(many0(tag(" ")), tag(">")),
-            )
-        },
+    preceded(
+        pair(tag("<"), many0(tag(" "))),
+        alt((
+            take_symbol_from_alpha2,
+            take_symbol_from_ascii_art,
+            take_number_in_brackets,
+            take_symbol_from_single_char_in_brackets,
+        )),
+    )(s)
+}
+
+pub(super) fn take_accent_in_angle_brackets(s: &str) -> IResult<&str, String> {
+    map(
+        alt((
+            preceded(
+                many0(tag(" ")),
+                alt((tag("!"), tag("^"), tag("~"), tag("-"))),
+            ),
+            preceded(many1(tag(" ")), alphanumeric1),
+        )),
+        tex_of_maybe_abbreviated_accent_name,
     )(s)
 }
 
@@ -153,9 +158,9 @@ fn take_string_literal_in_angle_brackets(s: &str) -> IResult<&str, String> {
     )(s)
 }
 
-////
+// ----
 
-fn tex_of_char(c: char) -> Result<String, ()> {
+pub(super) fn tex_of_char(c: char) -> Result<String, ()> {
     fn nfkc(c: char) -> Result<char, ()> {
         once(c).nfkc().next().ok_or(())
     }
@@ -563,7 +568,7 @@ fn tex_of_char(c: char) -> Result<String, ()> {
     })
 }
 
-fn tex_of_unicode_accent(c: char) -> Result<String, ()> {
+pub(super) fn tex_of_unicode_accent(c: char) -> Result<String, ()> {
     Ok(match c {
         '\u{0300}' => r"\grave",
         '\u{0301}' => r"\acute",
@@ -591,165 +596,283 @@ fn tex_of_unicode_accent(c: char) -> Result<String, ()> {
     .to_string())
 }
 
+pub(super) const ASCII_ART_SYMBOLS: &[(&str, &str)] = &[
+    // binop
+    ("+-", r"\pm "),
+    ("-+", r"\mp "),
+    ("-:-", r"\div "),
+    ("@", r"\cdot "),
+    (".", r"\cdot "),
+    ("-", r"\bullet "),
+    ("o", r"\circ "),
+    ("O", r"\circ "),
+    ("x", r"\times "),
+    ("X", r"\times "),
+    ("(x)", r"\otimes "),
+    ("(X)", r"\otimes "),
+    ("(+)", r"\oplus "),
+    ("(.)", r"\odot "),
+    ("^", r"\wedge "),
+    ("V", r"\vee "),
+    ("v", r"\vee "),
+    ("n", r"\cap "),
+    ("U", r"\cup "),
+    ("u", r"\cup "),
+    // rel
+    ("!=", r"\ne "),
+    ("-:", r"\eqcolon "),
+    ("-::", r"\Eqcolon "),
+    ("=:", r"\eqqcolon "),
+    ("=::", r"\Eqqcolon "),
+    (":-", r"\coloneq "),
+    ("::-", r"\Coloneq "),
+    (":=", r"\coloneqq "),
+    ("::=", r"\Coloneqq "),
+    ("-=", r"\equiv "),
+    ("=-", r"\equiv "),
+    ("-~", r"\eqsim "),
+    ("~-", r"\simeq "),
+    ("~=", r"\cong "),
+    ("~~", r"\approx "),
+    ("~~-", r"\approxeq "),
+    (":~", r"\colonsim "),
+    ("::~", r"\Colonsim "),
+    ("oc", r"\propto "),
+    ("<", r"\lt "),
+    ("<=", r"\le "),
+    (">", r"\gt "),
+    (">=", r"\ge "),
+    ("<<", r"\ll "),
+    ("<<<", r"\lll "),
+    (">>", r"\gg "),
+    (">>>", r"\ggg "),
+    ("|-", r"\vdash "),
+    ("||-", r"\Vdash "),
+    ("|=", r"\vDash "),
+    ("-|", r"\dashv "),
+    // arrow
+    ("-->", r"\rightarrow "),
+    ("<--", r"\leftarrow "),
+    ("==>", r"\Rightarrow "),
+    ("<==", r"\Leftarrow "),
+    ("<<-", r"\twoheadleftarrow "),
+    ("->>", r"\twoheadrightarrow "),
+    ("<-<", r"\leftarrowtail "),
+    (">->", r"\rightarrowtail "),
+    ("|->", r"\mapsto "),
+    ("<=>", r"\Leftrightarrow "),
+    ("<->", r"\leftrightarrow "),
+    ("~~>", r"\rightsquigarrow "),
+    ("<~>", r"\leftrightsquigarrow "),
+    // symbol
+    ("_|_", r"\bot "),
+    ("T", r"\top "),
+    ("h-", r"\hbar "),
+    ("t", r"\dagger "),
+    ("+", r"\dagger "),
+    ("A", r"\forall "),
+    ("E", r"\exists "),
+    ("oo", r"\infty "),
+    ("...", r"\ldots "),
+    ("---", r"\cdots "),
+    ("||", r"\|"),
+    ("[", "["),
+    ("]", "]"),
+    ("(", "("),
+    (")", ")"),
+    ("{", r"\{"),
+    ("}", r"\}"),
+];
+
 fn tex_of_ascii_art(s: &str) -> Result<String, ()> {
-    Ok(match s {
-        // binop
-        "+-" => r"\pm ",
-        "-+" => r"\mp ",
-        "-:-" => r"\div ",
-        "@" | "." => r"\cdot ",
-        "-" => r"\bullet ",
-        "o" | "O" => r"\circ ",
-        "x" | "X" => r"\times ",
-        "(x)" | "(X)" => r"\otimes ",
-        "(+)" => r"\oplus ",
-        "(.)" => r"\odot ",
-        "^" => r"\wedge ",
-        "V" | "v" => r"\vee ",
-        "n" => r"\cap ",
-        "U" | "u" => r"\cup ",
-        // rel
-        "!=" => r"\ne ",
-        "-:" => r"\eqcolon ",
-        "-::" => r"\Eqcolon ",
-        "=:" => r"\eqqcolon ",
-        "=::" => r"\Eqqcolon ",
-        ":-" => r"\coloneq ",
-        "::-" => r"\Coloneq ",
-        ":=" => r"\coloneqq ",
-        "::=" => r"\Coloneqq ",
-        "-=" | "=-" => r"\equiv ",
-        "-~" => r"\eqsim ",
-        "~-" => r"\simeq ",
-        "~=" => r"\cong ",
-        "~~" => r"\approx ",
-        "~~-" => r"\approxeq ",
-        ":~" => r"\colonsim ",
-        "::~" => r"\Colonsim ",
-        "oc" => r"\propto ",
-        "<" => r"\lt ",
-        "<=" => r"\le ",
-        ">" => r"\gt ",
-        ">=" => r"\ge ",
-        "<<" => r"\ll ",
-        "<<<" => r"\lll ",
-        ">>" => r"\gg ",
-        ">>>" => r"\ggg ",
-        "|-" => r"\vdash ",
-        "||-" => r"\Vdash ",
-        "|=" => r"\vDash ",
-        "-|" => r"\dashv ",
-        // arrow
-        "-->" => r"\rightarrow ",
-        "<--" => r"\leftarrow ",
-        "==>" => r"\Rightarrow ",
-        "<==" => r"\Leftarrow ",
-        "<<-" => r"\twoheadleftarrow ",
-        "->>" => r"\twoheadrightarrow ",
-        "<-<" => r"\leftarrowtail ",
-        ">->" => r"\rightarrowtail ",
-        "|->" => r"\mapsto ",
-        "<=>" => r"\Leftrightarrow ",
-        "<->" => r"\leftrightarrow ",
-        "~~>" => r"\rightsquigarrow ",
-        "<~>" => r"\leftrightsquigarrow ",
-        // symbol
-        "_|_" => r"\bot ",
-        "T" => r"\top ",
-        "h-" => r"\hbar ",
-        "t" | "+" => r"\dagger ",
-        "A" => r"\forall ",
-        "E" => r"\exists ",
-        "oo" => r"\infty ",
-        "..." => r"\ldots ",
-        "---" => r"\cdots ",
-        "||" => r"\|",
-        "[" => "[",
-        "]" => "]",
-        "(" => "(",
-        ")" => ")",
-        "{" => r"\{",
-        "}" => r"\}",
-        _ => return Err(()),
-    }
-    .to_string())
+    ASCII_ART_SYMBOLS
+        .iter()
+        .find(|(art, _)| *art == s)
+        .map(|(_, tex)| tex.to_string())
+        .ok_or(())
 }
 
+pub(super) const SYMBOL_NAME_ABBREVIATIONS: &[(&str, &str)] = &[
+    ("al", "alpha"),
+    ("alp", "alpha"),
+    ("be", "beta"),
+    ("bet", "beta"),
+    ("ga", "gamma"),
+    ("gam", "gamma"),
+    ("gamm", "gamma"),
+    ("de", "delta"),
+    ("del", "delta"),
+    ("delt", "delta"),
+    ("ve", "varepsilon"),
+    ("vep", "varepsilon"),
+    ("veps", "varepsilon"),
+    ("vepsi", "varepsilon"),
+    ("ze", "zeta"),
+    ("zet", "zeta"),
+    ("et", "eta"),
+    ("th", "theta"),
+    ("the", "theta"),
+    ("thet", "theta"),
+    ("io", "iota"),
+    ("iot", "iota"),
+    ("ka", "kappa"),
+    ("kap", "kappa"),
+    ("kapp", "kappa"),
+    ("la", "lambda"),
+    ("lam", "lambda"),
+    ("lamb", "lambda"),
+    ("lambd", "lambda"),
+    ("omi", "omicron"),
+    ("omic", "omicron"),
+    ("vs", "varsigma"),
+    ("vsi", "varsigma"),
+    ("vsig", "varsigma"),
+    ("vsigm", "varsigma"),
+    ("vsigma", "varsigma"),
+    ("si", "sigma"),
+    ("sig", "sigma"),
+    ("sigm", "sigma"),
+    ("rh", "rho"),
+    ("ta", "tau"),
+    ("up", "upsilon"),
+    ("ups", "upsilon"),
+    ("upsi", "upsilon"),
+    ("vph", "varphi"),
+    ("vphi", "varphi"),
+    ("ch", "chi"),
+    ("ps", "psi"),
+    ("om", "omega"),
+    ("ome", "omega"),
+    ("omeg", "omega"),
+    ("ep", "epsilon"),
+    ("eps", "epsilon"),
+    ("epsi", "epsilon"),
+    ("vt", "vartheta"),
+    ("vth", "vartheta"),
+    ("vthe", "vartheta"),
+    ("vthet", "vartheta"),
+    ("vtheta", "vartheta"),
+    ("vk", "varkappa"),
+    ("vka", "varkappa"),
+    ("vkap", "varkappa"),
+    ("vkapp", "varkappa"),
+    ("vkappa", "varkappa"),
+    ("ph", "phi"),
+    ("vr", "varrho"),
+    ("vrh", "varrho"),
+    ("vrho", "varrho"),
+    ("vpi", "varpi"),
+    ("di", "digamma"),
+    ("dig", "digamma"),
+    ("diga", "digamma"),
+    ("digam", "digamma"),
+    ("vT", "varTheta"),
+    ("vTh", "varTheta"),
+    ("vThe", "varTheta"),
+    ("vTheta", "varTheta"),
+    ("Ga", "Gamma"),
+    ("Gam", "Gamma"),
+    ("Gamm", "Gamma"),
+    ("De", "Delta"),
+    ("Del", "Delta"),
+    ("Delt", "Delta"),
+    ("Th", "Theta"),
+    ("The", "Theta"),
+    ("Thet", "Theta"),
+    ("La", "Lambda"),
+    ("Lam", "Lambda"),
+    ("Lamb", "Lambda"),
+    ("Lambd", "Lambda"),
+    ("Si", "Sigma"),
+    ("Sig", "Sigma"),
+    ("Sigm", "Sigma"),
+    ("Up", "Upsilon"),
+    ("Ups", "Upsilon"),
+    ("Upsi", "Upsilon"),
+    ("Ph", "Phi"),
+    ("Ps", "Psi"),
+    ("Om", "Omega"),
+    ("Ome", "Omega"),
+    ("Omeg", "Omega"),
+];
+
 fn tex_of_maybe_abbreviated_symbol_name(s: &str) -> String {
-    let sym = |s| format!("\\{} ", s);
-    match s {
-        "al" | "alp" => sym("alpha"),
-        "be" | "bet" => sym("beta"),
-        "ga" | "gam" | "gamm" => sym("gamma"),
-        "de" | "del" | "delt" => sym("delta"),
-        "ve" | "vep" | "veps" | "vepsi" => sym("varepsilon"),
-        "ze" | "zet" => sym("zeta"),
-        "et" => sym("eta"),
-        "th" | "the" | "thet" => sym("theta"),
-        "io" | "iot" => sym("iota"),
-        "ka" | "kap" | "kapp" => sym("kappa"),
-        "la" | "lam" | "lamb" | "lambd" => sym("lambda"),
-        "omi" | "omic" => sym("omicron"),
-        "vs" | "vsi" | "vsig" | "vsigm" | "vsigma" => sym("varsigma"),
-        "si" | "sig" | "sigm" => sym("sigma"),
-        "rh" => sym("rho"),
-        "ta" => sym("tau"),
-        "up" | "ups" | "upsi" => sym("upsilon"),
-        "vph" | "vphi" => sym("varphi"),
-        "ch" => sym("chi"),
-        "ps" => sym("psi"),
-        "om" | "ome" | "omeg" => sym("omega"),
-        "ep" | "eps" | "epsi" => sym("epsilon"),
-        "vt" | "vth" | "vthe" | "vthet" | "vtheta" => sym("vartheta"),
-        "vk" | "vka" | "vkap" | "vkapp" | "vkappa" => sym("varkappa"),
-        "ph" => sym("phi"),
-        "vr" | "vrh" | "vrho" => sym("varrho"),
-        "vpi" => sym("varpi"),
-        "di" | "dig" | "diga" | "digam" => sym("digamma"),
-        "vT" | "vTh" | "vThe" | "vTheta" => sym("varTheta"),
-        "Ga" | "Gam" | "Gamm" => sym("Gamma"),
-        "De" | "Del" | "Delt" => sym("Delta"),
-        "Th" | "The" | "Thet" => sym("Theta"),
-        "La" | "Lam" | "Lamb" | "Lambd" => sym("Lambda"),
-        "Si" | "Sig" | "Sigm" => sym("Sigma"),
-        "Up" | "Ups" | "Upsi" => sym("Upsilon"),
-        "Ph" => sym("Phi"),
-        "Ps" => sym("Psi"),
-        "Om" | "Ome" | "Omeg" => sym("Omega"),
-        _ => format!("\\{} ", s),
-    }
+    let name = SYMBOL_NAME_ABBREVIATIONS
+        .iter()
+        .find(|(abbr, _)| *abbr == s)
+        .map_or(s, |(_, name)| name);
+    format!("\\{} ", name)
 }
 
+pub(super) const ACCENT_NAME_ABBREVIATIONS: &[(&str, &str)] = &[
+    ("!", r"\not"),
+    ("^", r"\hat"),
+    ("~", r"\tilde"),
+    ("-", r"\bar"),
+    ("d", r"\dot"),
+    ("dd", r"\ddot"),
+    ("ddd", r"\dddot"),
+    ("v", r"\vec"),
+    ("ve", r"\vec"),
+    ("va", r"\vec"),
+    ("vb", r"\mathbf"),
+    ("bb", r"\mathbb"),
+    ("b", r"\mathbf"),
+    ("bf", r"\mathbf"),
+    ("c", r"\mathcal"),
+    ("cc", r"\mathcal"),
+    ("ca", r"\mathcal"),
+    ("cal", r"\mathcal"),
+    ("f", r"\mathfrak"),
+    ("fr", r"\mathfrak"),
+    ("fra", r"\mathfrak"),
+    ("frak", r"\mathfrak"),
+    ("frk", r"\mathfrak"),
+    ("i", r"\mathit"),
+    ("it", r"\mathit"),
+    ("r", r"\mathrm"),
+    ("rm", r"\mathrm"),
+    ("sc", r"\mathscr"),
+    ("scr", r"\mathscr"),
+    ("sf", r"\mathsf"),
+    ("tt", r"\mathtt"),
+    ("bffr", r"\mathbffrak"),
+    ("frbf", r"\mathbffrak"),
+    ("bffrak", r"\mathbffrak"),
+    ("frakbf", r"\mathbffrak"),
+    ("mathfrakbf", r"\mathbffrak"),
+    ("bfit", r"\mathbfit"),
+    ("itbf", r"\mathbfit"),
+    ("mathitbf", r"\mathbfit"),
+    ("bfsc", r"\mathbfscr"),
+    ("scbf", r"\mathbfscr"),
+    ("bfscr", r"\mathbfscr"),
+    ("scrbf", r"\mathbfscr"),
+    ("mathscrbf", r"\mathbfscr"),
+    ("bfsf", r"\mathbfsf"),
+    ("sfbf", r"\mathbfsf"),
+    ("mathsfbf", r"\mathbfsf"),
+    ("sfit", r"\mathsfit"),
+    ("itsf", r"\mathsfit"),
+    ("mathitsf", r"\mathsfit"),
+    ("bfsfit", r"\mathbfsfit"),
+    ("bfitsf", r"\mathbfsfit"),
+    ("sfbfit", r"\mathbfsfit"),
+    ("sfitbf", r"\mathbfsfit"),
+    ("itsfbf", r"\mathbfsfit"),
+    ("itbfsf", r"\mathbfsfit"),
+    ("mathbfitsf", r"\mathbfsfit"),
+    ("mathsfbfit", r"\mathbfsfit"),
+    ("mathsfitbf", r"\mathbfsfit"),
+    ("mathitsfbf", r"\mathbfsfit"),
+    ("mathitbfsf", r"\mathbfsfit"),
+];
+
 fn tex_of_maybe_abbreviated_accent_name(s: &str) -> String {
-    match s {
-        "!" => r"\not",
-        "^" => r"\hat",
-        "~" => r"\tilde",
-        "-" => r"\bar",
-        "d" => r"\dot",
-        "dd" => r"\ddot",
-        "ddd" => r"\dddot",
-        "v" | "ve" | "va" => r"\vec",
-        "vb" => r"\mathbf",
-        "bb" => r"\mathbb",
-        "b" | "bf" => r"\mathbf",
-        "c" | "cc" | "ca" | "cal" => r"\mathcal",
-        "f" | "fr" | "fra" | "frak" | "frk" => r"\mathfrak",
-        "i" | "it" => r"\mathit",
-        "r" | "rm" => r"\mathrm",
-        "sc" | "scr" => r"\mathscr",
-        "sf" => r"\mathsf",
-        "tt" => r"\mathtt",
-        "bffr" | "frbf" | "bffrak" | "frakbf" | "mathfrakbf" => r"\mathbffrak",
-        "bfit" | "itbf" | "mathitbf" => r"\mathbfit",
-        "bfsc" | "scbf" | "bfscr" | "scrbf" | "mathscrbf" => r"\mathbfscr",
-        "bfsf" | "sfbf" | "mathsfbf" => r"\mathbfsf",
-        "sfit" | "itsf" | "mathitsf" => r"\mathsfit",
-        "bfsfit" | "bfitsf" | "sfbfit" | "sfitbf" | "itsfbf" | "itbfsf" | "mathbfitsf"
-        | "mathsfbfit" | "mathsfitbf" | "mathitsfbf" | "mathitbfsf" => r"\mathbfsfit",
-        _ => return format!("\\{}", s),
-    }
-    .to_string()
+    ACCENT_NAME_ABBREVIATIONS
+        .iter()
+        .find(|(abbr, _)| *abbr == s)
+        .map_or_else(|| format!("\\{}", s), |(_, tex)| tex.to_string())
 }
 
 fn take_string_literal_content(s: &str) -> IResult<&str, String> {
@@ -773,7 +896,6 @@ fn take_raw_string_literal_content(s: &str) -> IResult<&str, String> {
 
 fn escape_tex_string_math(s: &str) -> String {
     s.chars()
-        .into_iter()
         .map(|c| match c {
             '#' | '$' | '%' | '_' | '{' | '}' => format!("\\{}", c),
             '~' => r"{\textasciitilde}".to_string(),
@@ -786,7 +908,6 @@ fn escape_tex_string_math(s: &str) -> String {
 
 fn escape_tex_string_text(s: &str) -> String {
     s.chars()
-        .into_iter()
         .map(|c| match c {
             '$' | '{' | '}' | '\\' => format!("\\{}", c),
             _ => c.to_string(),
@@ -827,8 +948,8 @@ fn resolve_string_literal_accent(content: &str, accents: Vec<&str>) -> Result<St
     accents.sort();
     accents.dedup();
     let content = match accents[..] {
-        ["text"] => escape_tex_string_text(&content),
-        _ => escape_tex_string_math(&content),
+        ["text"] => escape_tex_string_text(content),
+        _ => escape_tex_string_math(content),
     };
     let prefix = match accents[..] {
         ["bb"] => r"\mathbb",
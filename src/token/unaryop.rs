@@ -25,12 +25,17 @@ pub fn take_op(s: &str) -> IResult<&str, Token> {
 }
 
 fn take_op_unicode(s: &str) -> IResult<&str, String> {
-    map_res(anychar, |c| match c {
-        '√' => Ok(r"\sqrt".to_string()),
-        '∛' => Ok(r"\sqrt[3]".to_string()),
-        '∜' => Ok(r"\sqrt[4]".to_string()),
-        _ => Err(()),
-    })(s)
+    map_res(anychar, tex_of_unicode_op)(s)
+}
+
+pub(super) fn tex_of_unicode_op(c: char) -> Result<String, ()> {
+    Ok(match c {
+        '√' => r"\sqrt",
+        '∛' => r"\sqrt[3]",
+        '∜' => r"\sqrt[4]",
+        _ => return Err(()),
+    }
+    .to_string())
 }
 
 fn take_op_in_angle_bracket(s: &str) -> IResult<&str, String> {
@@ -61,9 +66,7 @@ fn take_root_in_angle_bracket(s: &str) -> IResult<&str, String> {
 }
 
 fn tex_of_maybe_abbreviated_op_name(s: &str) -> String {
-    match s {
-        _ => format!("\\{}", s),
-    }
+    format!("\\{}", s)
 }
 
 #[cfg(test)]
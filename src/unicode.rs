@@ -0,0 +1,340 @@
+//! Linear Unicode text of formulas, such as `x²ⁿ`, `√(a+b)` and `(a+b)/c`.
+//!
+//! Scripts use sub/superscript characters where every character has one, and `_(..)` or
+//! `^(..)` otherwise. Parentheses are added only where the grouping would be ambiguous.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use unicode_normalization::char::is_combining_mark;
+
+use crate::expr::{Frac, Inter, Math, Root, Simple, Stack};
+use crate::printer::{
+    spell_close, spell_open, spellings_of_symbol, split_command, string_literal,
+    unescape_tex_string, unicode_subscripts, unicode_superscripts, Notation,
+};
+use crate::token::reverse::unicode_of_tex;
+
+/// How tightly a text binds, from a single symbol to a fraction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Level {
+    Symbol,
+    /// Brackets or an operator applied as a function such as `overline(x)`.
+    Atom,
+    Script,
+    /// A prefix operator such as `√`.
+    Prefix,
+    Concat,
+    Frac,
+}
+
+struct Text {
+    text: String,
+    level: Level,
+}
+
+impl Text {
+    fn new(text: String, level: Level) -> Self {
+        Self { text, level }
+    }
+
+    /// The text in parentheses if it binds looser than `level`.
+    fn within(self, level: Level) -> String {
+        match self.level <= level {
+            true => self.text,
+            false => format!("({})", self.text),
+        }
+    }
+}
+
+/// Renders `math` as linear Unicode text.
+pub fn unicode_text(math: &Math) -> String {
+    self::math(math).text
+}
+
+fn math(x: &Math) -> Text {
+    match &x.0[..] {
+        [] => Text::new(String::new(), Level::Symbol),
+        [x] => root(x),
+        roots => {
+            let mut text = String::new();
+            let mut last = String::new();
+            for x in roots {
+                let y = root(x).within(Level::Concat);
+                if is_word_boundary(&last, &y) {
+                    text.push(' ');
+                }
+                text += &y;
+                last = y;
+            }
+            Text::new(text, Level::Concat)
+        }
+    }
+}
+
+/// Whether `a` followed by `b` needs a space to keep a name apart, as in `sin x` or `lim_(n→∞) aₙ`.
+pub(crate) fn is_word_boundary(a: &str, b: &str) -> bool {
+    let word = |x: &mut dyn Iterator<Item = char>| x.take_while(|c| c.is_alphabetic()).count();
+    let (name, trailing) = (word(&mut a.chars()), word(&mut a.chars().rev()));
+    let leading = word(&mut b.chars());
+    leading > 0 && (name > 1 || trailing > 1 || (trailing > 0 && leading > 1))
+}
+
+fn root(x: &Root) -> Text {
+    match x {
+        Root::Root { root, body } => radical(Some(&frac(root).text), frac(body)),
+        Root::Math { body } => frac(body),
+    }
+}
+
+/// `√x`, `∛x` or `ⁿ√x`, with `root(n, x)` for an index without superscript characters.
+fn radical(index: Option<&str>, body: Text) -> Text {
+    let sign = match index {
+        None | Some("2") => "√".to_string(),
+        Some("3") => "∛".to_string(),
+        Some("4") => "∜".to_string(),
+        Some(index) => match unicode_script(index, superscript_chars()) {
+            Some(index) => format!("{}√", index),
+            None => return Text::new(format!("root({}, {})", index, body.text), Level::Atom),
+        },
+    };
+    Text::new(
+        format!("{}{}", sign, body.within(Level::Prefix)),
+        Level::Prefix,
+    )
+}
+
+fn frac(x: &Frac) -> Text {
+    match x {
+        Frac::Frac { nume, denom } => {
+            let (nume, denom) = (stack(nume), stack(denom));
+            let text = match (nume.level, denom.level) {
+                (Level::Symbol, Level::Symbol) => format!("{}⁄{}", nume.text, denom.text),
+                _ => format!(
+                    "{}/{}",
+                    nume.within(Level::Prefix),
+                    denom.within(Level::Prefix)
+                ),
+            };
+            Text::new(text, Level::Frac)
+        }
+        Frac::Math { body } => stack(body),
+    }
+}
+
+/// Limits are written like scripts, e.g. `lim_(n→∞)`.
+fn stack(x: &Stack) -> Text {
+    let mut y = inter(x.body());
+    if let Some(under) = x.under() {
+        y = attach(y, inter(under), subscript_chars(), "_");
+    }
+    if let Some(over) = x.over() {
+        y = attach(y, inter(over), superscript_chars(), "^");
+    }
+    y
+}
+
+fn inter(x: &Inter) -> Text {
+    let mut y = simple(x.body());
+    if let Some(sub) = x.sub() {
+        y = attach(y, simple(sub), subscript_chars(), "_");
+    }
+    if let Some(sup) = x.sup() {
+        y = attach(y, simple(sup), superscript_chars(), "^");
+    }
+    y
+}
+
+/// Writes `script` after `base` with unicode characters, or after `mark` where it has none.
+fn attach(base: Text, script: Text, chars: &HashMap<char, char>, mark: &str) -> Text {
+    let base = base.within(Level::Script);
+    let text = match unicode_script(&script.text, chars) {
+        Some(script) => format!("{}{}", base, script),
+        None => format!("{}{}{}", base, mark, script.within(Level::Atom)),
+    };
+    Text::new(text, Level::Script)
+}
+
+fn unicode_script(text: &str, chars: &HashMap<char, char>) -> Option<String> {
+    text.chars().map(|c| chars.get(&c).copied()).collect()
+}
+
+fn simple(x: &Simple) -> Text {
+    match x {
+        Simple::UnarySymbol { operators, symbol } => {
+            apply(operators, Text::new(self::symbol(symbol), Level::Symbol))
+        }
+        Simple::UnaryParened {
+            operators,
+            open,
+            body,
+            close,
+        } => {
+            let body = math(body);
+            let base = close.trim_end_matches('\'');
+            let primes = "′".repeat(close.len() - base.len());
+            let y = match (open.is_empty(), base.is_empty()) {
+                // invisible brackets group without showing
+                (true, true) if primes.is_empty() => body,
+                (true, true) => Text::new(body.within(Level::Atom) + &primes, Level::Atom),
+                _ => Text::new(
+                    format!(
+                        "{}{}{}{}",
+                        bracket(open, true),
+                        body.text,
+                        bracket(base, false),
+                        primes
+                    ),
+                    Level::Atom,
+                ),
+            };
+            apply(operators, y)
+        }
+        Simple::UnaryExpr { operators, body } => apply(operators, math(body)),
+    }
+}
+
+/// Applies `operators` to `operand`, the last one first.
+fn apply(operators: &[String], operand: Text) -> Text {
+    operators
+        .iter()
+        .rev()
+        .fold(operand, |x, operator| self::operator(operator, x))
+}
+
+/// `√x`, `x̅` for an operator with a combining character, or `name(x)` otherwise.
+fn operator(tex: &str, operand: Text) -> Text {
+    if tex == r"\sqrt" {
+        return radical(None, operand);
+    }
+    if let Some(index) = tex
+        .strip_prefix(r"\sqrt[")
+        .and_then(|x| x.strip_suffix(']'))
+    {
+        return radical(Some(index), operand);
+    }
+    if let Some(prefix) = operator_prefix(tex) {
+        return Text::new(
+            format!("{}{}", prefix, operand.within(Level::Prefix)),
+            Level::Prefix,
+        );
+    }
+    let is_char = operand
+        .text
+        .chars()
+        .filter(|c| !is_combining_mark(*c))
+        .count()
+        == 1;
+    match combining(tex) {
+        Some(mark) if is_char => Text::new(operand.text + &mark, operand.level),
+        _ => Text::new(
+            format!("{}({})", tex.trim_start_matches('\\'), operand.text),
+            Level::Atom,
+        ),
+    }
+}
+
+/// The unicode character written before the operand of an operator, such as `√`.
+pub(crate) fn operator_prefix(tex: &str) -> Option<String> {
+    unicode_of_tex(tex).filter(|x| !x.is_ascii() && !x.chars().all(is_combining_mark))
+}
+
+/// The combining character of an accent command such as `\hat`.
+pub(crate) fn combining(command: &str) -> Option<String> {
+    unicode_of_tex(command).filter(|x| x.chars().all(is_combining_mark))
+}
+
+/// The text of a symbol: its unicode spelling, or its name such as `lim` without one.
+pub(crate) fn symbol(tex: &str) -> String {
+    let base = tex.trim_end_matches('\'');
+    let primes = "′".repeat(tex.len() - base.len());
+    format!("{}{}", symbol_base(base.trim_end_matches(' ')), primes)
+}
+
+fn symbol_base(tex: &str) -> String {
+    if !tex.contains(['\\', '{', '}']) {
+        return tex.to_string();
+    }
+    let spelling = spellings_of_symbol(tex)
+        .into_iter()
+        .find(|x| !x.is_ascii() && !x.starts_with(['<', '"', '`', '#']));
+    if let Some(spelling) = spelling.or_else(|| unicode_of_tex(tex)) {
+        return spelling;
+    }
+    if let Some((command, argument)) = split_command(tex) {
+        if let Some(mark) = combining(command) {
+            return symbol_base(argument) + &mark;
+        }
+        if string_literal(command, argument).is_some() {
+            return unescape_tex_string(argument);
+        }
+    }
+    tex.trim_start_matches('\\').to_string()
+}
+
+pub(crate) fn bracket(tex: &str, open: bool) -> String {
+    if tex.is_empty() {
+        return String::new();
+    }
+    let spelling = match open {
+        true => spell_open(tex, Notation::Unicode),
+        false => spell_close(tex, Notation::Unicode),
+    };
+    match spelling {
+        Ok(x) if !x.starts_with(['<', '`']) => x,
+        _ => tex.trim_start_matches('\\').to_string(),
+    }
+}
+
+fn subscript_chars() -> &'static HashMap<char, char> {
+    static TABLE: OnceLock<HashMap<char, char>> = OnceLock::new();
+    TABLE.get_or_init(|| script_chars(unicode_subscripts()))
+}
+
+fn superscript_chars() -> &'static HashMap<char, char> {
+    static TABLE: OnceLock<HashMap<char, char>> = OnceLock::new();
+    TABLE.get_or_init(|| script_chars(unicode_superscripts()))
+}
+
+/// Maps the text of every symbol with a script character to that character.
+fn script_chars(table: &HashMap<String, char>) -> HashMap<char, char> {
+    let mut entries = table.iter().collect::<Vec<_>>();
+    entries.sort();
+    let mut chars = HashMap::new();
+    for (tex, c) in entries {
+        let text = symbol(tex);
+        let mut text = text.chars();
+        if let (Some(x), None) = (text.next(), text.next()) {
+            chars.entry(x).or_insert(*c);
+        }
+    }
+    chars
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::maspace_to_unicode;
+
+    #[test]
+    fn test_unicode_text() {
+        let x = |input: &str| maspace_to_unicode(input).unwrap();
+        assert_eq!(x("x^[2n]"), "x²ⁿ");
+        assert_eq!(x("x_i^2"), "xᵢ²");
+        assert_eq!(x("√[a+b]"), "√(a+b)");
+        assert_eq!(x("a/b"), "a⁄b");
+        assert_eq!(x("a+b /c"), "(a+b)/c");
+        assert_eq!(x("<v bf hat>"), "𝐯̂");
+        assert_eq!(x("x^[a+b]"), "xᵃ⁺ᵇ");
+        assert_eq!(x("x^[α+ψ]"), "x^(α+ψ)");
+        assert_eq!(x("e^ψ"), "e^ψ");
+        assert_eq!(x("(a+b)^2"), "(a+b)²");
+        assert_eq!(x("3 _/x"), "∛x");
+        assert_eq!(x("<lim>__[n→∞] a_n"), "lim_(n→∞) aₙ");
+        assert_eq!(x("<sin> x"), "sin x");
+        assert_eq!(x("f'"), "f′");
+        assert_eq!(x("a b /c"), "a(b⁄c)");
+        assert_eq!(x("⟨a⟩"), "⟨a⟩");
+        assert_eq!(x("a`<`b"), "a<b");
+        assert_eq!(x("<lt> x <gt>"), "<x>");
+    }
+}
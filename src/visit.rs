@@ -0,0 +1,401 @@
+//! Traversals over the `expr` AST.
+//!
+//! Each trait method defaults to the free function of the same name, which walks
+//! into the children. Override a method to act on a node and call the free function
+//! to keep walking.
+
+use crate::expr::{Frac, Inter, Math, Root, Simple, Stack};
+
+/// Walks an AST by shared reference.
+pub trait Visitor {
+    fn visit_math(&mut self, math: &Math) {
+        visit_math(self, math)
+    }
+
+    fn visit_root(&mut self, root: &Root) {
+        visit_root(self, root)
+    }
+
+    fn visit_frac(&mut self, frac: &Frac) {
+        visit_frac(self, frac)
+    }
+
+    fn visit_stack(&mut self, stack: &Stack) {
+        visit_stack(self, stack)
+    }
+
+    fn visit_inter(&mut self, inter: &Inter) {
+        visit_inter(self, inter)
+    }
+
+    fn visit_simple(&mut self, simple: &Simple) {
+        visit_simple(self, simple)
+    }
+
+    /// Called with the TeX of each unary operator, e.g. `\hat`.
+    fn visit_operator(&mut self, _operator: &str) {}
+
+    /// Called with the TeX of each symbol, e.g. `\alpha `.
+    fn visit_symbol(&mut self, _symbol: &str) {}
+
+    /// Called with the TeX of each opening and closing bracket, e.g. `\langle`.
+    fn visit_delimiter(&mut self, _delimiter: &str) {}
+}
+
+pub fn visit_math<V: Visitor + ?Sized>(visitor: &mut V, math: &Math) {
+    for root in math.roots() {
+        visitor.visit_root(root);
+    }
+}
+
+pub fn visit_root<V: Visitor + ?Sized>(visitor: &mut V, root: &Root) {
+    match root {
+        Root::Root { root, body } => {
+            visitor.visit_frac(root);
+            visitor.visit_frac(body);
+        }
+        Root::Math { body } => visitor.visit_frac(body),
+    }
+}
+
+pub fn visit_frac<V: Visitor + ?Sized>(visitor: &mut V, frac: &Frac) {
+    match frac {
+        Frac::Frac { nume, denom } => {
+            visitor.visit_stack(nume);
+            visitor.visit_stack(denom);
+        }
+        Frac::Math { body } => visitor.visit_stack(body),
+    }
+}
+
+pub fn visit_stack<V: Visitor + ?Sized>(visitor: &mut V, stack: &Stack) {
+    visitor.visit_inter(stack.body());
+    if let Some(over) = stack.over() {
+        visitor.visit_inter(over);
+    }
+    if let Some(under) = stack.under() {
+        visitor.visit_inter(under);
+    }
+}
+
+pub fn visit_inter<V: Visitor + ?Sized>(visitor: &mut V, inter: &Inter) {
+    visitor.visit_simple(inter.body());
+    if let Some(sup) = inter.sup() {
+        visitor.visit_simple(sup);
+    }
+    if let Some(sub) = inter.sub() {
+        visitor.visit_simple(sub);
+    }
+}
+
+pub fn visit_simple<V: Visitor + ?Sized>(visitor: &mut V, simple: &Simple) {
+    match simple {
+        Simple::UnaryExpr { operators, body } => {
+            operators.iter().for_each(|x| visitor.visit_operator(x));
+            visitor.visit_math(body);
+        }
+        Simple::UnarySymbol { operators, symbol } => {
+            operators.iter().for_each(|x| visitor.visit_operator(x));
+            visitor.visit_symbol(symbol);
+        }
+        Simple::UnaryParened {
+            operators,
+            open,
+            body,
+            close,
+        } => {
+            operators.iter().for_each(|x| visitor.visit_operator(x));
+            visitor.visit_delimiter(open);
+            visitor.visit_math(body);
+            visitor.visit_delimiter(close);
+        }
+    }
+}
+
+/// Walks an AST by mutable reference to rewrite it in place.
+pub trait VisitorMut {
+    fn visit_math_mut(&mut self, math: &mut Math) {
+        visit_math_mut(self, math)
+    }
+
+    fn visit_root_mut(&mut self, root: &mut Root) {
+        visit_root_mut(self, root)
+    }
+
+    fn visit_frac_mut(&mut self, frac: &mut Frac) {
+        visit_frac_mut(self, frac)
+    }
+
+    fn visit_stack_mut(&mut self, stack: &mut Stack) {
+        visit_stack_mut(self, stack)
+    }
+
+    fn visit_inter_mut(&mut self, inter: &mut Inter) {
+        visit_inter_mut(self, inter)
+    }
+
+    fn visit_simple_mut(&mut self, simple: &mut Simple) {
+        visit_simple_mut(self, simple)
+    }
+
+    fn visit_operator_mut(&mut self, _operator: &mut String) {}
+
+    fn visit_symbol_mut(&mut self, _symbol: &mut String) {}
+
+    fn visit_delimiter_mut(&mut self, _delimiter: &mut String) {}
+}
+
+pub fn visit_math_mut<V: VisitorMut + ?Sized>(visitor: &mut V, math: &mut Math) {
+    for root in math.roots_mut() {
+        visitor.visit_root_mut(root);
+    }
+}
+
+pub fn visit_root_mut<V: VisitorMut + ?Sized>(visitor: &mut V, root: &mut Root) {
+    match root {
+        Root::Root { root, body } => {
+            visitor.visit_frac_mut(root);
+            visitor.visit_frac_mut(body);
+        }
+        Root::Math { body } => visitor.visit_frac_mut(body),
+    }
+}
+
+pub fn visit_frac_mut<V: VisitorMut + ?Sized>(visitor: &mut V, frac: &mut Frac) {
+    match frac {
+        Frac::Frac { nume, denom } => {
+            visitor.visit_stack_mut(nume);
+            visitor.visit_stack_mut(denom);
+        }
+        Frac::Math { body } => visitor.visit_stack_mut(body),
+    }
+}
+
+pub fn visit_stack_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stack: &mut Stack) {
+    visitor.visit_inter_mut(stack.body_mut());
+    if let Some(over) = stack.over_mut() {
+        visitor.visit_inter_mut(over);
+    }
+    if let Some(under) = stack.under_mut() {
+        visitor.visit_inter_mut(under);
+    }
+}
+
+pub fn visit_inter_mut<V: VisitorMut + ?Sized>(visitor: &mut V, inter: &mut Inter) {
+    visitor.visit_simple_mut(inter.body_mut());
+    if let Some(sup) = inter.sup_mut() {
+        visitor.visit_simple_mut(sup);
+    }
+    if let Some(sub) = inter.sub_mut() {
+        visitor.visit_simple_mut(sub);
+    }
+}
+
+pub fn visit_simple_mut<V: VisitorMut + ?Sized>(visitor: &mut V, simple: &mut Simple) {
+    match simple {
+        Simple::UnaryExpr { operators, body } => {
+            operators
+                .iter_mut()
+                .for_each(|x| visitor.visit_operator_mut(x));
+            visitor.visit_math_mut(body);
+        }
+        Simple::UnarySymbol { operators, symbol } => {
+            operators
+                .iter_mut()
+                .for_each(|x| visitor.visit_operator_mut(x));
+            visitor.visit_symbol_mut(symbol);
+        }
+        Simple::UnaryParened {
+            operators,
+            open,
+            body,
+            close,
+        } => {
+            operators
+                .iter_mut()
+                .for_each(|x| visitor.visit_operator_mut(x));
+            visitor.visit_delimiter_mut(open);
+            visitor.visit_math_mut(body);
+            visitor.visit_delimiter_mut(close);
+        }
+    }
+}
+
+/// Rebuilds an AST by value, so that a node may be replaced by one of another shape.
+pub trait Fold {
+    fn fold_math(&mut self, math: Math) -> Math {
+        fold_math(self, math)
+    }
+
+    fn fold_root(&mut self, root: Root) -> Root {
+        fold_root(self, root)
+    }
+
+    fn fold_frac(&mut self, frac: Frac) -> Frac {
+        fold_frac(self, frac)
+    }
+
+    fn fold_stack(&mut self, stack: Stack) -> Stack {
+        fold_stack(self, stack)
+    }
+
+    fn fold_inter(&mut self, inter: Inter) -> Inter {
+        fold_inter(self, inter)
+    }
+
+    fn fold_simple(&mut self, simple: Simple) -> Simple {
+        fold_simple(self, simple)
+    }
+
+    fn fold_operator(&mut self, operator: String) -> String {
+        operator
+    }
+
+    fn fold_symbol(&mut self, symbol: String) -> String {
+        symbol
+    }
+
+    fn fold_delimiter(&mut self, delimiter: String) -> String {
+        delimiter
+    }
+}
+
+pub fn fold_math<F: Fold + ?Sized>(folder: &mut F, math: Math) -> Math {
+    Math::new(
+        math.into_roots()
+            .into_iter()
+            .map(|x| folder.fold_root(x))
+            .collect(),
+    )
+}
+
+pub fn fold_root<F: Fold + ?Sized>(folder: &mut F, root: Root) -> Root {
+    match root {
+        Root::Root { root, body } => Root::Root {
+            root: folder.fold_frac(root),
+            body: folder.fold_frac(body),
+        },
+        Root::Math { body } => Root::Math {
+            body: folder.fold_frac(body),
+        },
+    }
+}
+
+pub fn fold_frac<F: Fold + ?Sized>(folder: &mut F, frac: Frac) -> Frac {
+    match frac {
+        Frac::Frac { nume, denom } => Frac::Frac {
+            nume: folder.fold_stack(nume),
+            denom: folder.fold_stack(denom),
+        },
+        Frac::Math { body } => Frac::Math {
+            body: folder.fold_stack(body),
+        },
+    }
+}
+
+pub fn fold_stack<F: Fold + ?Sized>(folder: &mut F, stack: Stack) -> Stack {
+    let (body, over, under) = stack.into_parts();
+    Stack::new(
+        folder.fold_inter(body),
+        over.map(|x| folder.fold_inter(x)),
+        under.map(|x| folder.fold_inter(x)),
+    )
+}
+
+pub fn fold_inter<F: Fold + ?Sized>(folder: &mut F, inter: Inter) -> Inter {
+    let (body, sup, sub) = inter.into_parts();
+    Inter::new(
+        folder.fold_simple(body),
+        sup.map(|x| folder.fold_simple(x)),
+        sub.map(|x| folder.fold_simple(x)),
+    )
+}
+
+pub fn fold_simple<F: Fold + ?Sized>(folder: &mut F, simple: Simple) -> Simple {
+    let mut fold_operators = |operators: Vec<String>| -> Vec<String> {
+        operators
+            .into_iter()
+            .map(|x| folder.fold_operator(x))
+            .collect()
+    };
+    match simple {
+        Simple::UnaryExpr { operators, body } => Simple::UnaryExpr {
+            operators: fold_operators(operators),
+            body: folder.fold_math(body),
+        },
+        Simple::UnarySymbol { operators, symbol } => Simple::UnarySymbol {
+            operators: fold_operators(operators),
+            symbol: folder.fold_symbol(symbol),
+        },
+        Simple::UnaryParened {
+            operators,
+            open,
+            body,
+            close,
+        } => Simple::UnaryParened {
+            operators: fold_operators(operators),
+            open: folder.fold_delimiter(open),
+            body: folder.fold_math(body),
+            close: folder.fold_delimiter(close),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::parse;
+    use crate::token::tokenize;
+
+    fn math(input: &str) -> Math {
+        parse(&tokenize(input).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_visitor() {
+        struct Symbols(Vec<String>);
+        impl Visitor for Symbols {
+            fn visit_symbol(&mut self, symbol: &str) {
+                self.0.push(symbol.to_string());
+            }
+        }
+        let mut symbols = Symbols(vec![]);
+        symbols.visit_math(&math("x_i^2 + <alpha hat> /y"));
+        assert_eq!(symbols.0, ["x", "2", "i", "+", r"\hat{\alpha}", "y"]);
+    }
+
+    #[test]
+    fn test_visitor_mut() {
+        struct Rename;
+        impl VisitorMut for Rename {
+            fn visit_symbol_mut(&mut self, symbol: &mut String) {
+                if symbol == "x" {
+                    *symbol = "y".to_string();
+                }
+            }
+        }
+        let mut x = math("x^2 + (x+1)");
+        Rename.visit_math_mut(&mut x);
+        assert_eq!(x.to_string(), r"y^{2}+\left(y+1\right)");
+    }
+
+    #[test]
+    fn test_fold() {
+        // replaces every fraction by its numerator over a stack
+        struct Unfrac;
+        impl Fold for Unfrac {
+            fn fold_frac(&mut self, frac: Frac) -> Frac {
+                match fold_frac(self, frac) {
+                    Frac::Frac { nume, denom } => Frac::from(Stack::new(
+                        Inter::from(Simple::from(Math::from(nume))),
+                        None,
+                        Some(Inter::from(Simple::from(Math::from(denom)))),
+                    )),
+                    frac => frac,
+                }
+            }
+        }
+        let x = Unfrac.fold_math(math("a/b + c"));
+        assert_eq!(x.to_string(), r"\underset{b}{a}+c");
+    }
+}
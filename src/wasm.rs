@@ -0,0 +1,311 @@
+use wasm_bindgen::prelude::*;
+
+use crate::diagnostic::Severity;
+use crate::token::Token;
+use crate::*;
+
+#[wasm_bindgen]
+pub fn maspace_to_tex_wasm(input: &str) -> Result<String, String> {
+    maspace_to_tex(input).map_err(|x| x.to_string())
+}
+
+#[wasm_bindgen]
+pub fn maspace_to_unicode_wasm(input: &str) -> Result<String, String> {
+    maspace_to_unicode(input).map_err(|x| x.to_string())
+}
+
+#[wasm_bindgen]
+pub fn maspace_to_omml_wasm(input: &str) -> Result<String, String> {
+    maspace_to_omml(input).map_err(|x| x.to_string())
+}
+
+#[wasm_bindgen]
+pub fn asciimath_to_tex_wasm(input: &str) -> Result<String, String> {
+    asciimath_to_tex(input).map_err(|x| x.to_string())
+}
+
+#[wasm_bindgen]
+pub fn asciimath_to_maspace_wasm(input: &str) -> Result<String, String> {
+    asciimath_to_maspace(input).map_err(|x| x.to_string())
+}
+
+#[wasm_bindgen]
+pub fn tex_to_maspace_wasm(input: &str) -> Result<String, String> {
+    tex_to_maspace(input).map_err(|x| x.to_string())
+}
+
+#[wasm_bindgen]
+pub fn tex_to_maspace_spellings_wasm(tex: &str) -> Vec<String> {
+    tex_to_maspace_spellings(tex)
+}
+
+#[wasm_bindgen(getter_with_clone)]
+pub struct CompletionWasm {
+    pub text: String,
+    pub start: usize,
+    pub tex: String,
+}
+
+/// Same as [`maspace_completions`] but offsets count UTF-16 code units as in JavaScript.
+#[wasm_bindgen]
+pub fn maspace_completions_wasm(input: &str, cursor: usize) -> Vec<CompletionWasm> {
+    let cursor = byte_offset_of_utf16_offset(input, cursor);
+    maspace_completions(input, cursor)
+        .into_iter()
+        .map(|x| CompletionWasm {
+            text: x.text,
+            start: input[..x.start].encode_utf16().count(),
+            tex: x.tex,
+        })
+        .collect()
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default)]
+pub struct ConvertOptionsWasm {
+    pub syntax: Syntax,
+    /// Whether to fill `tokens` of the result.
+    pub tokens: bool,
+    /// Whether to fill `ast` of the result, which needs the `serde` feature.
+    pub ast: bool,
+    /// Whether to add the warnings of [`crate::lint::lint`].
+    pub lint: bool,
+    /// Whether to render input with parse errors anyway, with placeholders.
+    pub recover: bool,
+}
+
+#[wasm_bindgen]
+impl ConvertOptionsWasm {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// A token with its range in UTF-16 code units.
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone)]
+pub struct TokenWasm {
+    /// `cat`, `sub`, `sup`, `over`, `under`, `root`, `frac`, `op`, `open`, `close` or `symbol`.
+    pub kind: String,
+    /// TeX of `op`, `open`, `close` and `symbol`, empty otherwise.
+    pub tex: String,
+    /// Number of spaces the token binds with.
+    pub order: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A diagnostic with its range in UTF-16 code units.
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone)]
+pub struct DiagnosticWasm {
+    /// `error` or `warning`.
+    pub severity: String,
+    pub message: String,
+    pub start: usize,
+    pub end: usize,
+    /// The input with the suggested fix applied, if there is one.
+    pub fixed: Option<String>,
+}
+
+/// A rewritten input that parses, with its TeX.
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone)]
+pub struct FixWasm {
+    pub input: String,
+    pub tex: String,
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone)]
+pub struct ConversionWasm {
+    pub tex: Option<String>,
+    /// Messages of the warnings in `diagnostics`.
+    pub warnings: Vec<String>,
+    pub tokens: Vec<TokenWasm>,
+    /// The parsed formula as JSON.
+    pub ast: Option<String>,
+    pub diagnostics: Vec<DiagnosticWasm>,
+    pub fixes: Vec<FixWasm>,
+}
+
+/// Same as [`convert`] but offsets count UTF-16 code units as in JavaScript.
+#[wasm_bindgen]
+pub fn convert_wasm(input: &str, options: &ConvertOptionsWasm) -> ConversionWasm {
+    let conversion = convert(
+        input,
+        &ConvertOptions {
+            syntax: options.syntax,
+            lint: options.lint,
+            recover: options.recover,
+            ..Default::default()
+        },
+    );
+    let utf16 = |x: usize| input[..x].encode_utf16().count();
+    let tokens = match options.tokens {
+        true => conversion
+            .tokens
+            .iter()
+            .map(|(token, span)| {
+                let (kind, tex) = match token {
+                    Token::Cat(_) => ("cat", ""),
+                    Token::Sub(_) => ("sub", ""),
+                    Token::Sup(_) => ("sup", ""),
+                    Token::Over(_) => ("over", ""),
+                    Token::Under(_) => ("under", ""),
+                    Token::Root(_) => ("root", ""),
+                    Token::Frac(_) => ("frac", ""),
+                    Token::Op(tex, _) => ("op", tex.as_str()),
+                    Token::Open(tex) => ("open", tex.as_str()),
+                    Token::Close(tex) => ("close", tex.as_str()),
+                    Token::Symbol(tex) => ("symbol", tex.as_str()),
+                    Token::UnicodeSub(_) => ("unicode_sub", ""),
+                    Token::UnicodeSup(_) => ("unicode_sup", ""),
+                };
+                TokenWasm {
+                    kind: kind.to_string(),
+                    tex: tex.to_string(),
+                    order: token.order(),
+                    start: utf16(span.start),
+                    end: utf16(span.end),
+                }
+            })
+            .collect(),
+        false => vec![],
+    };
+    #[cfg(feature = "serde")]
+    let ast = match options.ast {
+        true => conversion
+            .math
+            .as_ref()
+            .and_then(|x| serde_json::to_string(x).ok()),
+        false => None,
+    };
+    #[cfg(not(feature = "serde"))]
+    let ast = None;
+    ConversionWasm {
+        warnings: conversion.warnings().map(|x| x.message.clone()).collect(),
+        diagnostics: conversion
+            .diagnostics
+            .iter()
+            .map(|x| DiagnosticWasm {
+                severity: match x.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                }
+                .to_string(),
+                message: x.message.clone(),
+                start: utf16(x.range.start),
+                end: utf16(x.range.end),
+                fixed: x.suggestion.as_ref().map(|y| y.apply(input)),
+            })
+            .collect(),
+        fixes: conversion
+            .fixes
+            .into_iter()
+            .map(|x| FixWasm {
+                input: x.input,
+                tex: x.tex,
+            })
+            .collect(),
+        tex: conversion.tex,
+        tokens,
+        ast,
+    }
+}
+
+/// A classified range with its range in UTF-16 code units.
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone)]
+pub struct HighlightWasm {
+    /// `symbol`, `string_literal`, `accent`, `op`, `open`, `close`, `unicode_sub`,
+    /// `unicode_sup`, or the kind of a binary operator as in [`TokenWasm`].
+    pub class: String,
+    /// Number of spaces a binary operator binds with, 0 otherwise.
+    pub order: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Same as [`highlight::highlight`] but offsets count UTF-16 code units as in JavaScript.
+#[wasm_bindgen]
+pub fn highlight_wasm(input: &str) -> Vec<HighlightWasm> {
+    use highlight::{BinOp, Class};
+    let utf16 = |x: usize| input[..x].encode_utf16().count();
+    highlight::highlight(input)
+        .into_iter()
+        .map(|x| {
+            let (class, order) = match x.class {
+                Class::Symbol => ("symbol", 0),
+                Class::StringLiteral => ("string_literal", 0),
+                Class::Accent => ("accent", 0),
+                Class::Op => ("op", 0),
+                Class::Open => ("open", 0),
+                Class::Close => ("close", 0),
+                Class::BinOp(BinOp::Cat, x) => ("cat", x),
+                Class::BinOp(BinOp::Sub, x) => ("sub", x),
+                Class::BinOp(BinOp::Sup, x) => ("sup", x),
+                Class::BinOp(BinOp::Over, x) => ("over", x),
+                Class::BinOp(BinOp::Under, x) => ("under", x),
+                Class::BinOp(BinOp::Root, x) => ("root", x),
+                Class::BinOp(BinOp::Frac, x) => ("frac", x),
+                Class::UnicodeSub => ("unicode_sub", 0),
+                Class::UnicodeSup => ("unicode_sup", 0),
+            };
+            HighlightWasm {
+                class: class.to_string(),
+                order,
+                start: utf16(x.range.start),
+                end: utf16(x.range.end),
+            }
+        })
+        .collect()
+}
+
+fn byte_offset_of_utf16_offset(s: &str, offset: usize) -> usize {
+    let mut utf16 = 0;
+    for (i, c) in s.char_indices() {
+        if utf16 >= offset {
+            return i;
+        }
+        utf16 += c.len_utf16();
+    }
+    s.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maspace_completions_wasm() {
+        let completions = maspace_completions_wasm("𝐚+<alp", 6);
+        assert_eq!(completions[0].text, "alpha");
+        assert_eq!(completions[0].start, 4);
+    }
+
+    #[test]
+    fn test_convert_wasm() {
+        let options = ConvertOptionsWasm {
+            tokens: true,
+            ..Default::default()
+        };
+        let x = convert_wasm("𝐚 ^b ^c", &options);
+        assert_eq!(x.tex, None);
+        assert_eq!(x.tokens[0].kind, "symbol");
+        assert_eq!((x.tokens[1].start, x.tokens[1].end), (2, 4));
+        assert_eq!(x.diagnostics[0].severity, "error");
+        assert_eq!((x.diagnostics[0].start, x.diagnostics[0].end), (5, 7));
+        assert_eq!(x.diagnostics[0].fixed.as_deref(), Some("𝐚  ^b ^c"));
+        assert_eq!(x.fixes[0].tex, r"\mathbf{a}^{b^{c}}");
+        assert_eq!(x.ast, None);
+    }
+
+    #[test]
+    fn test_highlight_wasm() {
+        let x = highlight_wasm("𝐚  /b");
+        assert_eq!(x[1].class, "frac");
+        assert_eq!((x[1].order, x[1].start, x[1].end), (2, 2, 5));
+    }
+}
@@ -0,0 +1,3 @@
+//! The cdylib built by `wasm-pack`, exporting the bindings in `maspace::wasm`.
+
+pub use maspace::wasm::*;